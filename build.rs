@@ -32,8 +32,10 @@ fn compile_protofiles(
 ) -> Result<(), Box<dyn Error>> {
     let proto_input_filepath = Path::new(proto_input_directory).join(proto_input_file);
 
+    // the server is generated only for the stand-in MeeSign server used by tests
     tonic_build::configure()
-        .build_server(false)
+        .build_server(true)
+        .server_mod_attribute("meesign", "#[cfg(test)]")
         .compile(&[proto_input_filepath], &[proto_input_directory])?;
     Ok(())
 }
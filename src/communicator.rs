@@ -1,13 +1,27 @@
+use std::sync::{Arc, Mutex};
+
 use tonic::async_trait;
 
-use self::{communicator_error::CommunicatorError, group::Group};
+use self::{
+    communicator_error::CommunicatorError,
+    group::{Group, GroupKeyType, GroupParameters},
+    request_context::RequestContext,
+    server_info::ServerInfo,
+};
 
 pub(crate) mod communicator_error;
+pub(crate) mod device_identity;
 pub(crate) mod group;
 pub(crate) mod meesign;
 #[cfg(all(feature = "mocked_communicator", debug_assertions))]
 pub(crate) mod mocked_communicator;
+pub(crate) mod payload_summary;
+pub(crate) mod request_context;
+pub(crate) mod request_kind;
+pub(crate) mod retry_policy;
+pub(crate) mod server_info;
 pub(crate) mod task_name_provider;
+pub(crate) mod task_name_template;
 
 type ByteVector = Vec<u8>;
 pub(crate) type AuthResponse = ByteVector;
@@ -15,10 +29,22 @@ pub(crate) type GroupId = ByteVector;
 pub(crate) type TaskId = ByteVector;
 pub(crate) type RequestData = ByteVector;
 
+/// Index of the communicator in the list of configured communicators
+pub(crate) type CommunicatorId = usize;
+pub(crate) type CommunicatorStore = Arc<Mutex<Box<dyn Communicator>>>;
+
 /// Communicates with a remote communicator, e.g., MeeSign server
 // TODO: remove macro once rust 1.74 is released
 #[async_trait]
 pub(crate) trait Communicator: Send + Sync {
+    /// Returns information about the remote communicator,
+    /// queried when the connection was established
+    fn get_server_info(&self) -> &ServerInfo;
+
+    /// Returns the address of the remote communicator, identifying the server
+    /// the tasks are created on
+    fn get_server_address(&self) -> String;
+
     /// Returns a list of groups available for authentication
     async fn get_groups(&mut self) -> Result<Vec<Group>, CommunicatorError>;
 
@@ -27,14 +53,57 @@ pub(crate) trait Communicator: Send + Sync {
     /// # Arguments
     ///
     /// * `group_id` - the id of the group that will perform the authentication
+    /// * `key_type` - the key type of the group, determines the kind of the request
     /// * `data` - the data to be sent to the remote communicator, usually a challenge
-    /// * `request_originator` - the originator of the request,
-    ///     usually the website domain name
+    /// * `request_context` - describes the request to the approvers,
+    ///     e.g., the originator of the request
     async fn send_auth_request(
         &mut self,
         group_id: GroupId,
+        key_type: GroupKeyType,
         data: RequestData,
-        request_originator: Option<String>,
+        request_context: RequestContext,
+    ) -> Result<TaskId, CommunicatorError>;
+
+    /// Sends a decryption request to the remote communicator,
+    /// the plaintext is returned as the task's response
+    ///
+    /// # Arguments
+    ///
+    /// * `group_id` - the id of the decryption group whose public key the data were encrypted for
+    /// * `data` - the ciphertext
+    /// * `request_context` - describes the request to the approvers
+    async fn send_decryption_request(
+        &mut self,
+        group_id: GroupId,
+        data: RequestData,
+        request_context: RequestContext,
+    ) -> Result<TaskId, CommunicatorError>;
+
+    /// Sends a key agreement request to the remote communicator, the x coordinate
+    /// of the shared point is returned as the task's response
+    ///
+    /// # Arguments
+    ///
+    /// * `group_id` - the id of the decryption group whose private key is used
+    /// * `public_key` - the peer's uncompressed public key
+    /// * `request_context` - describes the request to the approvers
+    async fn send_key_agreement_request(
+        &mut self,
+        group_id: GroupId,
+        public_key: RequestData,
+        request_context: RequestContext,
+    ) -> Result<TaskId, CommunicatorError>;
+
+    /// Asks the remote communicator to create a group using distributed key generation,
+    /// the group's identifier, i.e., its public key, is returned as the task's response
+    ///
+    /// # Arguments
+    ///
+    /// * `parameters` - describes the group to be created
+    async fn send_group_request(
+        &mut self,
+        parameters: GroupParameters,
     ) -> Result<TaskId, CommunicatorError>;
 
     /// Returns the authentication response from the remote communicator
@@ -47,4 +116,18 @@ pub(crate) trait Communicator: Send + Sync {
         &mut self,
         task_id: TaskId,
     ) -> Result<Option<AuthResponse>, CommunicatorError>;
+
+    /// Tells the remote communicator that the requester is no longer
+    /// interested in the result of the task. Communicators whose protocol
+    /// offers no way to withdraw a task only stop waiting for it locally,
+    /// the task stays pending on the remote side.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_id` - the id of the canceled task
+    async fn cancel_task(&mut self, task_id: TaskId) -> Result<(), CommunicatorError>;
+
+    /// Returns a communicator sharing the connection of this one, so that waiting
+    /// for a task doesn't keep the other calls from using the communicator
+    fn clone_box(&self) -> Box<dyn Communicator>;
 }
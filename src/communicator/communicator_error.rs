@@ -1,5 +1,5 @@
 use thiserror::Error;
-use tonic::codegen::http::uri::InvalidUri;
+use tonic::{codegen::http::uri::InvalidUri, Code, Status};
 
 type WaitingTimeSeconds = u64;
 
@@ -11,13 +11,66 @@ pub(crate) enum CommunicatorError {
     InvalidStatus(#[from] tonic::Status),
     #[error("Invalid configuration")]
     InvalidConfiguration(#[from] InvalidUri),
+    #[error("Request data are not valid for the group")]
+    InvalidRequestData,
     #[error("Task failed remotely")]
     TaskFailed,
     #[error("Task timed out after {0} seconds")]
     TaskTimedOut(WaitingTimeSeconds),
     #[error("I/O error occurred: {0}")]
     Io(#[from] std::io::Error),
+    #[error("Server is not supported: {0}")]
+    UnsupportedServer(String),
+    #[error("Device identity setup failed: {0}")]
+    DeviceIdentity(openssl::error::ErrorStack),
     #[cfg(feature = "mocked_communicator")]
     #[error("Cryptographic operation failed")]
     CryptographicError(#[from] p256::ecdsa::Error),
 }
+
+impl CommunicatorError {
+    /// Returns whether the error is caused by the network, rather than by the server
+    /// refusing the request. Such requests can be retried over a new connection.
+    pub(crate) fn is_network_failure(&self) -> bool {
+        match self {
+            Self::Transport(_) => true,
+            Self::InvalidStatus(status) => is_network_failure_status(status),
+            _ => false,
+        }
+    }
+}
+
+/// Returns whether the status was produced by the transport, e.g., the connection
+/// couldn't be established, was closed or the request timed out. Other statuses
+/// the server responded with, e.g., `Unknown`, are refused requests.
+pub(crate) fn is_network_failure_status(status: &Status) -> bool {
+    status.code() == Code::Unavailable || is_transport_status(status)
+}
+
+/// The statuses created from transport errors keep the error as their source,
+/// the statuses received from the server have none
+fn is_transport_status(status: &Status) -> bool {
+    std::error::Error::source(status).is_some()
+}
+
+#[cfg(test)]
+mod test {
+    use std::{io, sync::Arc};
+
+    use super::*;
+
+    #[test]
+    fn given_status_from_the_server_only_unavailable_is_a_network_failure() {
+        assert!(is_network_failure_status(&Status::unavailable("")));
+        assert!(!is_network_failure_status(&Status::unknown("")));
+        assert!(!is_network_failure_status(&Status::cancelled("")));
+    }
+
+    #[test]
+    fn given_status_from_a_transport_error_it_is_a_network_failure() {
+        let mut status = Status::cancelled("Timeout expired");
+        status.set_source(Arc::new(io::Error::from(io::ErrorKind::TimedOut)));
+
+        assert!(is_network_failure_status(&status));
+    }
+}
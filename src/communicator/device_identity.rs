@@ -0,0 +1,256 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+};
+
+use openssl::{
+    ec::{EcGroup, EcKey},
+    error::ErrorStack,
+    hash::MessageDigest,
+    nid::Nid,
+    pkey::{PKey, Private},
+    x509::{X509NameBuilder, X509Req, X509},
+};
+use tonic::transport::Identity;
+
+use super::communicator_error::CommunicatorError;
+
+pub(crate) type DeviceId = Vec<u8>;
+
+static DEVICE_ID_FILE: &str = "device_id";
+static CERTIFICATE_FILE: &str = "certificate.pem";
+static PRIVATE_KEY_FILE: &str = "private_key.pem";
+static DEVICE_NAME_PREFIX: &str = "cryptoki-bridge";
+
+/// Identity of the bridge registered as a MeeSign device,
+/// presented to the server as a TLS client identity
+pub(crate) struct DeviceIdentity {
+    /// Identifier assigned to the device by the server
+    device_id: DeviceId,
+
+    /// PEM-encoded device certificate issued by the server
+    certificate: Vec<u8>,
+
+    /// PEM-encoded private key of the device
+    private_key: Vec<u8>,
+}
+
+impl DeviceIdentity {
+    /// Loads an identity stored in the directory, if there is any
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` - the directory the identity was stored into
+    pub(crate) fn load(directory: &Path) -> Result<Option<Self>, CommunicatorError> {
+        let device_id_path = directory.join(DEVICE_ID_FILE);
+        if !device_id_path.exists() {
+            return Ok(None);
+        }
+        let device_id = fs::read(device_id_path)?;
+        let certificate = fs::read(directory.join(CERTIFICATE_FILE))?;
+        let private_key = fs::read(directory.join(PRIVATE_KEY_FILE))?;
+        Ok(Some(Self {
+            device_id,
+            certificate,
+            private_key,
+        }))
+    }
+
+    /// Stores the identity, the private key is readable only by the current user
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` - the directory to store the identity into
+    pub(crate) fn store(&self, directory: &Path) -> Result<(), CommunicatorError> {
+        fs::create_dir_all(directory)?;
+        write_private_file(&directory.join(PRIVATE_KEY_FILE), &self.private_key)?;
+        fs::write(directory.join(CERTIFICATE_FILE), &self.certificate)?;
+        // the device id is written last, as its presence marks a complete identity
+        fs::write(directory.join(DEVICE_ID_FILE), &self.device_id)?;
+        Ok(())
+    }
+
+    pub(crate) fn get_device_id(&self) -> &DeviceId {
+        &self.device_id
+    }
+
+    pub(crate) fn get_tls_identity(&self) -> Identity {
+        Identity::from_pem(&self.certificate, &self.private_key)
+    }
+}
+
+/// A pending registration of a new device
+pub(crate) struct DeviceRegistration {
+    /// Freshly generated P-256 key of the device
+    private_key: PKey<Private>,
+}
+
+impl DeviceRegistration {
+    pub(crate) fn new() -> Result<Self, CommunicatorError> {
+        let private_key = generate_device_key().map_err(CommunicatorError::DeviceIdentity)?;
+        Ok(Self { private_key })
+    }
+
+    /// Returns a DER-encoded certificate signing request for the device key
+    ///
+    /// # Arguments
+    ///
+    /// * `device_name` - the name the device is registered under
+    pub(crate) fn get_csr(&self, device_name: &str) -> Result<Vec<u8>, CommunicatorError> {
+        build_csr(&self.private_key, device_name).map_err(CommunicatorError::DeviceIdentity)
+    }
+
+    /// Finishes the registration using the server's response
+    ///
+    /// # Arguments
+    ///
+    /// * `device_id` - the identifier assigned to the device
+    /// * `certificate` - the DER-encoded device certificate
+    pub(crate) fn into_identity(
+        self,
+        device_id: DeviceId,
+        certificate: &[u8],
+    ) -> Result<DeviceIdentity, CommunicatorError> {
+        let certificate = X509::from_der(certificate)
+            .and_then(|certificate| certificate.to_pem())
+            .map_err(CommunicatorError::DeviceIdentity)?;
+        let private_key = self
+            .private_key
+            .private_key_to_pem_pkcs8()
+            .map_err(CommunicatorError::DeviceIdentity)?;
+        Ok(DeviceIdentity {
+            device_id,
+            certificate,
+            private_key,
+        })
+    }
+}
+
+/// Generates a P-256 key for the device
+fn generate_device_key() -> Result<PKey<Private>, ErrorStack> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    PKey::from_ec_key(EcKey::generate(&group)?)
+}
+
+/// Builds a DER-encoded certificate signing request for the device key
+///
+/// # Arguments
+///
+/// * `private_key` - the key of the device
+/// * `device_name` - the name the device is registered under
+fn build_csr(private_key: &PKey<Private>, device_name: &str) -> Result<Vec<u8>, ErrorStack> {
+    let mut name = X509NameBuilder::new()?;
+    name.append_entry_by_nid(Nid::COMMONNAME, device_name)?;
+    let name = name.build();
+
+    let mut request = X509Req::builder()?;
+    request.set_subject_name(&name)?;
+    request.set_pubkey(private_key)?;
+    request.sign(private_key, MessageDigest::sha256())?;
+    request.build().to_der()
+}
+
+/// Returns the directory holding the device identity for the server
+///
+/// # Arguments
+///
+/// * `cryptoki_directory` - the bridge's data directory
+/// * `hostname` - the hostname of the server
+/// * `port` - the port of the server
+pub(crate) fn get_identity_directory(
+    cryptoki_directory: &Path,
+    hostname: &str,
+    port: u16,
+) -> PathBuf {
+    static IDENTITIES_DIRECTORY_NAME: &str = "identities";
+    cryptoki_directory
+        .join(IDENTITIES_DIRECTORY_NAME)
+        .join(format!("{hostname}_{port}"))
+}
+
+/// Returns the name the device is registered under,
+/// so that users can tell which machine the device belongs to
+pub(crate) fn get_device_name() -> String {
+    let mut buffer = [0u8; 256];
+    let result =
+        unsafe { libc::gethostname(buffer.as_mut_ptr() as *mut libc::c_char, buffer.len()) };
+    if result != 0 {
+        return DEVICE_NAME_PREFIX.into();
+    }
+    let length = buffer
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(buffer.len());
+    let hostname = String::from_utf8_lossy(&buffer[..length]);
+    format!("{DEVICE_NAME_PREFIX}@{hostname}")
+}
+
+fn write_private_file(path: &Path, content: &[u8]) -> Result<(), CommunicatorError> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(content)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use openssl::{asn1::Asn1Time, x509::X509Builder};
+
+    use super::*;
+
+    /// Issues a certificate for the CSR, as the server would on registration
+    fn issue_certificate(csr: &[u8]) -> Vec<u8> {
+        let request = X509Req::from_der(csr).unwrap();
+        let issuer_key = DeviceRegistration::new().unwrap().private_key;
+        let mut certificate = X509Builder::new().unwrap();
+        certificate
+            .set_subject_name(request.subject_name())
+            .unwrap();
+        certificate
+            .set_pubkey(&request.public_key().unwrap())
+            .unwrap();
+        certificate
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        certificate
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        certificate
+            .sign(&issuer_key, MessageDigest::sha256())
+            .unwrap();
+        certificate.build().to_der().unwrap()
+    }
+
+    #[test]
+    fn given_registration_csr_is_signed_by_device_key() {
+        let registration = DeviceRegistration::new().unwrap();
+        let csr = registration.get_csr("test-device").unwrap();
+        let request = X509Req::from_der(&csr).unwrap();
+        assert!(request.verify(&registration.private_key).unwrap());
+    }
+
+    #[test]
+    fn given_stored_identity_load_returns_it() {
+        let directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        assert!(DeviceIdentity::load(&directory).unwrap().is_none());
+
+        let registration = DeviceRegistration::new().unwrap();
+        let csr = registration.get_csr("test-device").unwrap();
+        let identity = registration
+            .into_identity(vec![1, 2, 3], &issue_certificate(&csr))
+            .unwrap();
+        identity.store(&directory).unwrap();
+
+        let loaded = DeviceIdentity::load(&directory).unwrap().unwrap();
+        assert_eq!(loaded.get_device_id(), identity.get_device_id());
+        assert_eq!(loaded.certificate, identity.certificate);
+        assert_eq!(loaded.private_key, identity.private_key);
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
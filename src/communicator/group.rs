@@ -1,4 +1,174 @@
-use super::GroupId;
+use crate::cryptoki::bindings::{CKK_ECDSA, CKK_EC_EDWARDS, CK_KEY_TYPE, CK_ULONG};
+
+use super::{device_identity::DeviceId, GroupId};
+
+const NIST_P256_EC_PARAMS_DER_HEX: &str = "06082a8648ce3d030107";
+const SECP256K1_EC_PARAMS_DER_HEX: &str = "06052b8104000a";
+const ED25519_EC_PARAMS_DER_HEX: &str = "06032b6570";
+/// Ed25519 named by the `edwards25519` printable string, used by older PKCS#11 tooling
+const ED25519_EC_PARAMS_PRINTABLE_STRING_DER_HEX: &str = "130c656477617264733235353139";
+
+/// Purpose of the group's key, determines which requests the group can handle
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum GroupKeyType {
+    /// Signs authentication challenges
+    SignChallenge,
+    /// Signs PDF documents
+    SignPdf,
+    /// Decrypts data encrypted for the group's public key
+    Decrypt,
+}
+
+impl GroupKeyType {
+    /// Encodes the key type as a `CK_ULONG` attribute value
+    pub(crate) fn to_attribute_value(self) -> Vec<u8> {
+        let value: CK_ULONG = match self {
+            Self::SignChallenge => 0,
+            Self::SignPdf => 1,
+            Self::Decrypt => 2,
+        };
+        value.to_le_bytes().to_vec()
+    }
+
+    /// Decodes the key type from a `CK_ULONG` attribute value
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - the attribute value
+    pub(crate) fn from_attribute_value(value: &[u8]) -> Option<Self> {
+        let value = CK_ULONG::from_le_bytes(value.try_into().ok()?);
+        match value {
+            0 => Some(Self::SignChallenge),
+            1 => Some(Self::SignPdf),
+            2 => Some(Self::Decrypt),
+            _ => None,
+        }
+    }
+}
+
+/// Curve of the group's key, determines the key objects exposed for the group
+/// and the signature mechanisms it accepts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum GroupCurve {
+    /// NIST P-256, the group's public key is an uncompressed SEC1 point
+    P256,
+    /// secp256k1, the group's public key is an uncompressed SEC1 point
+    Secp256k1,
+    /// Ed25519, the group's public key is the 32-byte encoded point
+    Ed25519,
+}
+
+impl GroupCurve {
+    /// Returns the `CKA_KEY_TYPE` of the group's keys
+    pub(crate) fn get_key_type(self) -> CK_KEY_TYPE {
+        match self {
+            Self::P256 | Self::Secp256k1 => CKK_ECDSA as CK_KEY_TYPE,
+            Self::Ed25519 => CKK_EC_EDWARDS as CK_KEY_TYPE,
+        }
+    }
+
+    /// Returns the DER-encoded curve OID, used as `CKA_EC_PARAMS`
+    pub(crate) fn get_ec_params(self) -> Vec<u8> {
+        let ec_params = match self {
+            Self::P256 => NIST_P256_EC_PARAMS_DER_HEX,
+            Self::Secp256k1 => SECP256K1_EC_PARAMS_DER_HEX,
+            Self::Ed25519 => ED25519_EC_PARAMS_DER_HEX,
+        };
+        hex::decode(ec_params).unwrap()
+    }
+
+    /// Decodes the curve from `CKA_EC_PARAMS`, returns `None` for unsupported curves
+    ///
+    /// # Arguments
+    ///
+    /// * `ec_params` - the DER-encoded curve OID or name
+    pub(crate) fn from_ec_params(ec_params: &[u8]) -> Option<Self> {
+        let ec_params = hex::encode(ec_params);
+        match ec_params.as_str() {
+            NIST_P256_EC_PARAMS_DER_HEX => Some(Self::P256),
+            SECP256K1_EC_PARAMS_DER_HEX => Some(Self::Secp256k1),
+            ED25519_EC_PARAMS_DER_HEX | ED25519_EC_PARAMS_PRINTABLE_STRING_DER_HEX => {
+                Some(Self::Ed25519)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Threshold protocol the group's members run to generate and use the key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum GroupProtocol {
+    /// Threshold ECDSA
+    Gg18,
+    /// Threshold ElGamal decryption, also used for ECDH
+    ElGamal,
+    /// Threshold Schnorr signatures, i.e., EdDSA
+    Frost,
+}
+
+impl GroupProtocol {
+    /// Returns the protocol generating and using keys of the given type and curve
+    ///
+    /// # Arguments
+    ///
+    /// * `key_type` - the purpose of the key
+    /// * `curve` - the curve of the key
+    pub(crate) fn for_key(key_type: GroupKeyType, curve: GroupCurve) -> Self {
+        match (key_type, curve) {
+            (GroupKeyType::Decrypt, _) => Self::ElGamal,
+            (_, GroupCurve::Ed25519) => Self::Frost,
+            _ => Self::Gg18,
+        }
+    }
+
+    pub(crate) fn get_name(self) -> &'static str {
+        match self {
+            Self::Gg18 => "GG18",
+            Self::ElGamal => "ElGamal",
+            Self::Frost => "FROST",
+        }
+    }
+}
+
+/// A device holding a share of the group's key
+///
+/// # Arguments
+///
+/// * `device_id` - ID of the device
+/// * `name` - Name of the device, if known
+/// * `certificate` - DER-encoded certificate of the device, if known
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct GroupDevice {
+    device_id: DeviceId,
+    name: Option<String>,
+    certificate: Option<Vec<u8>>,
+}
+
+impl GroupDevice {
+    pub(crate) fn new(
+        device_id: DeviceId,
+        name: Option<String>,
+        certificate: Option<Vec<u8>>,
+    ) -> Self {
+        Self {
+            device_id,
+            name,
+            certificate,
+        }
+    }
+
+    pub(crate) fn get_device_id(&self) -> &DeviceId {
+        &self.device_id
+    }
+
+    pub(crate) fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub(crate) fn get_certificate(&self) -> Option<&[u8]> {
+        self.certificate.as_deref()
+    }
+}
 
 /// Represents a single communicator group
 ///
@@ -6,15 +176,47 @@ use super::GroupId;
 ///
 /// * `group_id` - Group ID, which is also its public key
 /// * `name` - Name of the group
+/// * `key_type` - Purpose of the group's key
+/// * `curve` - Curve of the group's key
+/// * `threshold` - Number of devices required to use the key
+/// * `devices` - Devices holding the shares of the key, empty if unknown
 #[derive(Clone)]
 pub(crate) struct Group {
     group_id: GroupId,
     name: String,
+    key_type: GroupKeyType,
+    curve: GroupCurve,
+    threshold: u32,
+    devices: Vec<GroupDevice>,
 }
 
 impl Group {
-    pub(crate) fn new(group_id: GroupId, name: String) -> Self {
-        Self { group_id, name }
+    pub(crate) fn new(
+        group_id: GroupId,
+        name: String,
+        key_type: GroupKeyType,
+        curve: GroupCurve,
+    ) -> Self {
+        Self {
+            group_id,
+            name,
+            key_type,
+            curve,
+            threshold: 1,
+            devices: vec![],
+        }
+    }
+
+    /// Sets the quorum protecting the group's key
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - the number of devices required to use the key
+    /// * `devices` - the devices holding the shares of the key
+    pub(crate) fn with_members(mut self, threshold: u32, devices: Vec<GroupDevice>) -> Self {
+        self.threshold = threshold;
+        self.devices = devices;
+        self
     }
 
     pub(crate) fn get_group_id(&self) -> &GroupId {
@@ -24,4 +226,132 @@ impl Group {
     pub(crate) fn get_name(&self) -> &str {
         &self.name
     }
+
+    pub(crate) fn get_key_type(&self) -> GroupKeyType {
+        self.key_type
+    }
+
+    pub(crate) fn get_curve(&self) -> GroupCurve {
+        self.curve
+    }
+
+    pub(crate) fn get_protocol(&self) -> GroupProtocol {
+        GroupProtocol::for_key(self.key_type, self.curve)
+    }
+
+    pub(crate) fn get_threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    pub(crate) fn get_devices(&self) -> &[GroupDevice] {
+        &self.devices
+    }
+}
+
+/// Describes a group to be created by distributed key generation
+///
+/// # Arguments
+///
+/// * `name` - Name of the group
+/// * `device_ids` - Devices holding the shares of the group's key
+/// * `threshold` - Number of devices required to use the key
+/// * `key_type` - Purpose of the group's key
+/// * `curve` - Curve of the group's key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct GroupParameters {
+    name: String,
+    device_ids: Vec<DeviceId>,
+    threshold: u32,
+    key_type: GroupKeyType,
+    curve: GroupCurve,
+}
+
+impl GroupParameters {
+    pub(crate) fn new(
+        name: String,
+        device_ids: Vec<DeviceId>,
+        threshold: u32,
+        key_type: GroupKeyType,
+        curve: GroupCurve,
+    ) -> Self {
+        Self {
+            name,
+            device_ids,
+            threshold,
+            key_type,
+            curve,
+        }
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn get_device_ids(&self) -> &[DeviceId] {
+        &self.device_ids
+    }
+
+    pub(crate) fn get_threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    pub(crate) fn get_key_type(&self) -> GroupKeyType {
+        self.key_type
+    }
+
+    pub(crate) fn get_curve(&self) -> GroupCurve {
+        self.curve
+    }
+
+    /// Returns the group created from the parameters
+    ///
+    /// # Arguments
+    ///
+    /// * `group_id` - the public key resulting from the key generation
+    pub(crate) fn into_group(self, group_id: GroupId) -> Group {
+        let devices = self
+            .device_ids
+            .into_iter()
+            .map(|device_id| GroupDevice::new(device_id, None, None))
+            .collect();
+        Group::new(group_id, self.name, self.key_type, self.curve)
+            .with_members(self.threshold, devices)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn given_key_type_attribute_value_decodes_to_the_same_key_type() {
+        for key_type in [
+            GroupKeyType::SignChallenge,
+            GroupKeyType::SignPdf,
+            GroupKeyType::Decrypt,
+        ] {
+            let value = key_type.to_attribute_value();
+            assert_eq!(GroupKeyType::from_attribute_value(&value), Some(key_type));
+        }
+        assert_eq!(GroupKeyType::from_attribute_value(&[1, 2]), None);
+    }
+
+    #[test]
+    fn given_curve_ec_params_decode_to_the_same_curve() {
+        for curve in [GroupCurve::P256, GroupCurve::Secp256k1, GroupCurve::Ed25519] {
+            assert_eq!(
+                GroupCurve::from_ec_params(&curve.get_ec_params()),
+                Some(curve)
+            );
+        }
+        assert_eq!(
+            GroupCurve::from_ec_params(b"\x13\x0cedwards25519"),
+            Some(GroupCurve::Ed25519)
+        );
+        // P-384
+        assert_eq!(
+            GroupCurve::from_ec_params(&hex::decode("06052b81040022").unwrap()),
+            None
+        );
+    }
 }
@@ -1,80 +1,530 @@
 mod proto {
     tonic::include_proto!("meesign");
 }
+mod log_sink;
+#[cfg(test)]
+pub(crate) mod stand_in;
 
+use prost::Message;
 use tokio::time;
 use tonic::{
     async_trait,
     transport::{Certificate, Channel, ClientTlsConfig, Uri},
+    Code, Status, Streaming,
 };
 
-use std::{str::FromStr, time::Duration};
+use std::{collections::HashMap, future::Future, path::Path, str::FromStr, time::Duration};
 
-use crate::communicator::meesign::proto::{mpc_client::MpcClient, GroupsRequest, KeyType};
+use crate::communicator::meesign::proto::{
+    mpc_client::MpcClient, Device, DevicesRequest, GroupRequest, GroupsRequest, KeyType,
+};
 use crate::communicator::AuthResponse;
+use crate::configuration::CommunicatorEndpoint;
+use crate::diagnostics;
 
-use self::proto::{task::TaskState, SignRequest, TaskRequest};
+pub(crate) use self::log_sink::MeesignLogSink;
+use self::proto::{
+    task::TaskState, Curve, DecryptRequest, DeriveKeyRequest, ProtocolType, RegistrationRequest,
+    ServerInfoRequest, SignRequest, SubscribeRequest, Task, TaskRequest, TaskType, TasksRequest,
+};
 use super::{
-    communicator_error::CommunicatorError, group::Group, task_name_provider::TaskNameProvider,
+    communicator_error::{is_network_failure_status, CommunicatorError},
+    device_identity::{
+        get_device_name, get_identity_directory, DeviceId, DeviceIdentity, DeviceRegistration,
+    },
+    group::Group,
+    group::{GroupCurve, GroupDevice, GroupKeyType, GroupParameters, GroupProtocol},
+    request_context::RequestContext,
+    request_kind::RequestKind,
+    retry_policy::RetryPolicy,
+    server_info::ServerInfo,
+    task_name_provider::TaskNameProvider,
     Communicator, GroupId, RequestData, TaskId,
 };
 
-static MAX_ATTEMPT_COUNT: usize = 60 * 2 / ATTEMPT_SLEEP_SEC as usize;
 static ATTEMPT_SLEEP_SEC: u64 = 3;
+static PDF_HEADER: &[u8] = b"%PDF-";
+/// The bridge doesn't interpret the ciphertext, the plaintext is returned as is
+static DECRYPTION_DATA_TYPE: &str = "application/octet-stream";
 
 /// Communicates with the MeeSign server
+#[derive(Clone)]
 pub(crate) struct Meesign {
-    client: MpcClient<Channel>,
+    /// Client of the current connection, `None` after a network failure
+    /// until the connection is re-established by the next call
+    client: Option<MpcClient<Channel>>,
+
+    /// The connection settings of the server
+    endpoint: CommunicatorEndpoint,
+
+    /// The TLS configuration, including the device identity
+    client_tls_config: ClientTlsConfig,
+
+    /// Identifier of the bridge's device registered with the server
+    device_id: DeviceId,
+
+    /// Determines how RPCs interrupted by network failures are retried
+    retry_policy: RetryPolicy,
+
+    /// Information about the server, queried on connect
+    server_info: ServerInfo,
+
+    /// Names the tasks shown to the approvers
+    task_name_provider: TaskNameProvider,
 }
 
 impl Meesign {
+    /// Connects to the server, presenting the device identity stored
+    /// in the cryptoki directory.
+    /// The bridge is registered as a new device first if there is no identity
+    /// for the server yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - the connection settings of the server
+    /// * `certificate` - the CA certificate the server certificate is verified against
+    /// * `cryptoki_directory` - the bridge's data directory
     pub async fn new(
-        hostname: String,
-        port: u32,
+        endpoint: &CommunicatorEndpoint,
         certificate: Certificate,
+        cryptoki_directory: &Path,
     ) -> Result<Self, CommunicatorError> {
-        let server_uri = Uri::from_str(&format!("https://{}:{}", &hostname, port))?;
+        let identity_directory = get_identity_directory(
+            cryptoki_directory,
+            endpoint.get_hostname(),
+            endpoint.get_port(),
+        );
         let client_tls_config = ClientTlsConfig::new()
-            .domain_name(hostname)
+            .domain_name(endpoint.get_tls_server_name())
             .ca_certificate(certificate);
-        let channel = Channel::builder(server_uri)
-            .tls_config(client_tls_config)?
-            .connect()
-            .await?;
+
+        let channel = connect(endpoint, client_tls_config.clone()).await?;
+        let mut anonymous_client = MpcClient::new(channel);
+        let server_info = get_server_info(&mut anonymous_client).await?;
+
+        let identity = match DeviceIdentity::load(&identity_directory)? {
+            Some(identity) => identity,
+            None => {
+                let identity = register(&mut anonymous_client, &get_device_name()).await?;
+                identity.store(&identity_directory)?;
+                identity
+            }
+        };
+
+        let client_tls_config = client_tls_config.identity(identity.get_tls_identity());
+        let channel = connect(endpoint, client_tls_config.clone()).await?;
+        let client = MpcClient::new(channel);
+        Ok(Self {
+            client: Some(client),
+            endpoint: endpoint.clone(),
+            client_tls_config,
+            device_id: identity.get_device_id().clone(),
+            retry_policy: endpoint.get_retry_policy(),
+            server_info,
+            task_name_provider: TaskNameProvider::new(),
+        })
+    }
+
+    pub(crate) fn with_task_name_provider(mut self, task_name_provider: TaskNameProvider) -> Self {
+        self.task_name_provider = task_name_provider;
+        self
+    }
+
+    /// Returns a sink forwarding diagnostics to the server's log endpoint
+    /// over a connection of its own
+    pub(crate) fn get_log_sink(&self) -> MeesignLogSink {
+        MeesignLogSink::new(self.endpoint.clone(), self.client_tls_config.clone())
+    }
+
+    /// Returns a client of the current connection,
+    /// reconnecting first if the previous connection failed
+    async fn get_client(&mut self) -> Result<MpcClient<Channel>, CommunicatorError> {
+        if let Some(client) = &self.client {
+            return Ok(client.clone());
+        }
+        let channel = connect(&self.endpoint, self.client_tls_config.clone()).await?;
         let client = MpcClient::new(channel);
-        Ok(Self { client })
+        self.client = Some(client.clone());
+        Ok(client)
+    }
+
+    /// Returns a client, retrying with backoff if the connection can't be established.
+    /// No request is sent before the connection is established,
+    /// so this is safe even for non-idempotent calls.
+    async fn get_client_with_retries(&mut self) -> Result<MpcClient<Channel>, CommunicatorError> {
+        let mut attempt = 0;
+        loop {
+            match self.get_client().await {
+                Err(err) if err.is_network_failure() && self.retry_policy.allows_retry(attempt) => {
+                    time::sleep(self.retry_policy.get_backoff(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Makes an idempotent call. After a network failure, the connection
+    /// is re-established and the call is retried with backoff.
+    ///
+    /// # Arguments
+    ///
+    /// * `call` - makes the call using the provided client
+    async fn call_idempotent<T, F, R>(&mut self, mut call: F) -> Result<T, CommunicatorError>
+    where
+        F: FnMut(MpcClient<Channel>) -> R,
+        R: Future<Output = Result<tonic::Response<T>, Status>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let result = match self.get_client().await {
+                Ok(client) => call(client)
+                    .await
+                    .map(tonic::Response::into_inner)
+                    .map_err(CommunicatorError::from),
+                Err(err) => Err(err),
+            };
+            match result {
+                Err(err) if err.is_network_failure() => {
+                    self.client = None;
+                    if !self.retry_policy.allows_retry(attempt) {
+                        return Err(err);
+                    }
+                    time::sleep(self.retry_policy.get_backoff(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn get_task(&mut self, task_id: TaskId) -> Result<Task, CommunicatorError> {
+        let device_id = self.device_id.clone();
+        self.call_idempotent(|mut client| {
+            let request = tonic::Request::new(TaskRequest {
+                task_id: task_id.clone(),
+                device_id: Some(device_id.clone()),
+            });
+            async move { client.get_task(request).await }
+        })
+        .await
+    }
+
+    /// Waits for the task to be resolved by periodically querying its state
+    ///
+    /// # Arguments
+    ///
+    /// * `task_id` - the id of the task to wait for
+    async fn poll_task_result(
+        &mut self,
+        task_id: TaskId,
+    ) -> Result<Option<AuthResponse>, CommunicatorError> {
+        loop {
+            let task = self.get_task(task_id.clone()).await?;
+            if let Some(result) = get_task_result(task) {
+                return result;
+            }
+            time::sleep(Duration::from_secs(ATTEMPT_SLEEP_SEC)).await;
+        }
+    }
+
+    /// Waits for the task to be resolved using the server's update stream.
+    /// Falls back to polling if the stream is closed prematurely.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_id` - the id of the task to wait for
+    /// * `updates` - a stream of task updates, subscribed to before the call
+    async fn wait_for_task_update(
+        &mut self,
+        task_id: TaskId,
+        mut updates: Streaming<Task>,
+    ) -> Result<Option<AuthResponse>, CommunicatorError> {
+        // the task could have been resolved before we subscribed
+        let task = self.get_task(task_id.clone()).await?;
+        if let Some(result) = get_task_result(task) {
+            return result;
+        }
+
+        loop {
+            let task = match updates.message().await {
+                Ok(Some(task)) => task,
+                Ok(None) => break,
+                Err(status) if is_streaming_unavailable(&status) => break,
+                // polling re-establishes the connection
+                Err(status) if is_network_failure_status(&status) => break,
+                Err(status) => return Err(status.into()),
+            };
+            if task.id != task_id || is_task_pending(&task) {
+                continue;
+            }
+            // updates are broadcast to all devices, the authoritative result
+            // is the one returned for the task query
+            let task = self.get_task(task_id.clone()).await?;
+            if let Some(result) = get_task_result(task) {
+                return result;
+            }
+        }
+        self.poll_task_result(task_id).await
+    }
+
+    /// Looks for a pending task created by the request,
+    /// used when the response to the request was lost
+    ///
+    /// # Arguments
+    ///
+    /// * `task_type` - the type of the task the request creates
+    /// * `request` - the request the task was possibly created by
+    async fn find_pending_task<R>(
+        &mut self,
+        task_type: TaskType,
+        request: &R,
+    ) -> Result<Option<TaskId>, CommunicatorError>
+    where
+        R: Message + Default + PartialEq,
+    {
+        let tasks = self
+            .call_idempotent(|mut client| {
+                let request = tonic::Request::new(TasksRequest { device_id: None });
+                async move { client.get_tasks(request).await }
+            })
+            .await?
+            .tasks;
+        let candidates = tasks
+            .into_iter()
+            .filter(|task| task.r#type == task_type as i32 && is_task_pending(task));
+        for candidate in candidates {
+            // the request is present only when the task is queried directly
+            let task = self.get_task(candidate.id).await?;
+            let Some(task_request) = task.request else {
+                continue;
+            };
+            if R::decode(task_request.as_slice()).as_ref() == Ok(request) {
+                return Ok(Some(task.id));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the devices registered on the server by their IDs. The devices
+    /// only describe the groups, so an empty map is returned if they can't be listed.
+    async fn get_devices(&mut self) -> HashMap<DeviceId, Device> {
+        let response = self
+            .call_idempotent(|mut client| {
+                let request = tonic::Request::new(DevicesRequest {});
+                async move { client.get_devices(request).await }
+            })
+            .await;
+        match response {
+            Ok(response) => response
+                .devices
+                .into_iter()
+                .map(|device| (device.identifier.clone(), device))
+                .collect(),
+            Err(err) => {
+                diagnostics::report(&err);
+                HashMap::new()
+            }
+        }
+    }
+
+    /// Subscribes to task updates. Returns `None` if the server
+    /// doesn't provide updates for this client.
+    async fn subscribe_updates(&mut self) -> Result<Option<Streaming<Task>>, CommunicatorError> {
+        let request = tonic::Request::new(SubscribeRequest {});
+        let mut client = self.get_client_with_retries().await?;
+        match client.subscribe_updates(request).await {
+            Ok(response) => Ok(Some(response.into_inner())),
+            Err(status) if is_streaming_unavailable(&status) => Ok(None),
+            Err(status) if is_network_failure_status(&status) => {
+                // polling re-establishes the connection
+                self.client = None;
+                Ok(None)
+            }
+            Err(status) => Err(status.into()),
+        }
     }
 }
 
 #[async_trait]
 impl Communicator for Meesign {
-    async fn get_groups(&mut self) -> Result<Vec<Group>, CommunicatorError> {
-        let request = tonic::Request::new(GroupsRequest { device_id: None });
+    fn get_server_info(&self) -> &ServerInfo {
+        &self.server_info
+    }
 
-        let response = self.client.get_groups(request).await?;
-        let groups = &response.get_ref().groups;
+    fn get_server_address(&self) -> String {
+        format!(
+            "{}:{}",
+            self.endpoint.get_hostname(),
+            self.endpoint.get_port()
+        )
+    }
+
+    async fn get_groups(&mut self) -> Result<Vec<Group>, CommunicatorError> {
+        let response = self
+            .call_idempotent(|mut client| {
+                // the bridge is not a member of the groups, specifying
+                // the device would filter all of them out
+                let request = tonic::Request::new(GroupsRequest { device_id: None });
+                async move { client.get_groups(request).await }
+            })
+            .await?;
+        let groups = &response.groups;
+        let devices = match groups.iter().any(|group| !group.device_ids.is_empty()) {
+            true => self.get_devices().await,
+            false => HashMap::new(),
+        };
         let groups = groups
             .iter()
-            .filter(|group| group.key_type == KeyType::SignChallenge as i32)
-            .map(|group| Group::new(group.identifier.clone(), group.name.clone()))
+            // groups the bridge or the server can't use are skipped
+            .filter_map(|group| {
+                let key_type = get_group_key_type(group.key_type)?;
+                let curve = get_group_curve(group.protocol, group.curve, key_type)?;
+                let group_devices = group
+                    .device_ids
+                    .iter()
+                    .map(|device_id| get_group_device(device_id, devices.get(device_id)))
+                    .collect();
+                Some(
+                    Group::new(
+                        group.identifier.clone(),
+                        group.name.clone(),
+                        key_type,
+                        curve,
+                    )
+                    .with_members(group.threshold, group_devices),
+                )
+            })
             .collect();
         Ok(groups)
     }
 
     async fn send_auth_request(
+        &mut self,
+        group_id: GroupId,
+        key_type: GroupKeyType,
+        data: RequestData,
+        request_context: RequestContext,
+    ) -> Result<TaskId, CommunicatorError> {
+        if key_type == GroupKeyType::Decrypt {
+            return Err(CommunicatorError::InvalidRequestData);
+        }
+        if key_type == GroupKeyType::SignPdf && !data.starts_with(PDF_HEADER) {
+            return Err(CommunicatorError::InvalidRequestData);
+        }
+        let task_name =
+            self.task_name_provider
+                .get_task_name(key_type.into(), &request_context, &data);
+        let sign_request = SignRequest {
+            name: task_name,
+            group_id,
+            data,
+        };
+        // the request can't be retried once sent, as the server
+        // could have created the task even though the response was lost
+        let mut client = self.get_client_with_retries().await?;
+        let response = match client.sign(tonic::Request::new(sign_request.clone())).await {
+            Ok(response) => response,
+            Err(status) if is_network_failure_status(&status) => {
+                self.client = None;
+                let task_type = match key_type {
+                    GroupKeyType::SignPdf => TaskType::SignPdf,
+                    _ => TaskType::SignChallenge,
+                };
+                return match self.find_pending_task(task_type, &sign_request).await {
+                    Ok(Some(task_id)) => Ok(task_id),
+                    _ => Err(status.into()),
+                };
+            }
+            Err(status) => return Err(status.into()),
+        };
+
+        Ok(response.get_ref().id.clone())
+    }
+
+    async fn send_decryption_request(
         &mut self,
         group_id: GroupId,
         data: RequestData,
-        request_originator: Option<String>,
+        request_context: RequestContext,
     ) -> Result<TaskId, CommunicatorError> {
-        let task_name_provider = TaskNameProvider::new();
-        let task_name = task_name_provider.get_task_name(request_originator);
-        let request = tonic::Request::new(SignRequest {
+        let task_name =
+            self.task_name_provider
+                .get_task_name(RequestKind::Decryption, &request_context, &data);
+        let decrypt_request = DecryptRequest {
             name: task_name,
             group_id,
             data,
-        });
-        let response = self.client.sign(request).await?;
+            data_type: DECRYPTION_DATA_TYPE.into(),
+        };
+        // not retried for the same reason as signing requests
+        let mut client = self.get_client_with_retries().await?;
+        let response = match client
+            .decrypt(tonic::Request::new(decrypt_request.clone()))
+            .await
+        {
+            Ok(response) => response,
+            Err(status) if is_network_failure_status(&status) => {
+                self.client = None;
+                return match self
+                    .find_pending_task(TaskType::Decrypt, &decrypt_request)
+                    .await
+                {
+                    Ok(Some(task_id)) => Ok(task_id),
+                    _ => Err(status.into()),
+                };
+            }
+            Err(status) if status.code() == Code::Unimplemented => {
+                return Err(CommunicatorError::UnsupportedServer(
+                    "the server doesn't support decryption".into(),
+                ));
+            }
+            Err(status) => return Err(status.into()),
+        };
+
+        Ok(response.get_ref().id.clone())
+    }
+
+    async fn send_key_agreement_request(
+        &mut self,
+        group_id: GroupId,
+        public_key: RequestData,
+        request_context: RequestContext,
+    ) -> Result<TaskId, CommunicatorError> {
+        let task_name = self.task_name_provider.get_task_name(
+            RequestKind::KeyAgreement,
+            &request_context,
+            &public_key,
+        );
+        let derive_key_request = DeriveKeyRequest {
+            name: task_name,
+            group_id,
+            public_key,
+        };
+        // not retried for the same reason as signing requests
+        let mut client = self.get_client_with_retries().await?;
+        let response = match client
+            .derive_key(tonic::Request::new(derive_key_request.clone()))
+            .await
+        {
+            Ok(response) => response,
+            Err(status) if is_network_failure_status(&status) => {
+                self.client = None;
+                return match self
+                    .find_pending_task(TaskType::DeriveKey, &derive_key_request)
+                    .await
+                {
+                    Ok(Some(task_id)) => Ok(task_id),
+                    _ => Err(status.into()),
+                };
+            }
+            Err(status) if status.code() == Code::Unimplemented => {
+                return Err(CommunicatorError::UnsupportedServer(
+                    "the server doesn't support key agreement".into(),
+                ));
+            }
+            Err(status) => return Err(status.into()),
+        };
 
         Ok(response.get_ref().id.clone())
     }
@@ -83,23 +533,798 @@ impl Communicator for Meesign {
         &mut self,
         task_id: TaskId,
     ) -> Result<Option<AuthResponse>, CommunicatorError> {
-        for _attempt in 0..MAX_ATTEMPT_COUNT {
-            let request = tonic::Request::new(TaskRequest {
-                task_id: task_id.clone(),
-                device_id: None,
-            });
-            let response = self.client.get_task(request).await?;
-            if response.get_ref().state == TaskState::Finished as i32 {
-                return Ok(response.get_ref().data.to_owned());
+        let waiting_time = self.endpoint.get_approval_timeout();
+        let waiting = async {
+            match self.subscribe_updates().await? {
+                Some(updates) => self.wait_for_task_update(task_id, updates).await,
+                None => self.poll_task_result(task_id).await,
             }
-            if response.get_ref().state == TaskState::Failed as i32 {
-                return Err(CommunicatorError::TaskFailed);
+        };
+
+        time::timeout(waiting_time, waiting)
+            .await
+            .map_err(|_| CommunicatorError::TaskTimedOut(waiting_time.as_secs()))?
+    }
+
+    async fn send_group_request(
+        &mut self,
+        parameters: GroupParameters,
+    ) -> Result<TaskId, CommunicatorError> {
+        let key_type = parameters.get_key_type();
+        let curve = parameters.get_curve();
+        let protocol =
+            get_group_protocol(key_type, curve).ok_or(CommunicatorError::InvalidRequestData)?;
+        let group_request = GroupRequest {
+            name: parameters.get_name().into(),
+            device_ids: parameters.get_device_ids().to_vec(),
+            threshold: parameters.get_threshold(),
+            protocol: protocol as i32,
+            key_type: get_proto_key_type(key_type) as i32,
+            curve: get_proto_curve(curve) as i32,
+        };
+        // not retried, a repeated request would create another group
+        let mut client = self.get_client_with_retries().await?;
+        let response = match client
+            .group(tonic::Request::new(group_request.clone()))
+            .await
+        {
+            Ok(response) => response,
+            Err(status) if is_network_failure_status(&status) => {
+                self.client = None;
+                return match self
+                    .find_pending_task(TaskType::Group, &group_request)
+                    .await
+                {
+                    Ok(Some(task_id)) => Ok(task_id),
+                    _ => Err(status.into()),
+                };
             }
-            time::sleep(Duration::from_secs(ATTEMPT_SLEEP_SEC)).await;
+            Err(status) => return Err(status.into()),
+        };
+
+        Ok(response.get_ref().id.clone())
+    }
+
+    async fn cancel_task(&mut self, _task_id: TaskId) -> Result<(), CommunicatorError> {
+        // the protocol offers no way for the requester to withdraw a task,
+        // only the group members can decide it. The task stays pending
+        // on the server until the members decide it or it expires.
+        Ok(())
+    }
+
+    fn clone_box(&self) -> Box<dyn Communicator> {
+        Box::new(self.clone())
+    }
+}
+
+/// Maps the proto key type to the group key type,
+/// returns `None` for key types the bridge doesn't support
+fn get_group_key_type(key_type: i32) -> Option<GroupKeyType> {
+    match KeyType::from_i32(key_type)? {
+        KeyType::SignChallenge => Some(GroupKeyType::SignChallenge),
+        KeyType::SignPdf => Some(GroupKeyType::SignPdf),
+        KeyType::Decrypt => Some(GroupKeyType::Decrypt),
+    }
+}
+
+fn get_proto_key_type(key_type: GroupKeyType) -> KeyType {
+    match key_type {
+        GroupKeyType::SignChallenge => KeyType::SignChallenge,
+        GroupKeyType::SignPdf => KeyType::SignPdf,
+        GroupKeyType::Decrypt => KeyType::Decrypt,
+    }
+}
+
+/// Describes the group's device using the details the server provided, if any
+///
+/// # Arguments
+///
+/// * `device_id` - the ID of the group's device
+/// * `device` - the device as listed by the server
+fn get_group_device(device_id: &DeviceId, device: Option<&Device>) -> GroupDevice {
+    let name = device
+        .map(|device| device.name.clone())
+        .filter(|name| !name.is_empty());
+    let certificate = device
+        .map(|device| device.certificate.clone())
+        .filter(|certificate| !certificate.is_empty());
+    GroupDevice::new(device_id.clone(), name, certificate)
+}
+
+fn get_proto_protocol(protocol: GroupProtocol) -> ProtocolType {
+    match protocol {
+        GroupProtocol::Gg18 => ProtocolType::Gg18,
+        GroupProtocol::ElGamal => ProtocolType::Elgamal,
+        GroupProtocol::Frost => ProtocolType::Frost,
+    }
+}
+
+fn get_proto_curve(curve: GroupCurve) -> Curve {
+    match curve {
+        GroupCurve::P256 => Curve::P256,
+        GroupCurve::Secp256k1 => Curve::Secp256k1,
+        GroupCurve::Ed25519 => Curve::Ed25519,
+    }
+}
+
+/// Returns the protocol generating and using keys of the given type and curve,
+/// or `None` if no protocol supported by the bridge can do so
+///
+/// # Arguments
+///
+/// * `key_type` - the purpose of the group's key
+/// * `curve` - the curve of the group's key
+fn get_group_protocol(key_type: GroupKeyType, curve: GroupCurve) -> Option<ProtocolType> {
+    let protocol = get_proto_protocol(GroupProtocol::for_key(key_type, curve));
+    get_group_curve(protocol as i32, get_proto_curve(curve) as i32, key_type).map(|_| protocol)
+}
+
+/// Maps the proto protocol and curve to the curve of the group's key,
+/// returns `None` for combinations the bridge doesn't support
+///
+/// # Arguments
+///
+/// * `protocol` - the threshold protocol the group runs
+/// * `curve` - the curve of the group's key
+/// * `key_type` - the purpose of the group's key
+fn get_group_curve(protocol: i32, curve: i32, key_type: GroupKeyType) -> Option<GroupCurve> {
+    let curve = match Curve::from_i32(curve)? {
+        Curve::P256 => GroupCurve::P256,
+        Curve::Secp256k1 => GroupCurve::Secp256k1,
+        Curve::Ed25519 => GroupCurve::Ed25519,
+    };
+    let is_supported = match (ProtocolType::from_i32(protocol)?, key_type) {
+        (ProtocolType::Gg18, GroupKeyType::SignChallenge | GroupKeyType::SignPdf) => {
+            curve != GroupCurve::Ed25519
+        }
+        // Schnorr signatures can't be embedded in PDF documents
+        (ProtocolType::Frost, GroupKeyType::SignChallenge) => curve == GroupCurve::Ed25519,
+        (ProtocolType::Elgamal, GroupKeyType::Decrypt) => curve == GroupCurve::P256,
+        _ => false,
+    };
+    is_supported.then_some(curve)
+}
+
+/// Opens a channel to the server
+///
+/// # Arguments
+///
+/// * `endpoint` - the connection settings of the server
+/// * `client_tls_config` - the TLS configuration, including the client identity, if any
+async fn connect(
+    endpoint: &CommunicatorEndpoint,
+    client_tls_config: ClientTlsConfig,
+) -> Result<Channel, CommunicatorError> {
+    let server_uri = Uri::from_str(&format!(
+        "https://{}:{}",
+        endpoint.get_hostname(),
+        endpoint.get_port()
+    ))?;
+    let channel_builder = match endpoint.get_resolved_address() {
+        // the origin keeps the hostname in the requests
+        Some(address) => {
+            Channel::builder(Uri::from_str(&format!("https://{address}"))?).origin(server_uri)
         }
+        None => Channel::builder(server_uri),
+    };
+    let channel = channel_builder
+        .tls_config(client_tls_config)?
+        .connect_timeout(endpoint.get_connect_timeout())
+        .timeout(endpoint.get_request_timeout())
+        .connect()
+        .await?;
+    Ok(channel)
+}
+
+/// Queries the server version, servers released before `GetServerInfo` are legacy servers
+///
+/// # Arguments
+///
+/// * `client` - a client connected to the server
+async fn get_server_info(client: &mut MpcClient<Channel>) -> Result<ServerInfo, CommunicatorError> {
+    let request = tonic::Request::new(ServerInfoRequest {});
+    let response = match client.get_server_info(request).await {
+        Ok(response) => response.into_inner(),
+        Err(status) if status.code() == Code::Unimplemented => return Ok(ServerInfo::legacy()),
+        Err(status) => return Err(status.into()),
+    };
+    ServerInfo::from_reported_version(&response.version)
+}
+
+/// Registers the bridge as a new device with the server
+///
+/// # Arguments
+///
+/// * `client` - a client connected to the server
+/// * `device_name` - the name the device is registered under
+async fn register(
+    client: &mut MpcClient<Channel>,
+    device_name: &str,
+) -> Result<DeviceIdentity, CommunicatorError> {
+    let registration = DeviceRegistration::new()?;
+    let request = tonic::Request::new(RegistrationRequest {
+        name: device_name.into(),
+        csr: registration.get_csr(device_name)?,
+    });
+    let response = client.register(request).await?.into_inner();
+    registration.into_identity(response.device_id, &response.certificate)
+}
+
+fn is_task_pending(task: &Task) -> bool {
+    task.state == TaskState::Created as i32 || task.state == TaskState::Running as i32
+}
+
+/// Returns the result of a resolved task, or `None` if the task is still pending
+fn get_task_result(task: Task) -> Option<Result<Option<AuthResponse>, CommunicatorError>> {
+    if task.state == TaskState::Finished as i32 {
+        return Some(Ok(task.data));
+    }
+    if task.state == TaskState::Failed as i32 {
+        return Some(Err(CommunicatorError::TaskFailed));
+    }
+    None
+}
+
+/// Older servers don't implement the update stream, and servers
+/// require an authenticated device to subscribe
+fn is_streaming_unavailable(status: &Status) -> bool {
+    matches!(
+        status.code(),
+        Code::Unimplemented | Code::Unauthenticated | Code::PermissionDenied
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::{net::SocketAddr, time::Instant};
+
+    use crate::diagnostics::forwarder::DiagnosticsSink;
+
+    use super::stand_in::{
+        encrypt_for_group, verify_signature, verify_signature_on_curve, MeesignStandIn,
+        RunningStandIn,
+    };
+    use super::*;
+
+    static CHALLENGE: [u8; 32] = [0xab; 32];
+
+    /// Connects a client to the stand-in server, registering a new device
+    async fn connect(stand_in: &RunningStandIn) -> Meesign {
+        let directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let endpoint = stand_in.get_endpoint("unused".into());
+        let certificate = Certificate::from_pem(stand_in.get_ca_certificate());
+        Meesign::new(&endpoint, certificate, &directory)
+            .await
+            .unwrap()
+    }
+
+    async fn get_group_id(meesign: &mut Meesign) -> GroupId {
+        let groups = meesign.get_groups().await.unwrap();
+        groups[0].get_group_id().clone()
+    }
+
+    #[tokio::test]
+    async fn given_streaming_server_get_auth_response_returns_as_soon_as_task_finishes() {
+        let stand_in = MeesignStandIn::new()
+            .with_group("challenge", GroupKeyType::SignChallenge)
+            .start()
+            .await;
+        let mut meesign = connect(&stand_in).await;
+        let group_id = get_group_id(&mut meesign).await;
+
+        let start = Instant::now();
+        let task_id = meesign
+            .send_auth_request(
+                group_id.clone(),
+                GroupKeyType::SignChallenge,
+                CHALLENGE.to_vec(),
+                RequestContext::default(),
+            )
+            .await
+            .unwrap();
+        let response = meesign.get_auth_response(task_id).await.unwrap().unwrap();
+
+        assert!(start.elapsed() < Duration::from_secs(ATTEMPT_SLEEP_SEC));
+        assert!(verify_signature(&group_id, &CHALLENGE.to_vec(), &response));
+    }
+
+    #[tokio::test]
+    async fn given_server_without_streaming_get_auth_response_falls_back_to_polling() {
+        let stand_in = MeesignStandIn::new()
+            .with_group("challenge", GroupKeyType::SignChallenge)
+            .with_task_script(vec![TaskState::Finished])
+            .without_streaming()
+            .start()
+            .await;
+        let mut meesign = connect(&stand_in).await;
+        let group_id = get_group_id(&mut meesign).await;
+
+        let task_id = meesign
+            .send_auth_request(
+                group_id.clone(),
+                GroupKeyType::SignChallenge,
+                CHALLENGE.to_vec(),
+                RequestContext::default(),
+            )
+            .await
+            .unwrap();
+        let response = meesign.get_auth_response(task_id).await.unwrap().unwrap();
+
+        assert!(verify_signature(&group_id, &CHALLENGE.to_vec(), &response));
+    }
+
+    #[tokio::test]
+    async fn given_failing_task_get_auth_response_returns_task_failed() {
+        let stand_in = MeesignStandIn::new()
+            .with_group("challenge", GroupKeyType::SignChallenge)
+            .with_task_script(vec![TaskState::Created, TaskState::Failed])
+            .with_step_delay(Duration::from_millis(10))
+            .start()
+            .await;
+        let mut meesign = connect(&stand_in).await;
+        let group_id = get_group_id(&mut meesign).await;
+
+        let task_id = meesign
+            .send_auth_request(
+                group_id,
+                GroupKeyType::SignChallenge,
+                CHALLENGE.to_vec(),
+                RequestContext::default(),
+            )
+            .await
+            .unwrap();
+        let response = meesign.get_auth_response(task_id).await;
+
+        assert!(matches!(response, Err(CommunicatorError::TaskFailed)));
+    }
+
+    #[tokio::test]
+    async fn given_stored_identity_new_reuses_the_registered_device() {
+        let stand_in = MeesignStandIn::new().start().await;
+        let directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let endpoint = stand_in.get_endpoint("unused".into());
+        let certificate = Certificate::from_pem(stand_in.get_ca_certificate());
+
+        let registered = Meesign::new(&endpoint, certificate.clone(), &directory)
+            .await
+            .unwrap();
+        let reconnected = Meesign::new(&endpoint, certificate, &directory)
+            .await
+            .unwrap();
+
+        assert_eq!(registered.device_id, reconnected.device_id);
+    }
+
+    #[tokio::test]
+    async fn given_groups_of_both_key_types_get_groups_keeps_the_key_type() {
+        let stand_in = MeesignStandIn::new()
+            .with_group("challenge", GroupKeyType::SignChallenge)
+            .with_group("documents", GroupKeyType::SignPdf)
+            .start()
+            .await;
+        let mut meesign = connect(&stand_in).await;
+
+        let groups = meesign.get_groups().await.unwrap();
+
+        let key_types: Vec<GroupKeyType> = groups.iter().map(Group::get_key_type).collect();
+        assert_eq!(
+            key_types,
+            vec![GroupKeyType::SignChallenge, GroupKeyType::SignPdf]
+        );
+    }
+
+    #[tokio::test]
+    async fn given_document_group_send_auth_request_refuses_non_pdf_data() {
+        let stand_in = MeesignStandIn::new()
+            .with_group("documents", GroupKeyType::SignPdf)
+            .start()
+            .await;
+        let mut meesign = connect(&stand_in).await;
+        let group_id = get_group_id(&mut meesign).await;
+
+        let result = meesign
+            .send_auth_request(
+                group_id,
+                GroupKeyType::SignPdf,
+                vec![1, 2, 3],
+                RequestContext::default(),
+            )
+            .await;
+
+        assert!(matches!(result, Err(CommunicatorError::InvalidRequestData)));
+    }
+
+    #[tokio::test]
+    async fn given_newer_major_server_version_new_connects() {
+        let stand_in = MeesignStandIn::new()
+            .with_group("challenge", GroupKeyType::SignChallenge)
+            .with_version("1.2.0")
+            .start()
+            .await;
+        let mut meesign = connect(&stand_in).await;
+
+        let groups = meesign.get_groups().await.unwrap();
+
+        assert_eq!(meesign.get_server_info().get_version().to_string(), "1.2.0");
+        assert_eq!(groups.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn given_server_without_server_info_new_assumes_legacy_server() {
+        let stand_in = MeesignStandIn::new()
+            .with_group("challenge", GroupKeyType::SignChallenge)
+            .with_group("decryption", GroupKeyType::Decrypt)
+            .without_server_info()
+            .start()
+            .await;
+        let mut meesign = connect(&stand_in).await;
+
+        let groups = meesign.get_groups().await.unwrap();
+
+        assert_eq!(meesign.get_server_info().get_version().to_string(), "0.0.0");
+        let key_types: Vec<GroupKeyType> = groups.iter().map(Group::get_key_type).collect();
+        assert_eq!(
+            key_types,
+            vec![GroupKeyType::SignChallenge, GroupKeyType::Decrypt]
+        );
+    }
+
+    #[tokio::test]
+    async fn given_older_server_get_groups_keeps_its_challenge_groups() {
+        let stand_in = MeesignStandIn::new()
+            .with_group("challenge", GroupKeyType::SignChallenge)
+            .with_group("documents", GroupKeyType::SignPdf)
+            .with_version("0.1.2")
+            .start()
+            .await;
+        let mut meesign = connect(&stand_in).await;
+
+        let groups = meesign.get_groups().await.unwrap();
+
+        let key_types: Vec<GroupKeyType> = groups.iter().map(Group::get_key_type).collect();
+        assert_eq!(
+            key_types,
+            vec![GroupKeyType::SignChallenge, GroupKeyType::SignPdf]
+        );
+    }
+
+    #[tokio::test]
+    async fn given_interrupted_connection_get_groups_is_retried() {
+        let stand_in = MeesignStandIn::new()
+            .with_group("challenge", GroupKeyType::SignChallenge)
+            .start()
+            .await;
+        let mut meesign = connect(&stand_in).await;
+
+        stand_in.interrupt(2);
+        let groups = meesign.get_groups().await.unwrap();
+        assert_eq!(groups.len(), 1);
+
+        stand_in.interrupt(3);
+        assert!(meesign.get_groups().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn given_interrupted_connection_sign_is_not_retried() {
+        let stand_in = MeesignStandIn::new()
+            .with_group("challenge", GroupKeyType::SignChallenge)
+            .start()
+            .await;
+        let mut meesign = connect(&stand_in).await;
+        let group_id = get_group_id(&mut meesign).await;
+
+        stand_in.interrupt(1);
+        let result = meesign
+            .send_auth_request(
+                group_id.clone(),
+                GroupKeyType::SignChallenge,
+                CHALLENGE.to_vec(),
+                RequestContext::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(CommunicatorError::InvalidStatus(_))));
+
+        // the next request goes through a new connection
+        let task_id = meesign
+            .send_auth_request(
+                group_id.clone(),
+                GroupKeyType::SignChallenge,
+                CHALLENGE.to_vec(),
+                RequestContext::default(),
+            )
+            .await
+            .unwrap();
+        let response = meesign.get_auth_response(task_id).await.unwrap().unwrap();
+        assert!(verify_signature(&group_id, &CHALLENGE.to_vec(), &response));
+    }
+
+    #[tokio::test]
+    async fn given_lost_sign_response_send_auth_request_finds_the_created_task() {
+        let stand_in = MeesignStandIn::new()
+            .with_group("challenge", GroupKeyType::SignChallenge)
+            .start()
+            .await;
+        let mut meesign = connect(&stand_in).await;
+        let group_id = get_group_id(&mut meesign).await;
+
+        stand_in.lose_responses(1);
+        let task_id = meesign
+            .send_auth_request(
+                group_id.clone(),
+                GroupKeyType::SignChallenge,
+                CHALLENGE.to_vec(),
+                RequestContext::default(),
+            )
+            .await
+            .unwrap();
+        let response = meesign.get_auth_response(task_id).await.unwrap().unwrap();
+
+        assert_eq!(stand_in.get_task_count(), 1);
+        assert!(verify_signature(&group_id, &CHALLENGE.to_vec(), &response));
+    }
+
+    #[tokio::test]
+    async fn given_groups_on_other_curves_signatures_verify_on_their_curves() {
+        let stand_in = MeesignStandIn::new()
+            .with_group_on_curve("ssh", GroupKeyType::SignChallenge, GroupCurve::Ed25519)
+            .with_group_on_curve("wallet", GroupKeyType::SignChallenge, GroupCurve::Secp256k1)
+            .start()
+            .await;
+        let mut meesign = connect(&stand_in).await;
+        let groups = meesign.get_groups().await.unwrap();
+
+        let curves: Vec<GroupCurve> = groups.iter().map(Group::get_curve).collect();
+        assert_eq!(curves, vec![GroupCurve::Ed25519, GroupCurve::Secp256k1]);
+        for group in groups {
+            let group_id = group.get_group_id().clone();
+            let task_id = meesign
+                .send_auth_request(
+                    group_id.clone(),
+                    GroupKeyType::SignChallenge,
+                    CHALLENGE.to_vec(),
+                    RequestContext::default(),
+                )
+                .await
+                .unwrap();
+            let response = meesign.get_auth_response(task_id).await.unwrap().unwrap();
+            assert!(verify_signature_on_curve(
+                group.get_curve(),
+                &group_id,
+                &CHALLENGE.to_vec(),
+                &response
+            ));
+        }
+    }
+
+    #[test]
+    fn given_unsupported_protocol_and_curve_combination_group_curve_is_none() {
+        let curve = |protocol: ProtocolType, curve: Curve, key_type| {
+            get_group_curve(protocol as i32, curve as i32, key_type)
+        };
+        assert_eq!(
+            curve(ProtocolType::Gg18, Curve::P256, GroupKeyType::SignPdf),
+            Some(GroupCurve::P256)
+        );
+        assert_eq!(
+            curve(
+                ProtocolType::Frost,
+                Curve::Ed25519,
+                GroupKeyType::SignChallenge
+            ),
+            Some(GroupCurve::Ed25519)
+        );
+        assert_eq!(
+            curve(
+                ProtocolType::Gg18,
+                Curve::Ed25519,
+                GroupKeyType::SignChallenge
+            ),
+            None
+        );
+        assert_eq!(
+            curve(ProtocolType::Frost, Curve::Ed25519, GroupKeyType::SignPdf),
+            None
+        );
+        assert_eq!(
+            curve(
+                ProtocolType::Elgamal,
+                Curve::Secp256k1,
+                GroupKeyType::Decrypt
+            ),
+            None
+        );
+        assert_eq!(
+            get_group_curve(ProtocolType::Gg18 as i32, 42, GroupKeyType::SignPdf),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn given_decryption_group_send_decryption_request_returns_the_plaintext() {
+        let stand_in = MeesignStandIn::new()
+            .with_group("secrets", GroupKeyType::Decrypt)
+            .start()
+            .await;
+        let mut meesign = connect(&stand_in).await;
+        let groups = meesign.get_groups().await.unwrap();
+        assert_eq!(groups[0].get_key_type(), GroupKeyType::Decrypt);
+        let group_id = groups[0].get_group_id().clone();
+
+        let ciphertext = encrypt_for_group(&group_id, b"quorum secret");
+        let task_id = meesign
+            .send_decryption_request(group_id.clone(), ciphertext, RequestContext::default())
+            .await
+            .unwrap();
+        let plaintext = meesign.get_auth_response(task_id).await.unwrap().unwrap();
+        assert_eq!(plaintext, b"quorum secret");
+
+        // decryption groups can't sign
+        let result = meesign
+            .send_auth_request(
+                group_id,
+                GroupKeyType::Decrypt,
+                CHALLENGE.to_vec(),
+                RequestContext::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(CommunicatorError::InvalidRequestData)));
+    }
+
+    #[tokio::test]
+    async fn given_lost_decrypt_response_send_decryption_request_finds_the_created_task() {
+        let stand_in = MeesignStandIn::new()
+            .with_group("secrets", GroupKeyType::Decrypt)
+            .start()
+            .await;
+        let mut meesign = connect(&stand_in).await;
+        let group_id = get_group_id(&mut meesign).await;
+        let ciphertext = encrypt_for_group(&group_id, b"quorum secret");
+
+        stand_in.lose_responses(1);
+        let task_id = meesign
+            .send_decryption_request(group_id, ciphertext, RequestContext::default())
+            .await
+            .unwrap();
+        let plaintext = meesign.get_auth_response(task_id).await.unwrap().unwrap();
+
+        assert_eq!(stand_in.get_task_count(), 1);
+        assert_eq!(plaintext, b"quorum secret");
+    }
+
+    #[tokio::test]
+    async fn given_decryption_group_send_key_agreement_request_returns_the_shared_secret() {
+        use openssl::{
+            bn::BigNumContext,
+            derive::Deriver,
+            ec::{EcGroup, EcKey, EcPoint, PointConversionForm},
+            nid::Nid,
+            pkey::PKey,
+        };
+
+        let stand_in = MeesignStandIn::new()
+            .with_group("secrets", GroupKeyType::Decrypt)
+            .start()
+            .await;
+        let mut meesign = connect(&stand_in).await;
+        let group_id = get_group_id(&mut meesign).await;
+        let curve = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let mut context = BigNumContext::new().unwrap();
+        let peer_key = EcKey::generate(&curve).unwrap();
+        let peer_public_key = peer_key
+            .public_key()
+            .to_bytes(&curve, PointConversionForm::UNCOMPRESSED, &mut context)
+            .unwrap();
+
+        let task_id = meesign
+            .send_key_agreement_request(
+                group_id.clone(),
+                peer_public_key,
+                RequestContext::default(),
+            )
+            .await
+            .unwrap();
+        let shared_secret = meesign.get_auth_response(task_id).await.unwrap().unwrap();
+
+        let group_point = EcPoint::from_bytes(&curve, &group_id, &mut context).unwrap();
+        let group_key =
+            PKey::from_ec_key(EcKey::from_public_key(&curve, &group_point).unwrap()).unwrap();
+        let peer_key = PKey::from_ec_key(peer_key).unwrap();
+        let mut deriver = Deriver::new(&peer_key).unwrap();
+        deriver.set_peer(&group_key).unwrap();
+        assert_eq!(shared_secret, deriver.derive_to_vec().unwrap());
+    }
+
+    #[tokio::test]
+    async fn given_server_without_key_agreement_send_key_agreement_request_is_refused() {
+        let stand_in = MeesignStandIn::new()
+            .with_group("secrets", GroupKeyType::Decrypt)
+            .without_key_agreement()
+            .start()
+            .await;
+        let mut meesign = connect(&stand_in).await;
+        let group_id = get_group_id(&mut meesign).await;
+
+        let result = meesign
+            .send_key_agreement_request(group_id.clone(), group_id, RequestContext::default())
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(CommunicatorError::UnsupportedServer(_))
+        ));
+        assert_eq!(stand_in.get_task_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn given_group_of_registered_devices_get_groups_describes_its_members() {
+        let stand_in = MeesignStandIn::new()
+            .with_device("alice's phone")
+            .with_device("bob's phone")
+            .start()
+            .await;
+        let device_ids = stand_in.get_device_ids();
+        let mut meesign = connect(&stand_in).await;
+        let parameters = GroupParameters::new(
+            "ssh".into(),
+            device_ids.clone(),
+            2,
+            GroupKeyType::SignChallenge,
+            GroupCurve::P256,
+        );
+        let task_id = meesign.send_group_request(parameters).await.unwrap();
+        meesign.get_auth_response(task_id).await.unwrap().unwrap();
+
+        let groups = meesign.get_groups().await.unwrap();
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].get_threshold(), 2);
+        let devices = groups[0].get_devices();
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].get_device_id(), &device_ids[0]);
+        assert_eq!(devices[0].get_name(), Some("alice's phone"));
+        assert_eq!(devices[1].get_name(), Some("bob's phone"));
+        assert!(devices
+            .iter()
+            .all(
+                |device| openssl::x509::X509::from_der(device.get_certificate().unwrap()).is_ok()
+            ));
+    }
+
+    #[tokio::test]
+    async fn given_resolved_address_the_hostname_is_not_resolved_on_connect() {
+        let stand_in = MeesignStandIn::new()
+            .with_group("ssh", GroupKeyType::SignChallenge)
+            .start()
+            .await;
+        let directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let address = SocketAddr::from(([127, 0, 0, 1], stand_in.get_port()));
+        let endpoint = CommunicatorEndpoint::new("meesign.invalid".into(), "unused".into())
+            .with_port(Some(stand_in.get_port()))
+            .with_tls_server_name(Some(stand_in.get_tls_server_name().into()))
+            .with_resolved_address(Some(address));
+        let certificate = Certificate::from_pem(stand_in.get_ca_certificate());
+
+        let mut meesign = Meesign::new(&endpoint, certificate, &directory)
+            .await
+            .unwrap();
+
+        assert_eq!(meesign.get_groups().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn given_log_sink_diagnostics_reach_the_server() {
+        let stand_in = MeesignStandIn::new().start().await;
+        let meesign = connect(&stand_in).await;
+        let mut log_sink = meesign.get_log_sink();
+
+        log_sink
+            .send_diagnostics("warning communicator: Task failed remotely".into())
+            .await
+            .unwrap();
 
-        Err(CommunicatorError::TaskTimedOut(
-            (MAX_ATTEMPT_COUNT as u64) * ATTEMPT_SLEEP_SEC,
-        ))
+        assert_eq!(
+            stand_in.get_logs(),
+            vec!["warning communicator: Task failed remotely"]
+        );
     }
 }
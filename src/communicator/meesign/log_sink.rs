@@ -0,0 +1,52 @@
+use tonic::{
+    async_trait,
+    transport::{Channel, ClientTlsConfig},
+};
+
+use crate::{
+    communicator::communicator_error::CommunicatorError, configuration::CommunicatorEndpoint,
+    diagnostics::forwarder::DiagnosticsSink,
+};
+
+use super::{
+    connect,
+    proto::{mpc_client::MpcClient, LogRequest},
+};
+
+/// Forwards diagnostics to the server's log endpoint
+pub(crate) struct MeesignLogSink {
+    /// Client of the current connection, `None` until the first batch is sent
+    /// or after a failure
+    client: Option<MpcClient<Channel>>,
+
+    endpoint: CommunicatorEndpoint,
+
+    /// The TLS configuration, including the device identity, so that the server
+    /// knows which device the diagnostics come from
+    client_tls_config: ClientTlsConfig,
+}
+
+impl MeesignLogSink {
+    pub(super) fn new(endpoint: CommunicatorEndpoint, client_tls_config: ClientTlsConfig) -> Self {
+        Self {
+            client: None,
+            endpoint,
+            client_tls_config,
+        }
+    }
+}
+
+#[async_trait]
+impl DiagnosticsSink for MeesignLogSink {
+    async fn send_diagnostics(&mut self, message: String) -> Result<(), CommunicatorError> {
+        let mut client = match self.client.take() {
+            Some(client) => client,
+            None => MpcClient::new(connect(&self.endpoint, self.client_tls_config.clone()).await?),
+        };
+        client
+            .log(tonic::Request::new(LogRequest { message }))
+            .await?;
+        self.client = Some(client);
+        Ok(())
+    }
+}
@@ -0,0 +1,1037 @@
+//! A stand-in MeeSign server used by tests. Groups are backed by local keys,
+//! tasks go through a scripted sequence of states and the server is reachable
+//! only through TLS, using a freshly generated certificate authority.
+//! Decryption groups decrypt ECIES ciphertexts created by [`encrypt_for_group`]
+//! and derive ECDH shared secrets.
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use openssl::{
+    asn1::Asn1Time,
+    bn::{BigNum, BigNumContext},
+    ec::{EcGroup, EcKey, EcPoint, PointConversionForm},
+    ecdsa::EcdsaSig,
+    hash::{hash, MessageDigest},
+    nid::Nid,
+    pkey::{Id, PKey, PKeyRef, Private, Public},
+    rand::rand_bytes,
+    sign::{Signer, Verifier},
+    symm::{decrypt_aead, encrypt_aead, Cipher},
+    x509::{
+        extension::{BasicConstraints, SubjectAlternativeName},
+        X509NameBuilder, X509NameRef, X509Req, X509,
+    },
+};
+use prost::Message;
+use tokio::{
+    net::TcpListener,
+    sync::{broadcast, watch},
+    time,
+};
+use tokio_stream::{
+    wrappers::{BroadcastStream, TcpListenerStream},
+    Stream, StreamExt,
+};
+use tonic::{
+    async_trait,
+    transport::{Identity, Server, ServerTlsConfig},
+    Request, Response, Status,
+};
+use uuid::Uuid;
+
+use crate::{
+    communicator::{
+        group::{GroupCurve, GroupKeyType},
+        GroupId, RequestData,
+    },
+    configuration::CommunicatorEndpoint,
+};
+
+pub(crate) use super::proto::task::TaskState;
+use super::proto::{
+    mpc_server::{Mpc, MpcServer},
+    Curve, DecryptRequest, DeriveKeyRequest, Device, Devices, DevicesRequest, Group, GroupRequest,
+    Groups, GroupsRequest, KeyType, LogRequest, ProtocolType, RegistrationRequest,
+    RegistrationResponse, Resp, ServerInfo, ServerInfoRequest, SignRequest, SubscribeRequest, Task,
+    TaskAcknowledgement, TaskDecision, TaskRequest, TaskType, TaskUpdate, Tasks, TasksRequest,
+};
+use super::{get_group_curve, get_group_key_type};
+
+static STAND_IN_SERVER_NAME: &str = "localhost";
+static STAND_IN_ADDRESS: &str = "127.0.0.1";
+static STAND_IN_VERSION: &str = "0.3.0";
+const SIGNATURE_COORDINATE_LENGTH: i32 = 32;
+const UPDATE_CHANNEL_CAPACITY: usize = 16;
+/// Length of the uncompressed ephemeral public key prepended to ECIES ciphertexts
+const EPHEMERAL_KEY_LENGTH: usize = 65;
+const ECIES_NONCE_LENGTH: usize = 12;
+const ECIES_TAG_LENGTH: usize = 16;
+
+/// A group whose signatures are created using a local key
+#[derive(Clone)]
+struct StandInGroup {
+    name: String,
+    key_type: GroupKeyType,
+    curve: GroupCurve,
+    key: PKey<Private>,
+    threshold: u32,
+    device_ids: Vec<Vec<u8>>,
+}
+
+impl StandInGroup {
+    fn new(name: String, key_type: GroupKeyType, curve: GroupCurve) -> Self {
+        Self {
+            name,
+            key_type,
+            curve,
+            key: generate_group_key(curve),
+            threshold: 1,
+            device_ids: vec![],
+        }
+    }
+
+    /// Returns the group identifier, i.e., the uncompressed public key,
+    /// or the encoded point for Ed25519 groups
+    fn get_identifier(&self) -> GroupId {
+        if self.curve == GroupCurve::Ed25519 {
+            return self.key.raw_public_key().unwrap();
+        }
+        let key = self.get_ec_key();
+        let mut context = BigNumContext::new().unwrap();
+        key.public_key()
+            .to_bytes(key.group(), PointConversionForm::UNCOMPRESSED, &mut context)
+            .unwrap()
+    }
+
+    fn get_ec_key(&self) -> EcKey<Private> {
+        self.key.ec_key().unwrap()
+    }
+
+    /// Signs the data, ECDSA challenges are expected to be already hashed
+    fn sign(&self, data: &[u8]) -> Vec<u8> {
+        if self.curve == GroupCurve::Ed25519 {
+            let mut signer = Signer::new_without_digest(&self.key).unwrap();
+            return signer.sign_oneshot_to_vec(data).unwrap();
+        }
+        let digest = match self.key_type {
+            GroupKeyType::SignChallenge | GroupKeyType::Decrypt => data.to_vec(),
+            GroupKeyType::SignPdf => hash(MessageDigest::sha256(), data).unwrap().to_vec(),
+        };
+        let signature = EcdsaSig::sign(&digest, &self.get_ec_key()).unwrap();
+        let mut signature_bytes = signature
+            .r()
+            .to_vec_padded(SIGNATURE_COORDINATE_LENGTH)
+            .unwrap();
+        signature_bytes.extend(
+            signature
+                .s()
+                .to_vec_padded(SIGNATURE_COORDINATE_LENGTH)
+                .unwrap(),
+        );
+        signature_bytes
+    }
+
+    /// Decrypts the ciphertext, see [`encrypt_for_group`] for its format
+    fn decrypt(&self, ciphertext: &[u8]) -> Option<Vec<u8>> {
+        if ciphertext.len() < EPHEMERAL_KEY_LENGTH + ECIES_NONCE_LENGTH + ECIES_TAG_LENGTH {
+            return None;
+        }
+        let (ephemeral_key, ciphertext) = ciphertext.split_at(EPHEMERAL_KEY_LENGTH);
+        let (nonce, ciphertext) = ciphertext.split_at(ECIES_NONCE_LENGTH);
+        let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - ECIES_TAG_LENGTH);
+        let group_key = self.get_ec_key();
+        let mut context = BigNumContext::new().unwrap();
+        let ephemeral_key =
+            EcPoint::from_bytes(group_key.group(), ephemeral_key, &mut context).ok()?;
+        let key = derive_ecies_key(&group_key, &ephemeral_key);
+        decrypt_aead(
+            Cipher::aes_256_gcm(),
+            &key,
+            Some(nonce),
+            &[],
+            ciphertext,
+            tag,
+        )
+        .ok()
+    }
+
+    /// Returns the x coordinate of the shared point, `None` for invalid public keys
+    fn derive_shared_secret(&self, public_key: &[u8]) -> Option<Vec<u8>> {
+        let group_key = self.get_ec_key();
+        let mut context = BigNumContext::new().unwrap();
+        let public_key = EcPoint::from_bytes(group_key.group(), public_key, &mut context).ok()?;
+        Some(get_shared_secret(&group_key, &public_key))
+    }
+
+    fn to_proto(&self) -> Group {
+        let (key_type, protocol) = match self.key_type {
+            GroupKeyType::SignChallenge if self.curve == GroupCurve::Ed25519 => {
+                (KeyType::SignChallenge, ProtocolType::Frost)
+            }
+            GroupKeyType::SignChallenge => (KeyType::SignChallenge, ProtocolType::Gg18),
+            GroupKeyType::SignPdf => (KeyType::SignPdf, ProtocolType::Gg18),
+            GroupKeyType::Decrypt => (KeyType::Decrypt, ProtocolType::Elgamal),
+        };
+        let curve = match self.curve {
+            GroupCurve::P256 => Curve::P256,
+            GroupCurve::Secp256k1 => Curve::Secp256k1,
+            GroupCurve::Ed25519 => Curve::Ed25519,
+        };
+        Group {
+            identifier: self.get_identifier(),
+            name: self.name.clone(),
+            threshold: self.threshold,
+            protocol: protocol as i32,
+            key_type: key_type as i32,
+            device_ids: self.device_ids.clone(),
+            curve: curve as i32,
+        }
+    }
+}
+
+/// Issues the server certificate and certificates of registered devices
+struct CertificateAuthority {
+    key: PKey<Private>,
+    certificate: X509,
+}
+
+impl CertificateAuthority {
+    fn new() -> Self {
+        let key = PKey::from_ec_key(generate_key()).unwrap();
+        let name = build_name("MeeSign stand-in CA");
+        let mut certificate = build_certificate(&name, &key, &name);
+        certificate
+            .append_extension(BasicConstraints::new().critical().ca().build().unwrap())
+            .unwrap();
+        certificate.sign(&key, MessageDigest::sha256()).unwrap();
+        Self {
+            key,
+            certificate: certificate.build(),
+        }
+    }
+
+    /// Issues a certificate for a server reachable as `STAND_IN_SERVER_NAME`
+    fn issue_server_identity(&self) -> Identity {
+        let key = PKey::from_ec_key(generate_key()).unwrap();
+        let name = build_name(STAND_IN_SERVER_NAME);
+        let mut certificate = build_certificate(&name, &key, self.certificate.subject_name());
+        // the address lets clients connect without overriding the TLS server name
+        let subject_alternative_name = SubjectAlternativeName::new()
+            .dns(STAND_IN_SERVER_NAME)
+            .ip(STAND_IN_ADDRESS)
+            .build(&certificate.x509v3_context(Some(&self.certificate), None))
+            .unwrap();
+        certificate
+            .append_extension(subject_alternative_name)
+            .unwrap();
+        certificate
+            .sign(&self.key, MessageDigest::sha256())
+            .unwrap();
+
+        Identity::from_pem(
+            certificate.build().to_pem().unwrap(),
+            key.private_key_to_pem_pkcs8().unwrap(),
+        )
+    }
+
+    /// Issues a device certificate for the DER-encoded CSR
+    fn issue_device_certificate(&self, csr: &[u8]) -> Result<Vec<u8>, Status> {
+        let request =
+            X509Req::from_der(csr).map_err(|_| Status::invalid_argument("Invalid CSR"))?;
+        let public_key = request
+            .public_key()
+            .map_err(|_| Status::invalid_argument("Invalid CSR"))?;
+        let mut certificate = build_certificate(
+            request.subject_name(),
+            &public_key,
+            self.certificate.subject_name(),
+        );
+        certificate
+            .sign(&self.key, MessageDigest::sha256())
+            .unwrap();
+        Ok(certificate.build().to_der().unwrap())
+    }
+
+    /// Issues a DER-encoded device certificate for a freshly generated key
+    fn issue_certificate_for(&self, common_name: &str) -> Vec<u8> {
+        let key = PKey::from_ec_key(generate_key()).unwrap();
+        let mut certificate = build_certificate(
+            &build_name(common_name),
+            &key,
+            self.certificate.subject_name(),
+        );
+        certificate
+            .sign(&self.key, MessageDigest::sha256())
+            .unwrap();
+        certificate.build().to_der().unwrap()
+    }
+}
+
+/// Implements the MPC service, see the module documentation
+#[derive(Clone)]
+pub(crate) struct MeesignStandIn {
+    groups: Arc<Mutex<Vec<StandInGroup>>>,
+
+    /// Devices registered with the server
+    devices: Arc<Mutex<Vec<Device>>>,
+    tasks: Arc<Mutex<HashMap<Vec<u8>, Task>>>,
+    updates: broadcast::Sender<Task>,
+    task_script: Vec<TaskState>,
+    step_delay: Duration,
+    supports_streaming: bool,
+    version: String,
+
+    /// Whether `GetServerInfo` is implemented, older servers don't have it
+    reports_server_info: bool,
+
+    /// Whether `DeriveKey` is implemented, older servers don't have it
+    supports_key_agreement: bool,
+
+    /// Whether the tasks may take their last scripted state,
+    /// held tasks wait for the test to release them
+    tasks_released: Arc<watch::Sender<bool>>,
+
+    /// Number of the upcoming requests failing as if the server was unreachable
+    interrupted_requests: Arc<AtomicUsize>,
+
+    /// Number of the upcoming signing, decryption and group requests whose response gets lost
+    lost_responses: Arc<AtomicUsize>,
+
+    /// Messages received by the log endpoint
+    logs: Arc<Mutex<Vec<String>>>,
+    certificate_authority: Arc<CertificateAuthority>,
+}
+
+impl MeesignStandIn {
+    /// Creates a server without groups, whose tasks get finished shortly after creation
+    pub(crate) fn new() -> Self {
+        let (updates, _) = broadcast::channel(UPDATE_CHANNEL_CAPACITY);
+        Self {
+            groups: Arc::new(Mutex::new(vec![])),
+            devices: Arc::new(Mutex::new(vec![])),
+            tasks: Arc::new(Mutex::new(HashMap::new())),
+            updates,
+            task_script: vec![TaskState::Created, TaskState::Running, TaskState::Finished],
+            step_delay: Duration::from_millis(100),
+            supports_streaming: true,
+            version: STAND_IN_VERSION.into(),
+            reports_server_info: true,
+            supports_key_agreement: true,
+            tasks_released: Arc::new(watch::channel(true).0),
+            interrupted_requests: Arc::new(AtomicUsize::new(0)),
+            lost_responses: Arc::new(AtomicUsize::new(0)),
+            logs: Arc::new(Mutex::new(vec![])),
+            certificate_authority: Arc::new(CertificateAuthority::new()),
+        }
+    }
+
+    /// Adds a registered device with a certificate issued by the stand-in CA
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the name of the device
+    pub(crate) fn with_device(self, name: &str) -> Self {
+        let certificate = self.certificate_authority.issue_certificate_for(name);
+        self.devices.lock().unwrap().push(Device {
+            identifier: Uuid::new_v4().as_bytes().to_vec(),
+            name: name.into(),
+            certificate,
+            last_active: 0,
+        });
+        self
+    }
+
+    /// Adds a group backed by a freshly generated P-256 key
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the name of the group
+    /// * `key_type` - the key type of the group
+    pub(crate) fn with_group(self, name: &str, key_type: GroupKeyType) -> Self {
+        self.with_group_on_curve(name, key_type, GroupCurve::P256)
+    }
+
+    /// Adds a group backed by a freshly generated key on the given curve.
+    /// Ed25519 groups run FROST, the others run the protocol of their key type.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the name of the group
+    /// * `key_type` - the key type of the group
+    /// * `curve` - the curve of the group's key
+    pub(crate) fn with_group_on_curve(
+        self,
+        name: &str,
+        key_type: GroupKeyType,
+        curve: GroupCurve,
+    ) -> Self {
+        self.groups
+            .lock()
+            .unwrap()
+            .push(StandInGroup::new(name.into(), key_type, curve));
+        self
+    }
+
+    /// Sets the states every task goes through, the first state is the one
+    /// the task is created in
+    ///
+    /// # Arguments
+    ///
+    /// * `task_script` - the sequence of task states
+    pub(crate) fn with_task_script(mut self, task_script: Vec<TaskState>) -> Self {
+        assert!(!task_script.is_empty());
+        self.task_script = task_script;
+        self
+    }
+
+    /// Sets the time between two consecutive task state transitions
+    pub(crate) fn with_step_delay(mut self, step_delay: Duration) -> Self {
+        self.step_delay = step_delay;
+        self
+    }
+
+    /// Makes the server behave like older servers without the update stream
+    pub(crate) fn without_streaming(mut self) -> Self {
+        self.supports_streaming = false;
+        self
+    }
+
+    /// Sets the version the server reports
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - the reported version
+    pub(crate) fn with_version(mut self, version: &str) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    /// Makes the server behave like servers released before `GetServerInfo`
+    pub(crate) fn without_server_info(mut self) -> Self {
+        self.reports_server_info = false;
+        self
+    }
+
+    /// Makes the server behave like servers released before `DeriveKey`
+    pub(crate) fn without_key_agreement(mut self) -> Self {
+        self.supports_key_agreement = false;
+        self
+    }
+
+    /// Keeps the tasks from taking their last scripted state
+    /// until [`RunningStandIn::release_tasks`] is called
+    pub(crate) fn with_held_tasks(self) -> Self {
+        self.tasks_released.send_replace(false);
+        self
+    }
+
+    /// Starts the server on the current runtime
+    pub(crate) async fn start(self) -> RunningStandIn {
+        let listener = TcpListener::bind(format!("{STAND_IN_ADDRESS}:0"))
+            .await
+            .unwrap();
+        let address = listener.local_addr().unwrap();
+        let ca_certificate = self.certificate_authority.certificate.to_pem().unwrap();
+        let interrupted_requests = self.interrupted_requests.clone();
+        let lost_responses = self.lost_responses.clone();
+        let tasks = self.tasks.clone();
+        let logs = self.logs.clone();
+        let devices = self.devices.clone();
+        let tasks_released = self.tasks_released.clone();
+        let tls_config = ServerTlsConfig::new()
+            .identity(self.certificate_authority.issue_server_identity())
+            .client_ca_root(tonic::transport::Certificate::from_pem(&ca_certificate))
+            .client_auth_optional(true);
+
+        tokio::spawn(
+            Server::builder()
+                .tls_config(tls_config)
+                .unwrap()
+                .add_service(MpcServer::new(self))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        RunningStandIn {
+            address,
+            ca_certificate,
+            interrupted_requests,
+            lost_responses,
+            tasks,
+            logs,
+            devices,
+            tasks_released,
+        }
+    }
+
+    /// Starts the server on a runtime of its own, to be used by tests
+    /// calling the blocking cryptoki functions
+    pub(crate) fn start_in_background(self) -> RunningStandIn {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime.block_on(async move {
+                sender.send(self.start().await).unwrap();
+                std::future::pending::<()>().await
+            });
+        });
+        receiver.recv().unwrap()
+    }
+
+    /// Fails the request if it is one of the interrupted ones
+    fn check_availability(&self) -> Result<(), Status> {
+        if take_one(&self.interrupted_requests) {
+            return Err(Status::unavailable("Connection interrupted"));
+        }
+        Ok(())
+    }
+
+    fn find_group(&self, group_id: &[u8]) -> Result<StandInGroup, Status> {
+        self.groups
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|group| group.get_identifier() == group_id)
+            .cloned()
+            .ok_or_else(|| Status::not_found("Unknown group"))
+    }
+
+    /// Creates a task that walks through the scripted states
+    ///
+    /// # Arguments
+    ///
+    /// * `task_type` - the type of the task
+    /// * `request` - the serialized request creating the task
+    /// * `result` - the data carried by the finished task
+    fn create_task(&self, task_type: TaskType, request: Vec<u8>, result: Vec<u8>) -> Task {
+        let task_id = Uuid::new_v4().as_bytes().to_vec();
+        let initial_state = self.task_script[0];
+        let task = Task {
+            id: task_id.clone(),
+            r#type: task_type as i32,
+            state: initial_state as i32,
+            data: (initial_state == TaskState::Finished).then(|| result.clone()),
+            request: Some(request),
+            ..Default::default()
+        };
+        self.tasks
+            .lock()
+            .unwrap()
+            .insert(task_id.clone(), task.clone());
+        self.run_task_script(task_id, result);
+        Task {
+            request: None,
+            ..task
+        }
+    }
+
+    /// Walks the task through the scripted states, finished tasks carry the result
+    fn run_task_script(&self, task_id: Vec<u8>, result: Vec<u8>) {
+        let stand_in = self.clone();
+        tokio::spawn(async move {
+            let last_state = stand_in.task_script.len() - 1;
+            for (index, state) in stand_in.task_script.iter().enumerate().skip(1) {
+                time::sleep(stand_in.step_delay).await;
+                if index == last_state {
+                    let mut tasks_released = stand_in.tasks_released.subscribe();
+                    let _ = tasks_released.wait_for(|released| *released).await;
+                }
+                let update = {
+                    let mut tasks = stand_in.tasks.lock().unwrap();
+                    let task = tasks.get_mut(&task_id).unwrap();
+                    task.state = *state as i32;
+                    task.data = (*state == TaskState::Finished).then(|| result.clone());
+                    // the update stream doesn't carry the result
+                    Task {
+                        data: None,
+                        request: None,
+                        ..task.clone()
+                    }
+                };
+                let _ = stand_in.updates.send(update);
+            }
+        });
+    }
+}
+
+/// A stand-in server accepting connections
+pub(crate) struct RunningStandIn {
+    address: SocketAddr,
+
+    /// PEM-encoded certificate of the stand-in certificate authority
+    ca_certificate: Vec<u8>,
+
+    interrupted_requests: Arc<AtomicUsize>,
+    lost_responses: Arc<AtomicUsize>,
+    tasks: Arc<Mutex<HashMap<Vec<u8>, Task>>>,
+    logs: Arc<Mutex<Vec<String>>>,
+    devices: Arc<Mutex<Vec<Device>>>,
+    tasks_released: Arc<watch::Sender<bool>>,
+}
+
+impl RunningStandIn {
+    pub(crate) fn get_port(&self) -> u16 {
+        self.address.port()
+    }
+
+    /// Makes the upcoming signing, task and group requests fail
+    /// as if the server was unreachable
+    ///
+    /// # Arguments
+    ///
+    /// * `requests` - the number of failing requests
+    pub(crate) fn interrupt(&self, requests: usize) {
+        self.interrupted_requests.store(requests, Ordering::SeqCst);
+    }
+
+    /// Makes the upcoming signing, decryption and group requests create their tasks,
+    /// but fail as if the connection dropped before the response was sent
+    ///
+    /// # Arguments
+    ///
+    /// * `requests` - the number of requests losing their response
+    pub(crate) fn lose_responses(&self, requests: usize) {
+        self.lost_responses.store(requests, Ordering::SeqCst);
+    }
+
+    /// Returns the number of tasks created so far
+    /// Lets the held tasks take their last scripted state
+    pub(crate) fn release_tasks(&self) {
+        self.tasks_released.send_replace(true);
+    }
+
+    pub(crate) fn get_task_count(&self) -> usize {
+        self.tasks.lock().unwrap().len()
+    }
+
+    /// Returns the messages received by the log endpoint so far
+    pub(crate) fn get_logs(&self) -> Vec<String> {
+        self.logs.lock().unwrap().clone()
+    }
+
+    /// Returns the identifiers of the registered devices in order of registration
+    pub(crate) fn get_device_ids(&self) -> Vec<Vec<u8>> {
+        self.devices
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|device| device.identifier.clone())
+            .collect()
+    }
+
+    pub(crate) fn get_ca_certificate(&self) -> &[u8] {
+        &self.ca_certificate
+    }
+
+    /// Returns the endpoint of the server, connecting by address
+    /// and verifying the server certificate against its name
+    ///
+    /// # Arguments
+    ///
+    /// * `certificate_path` - the path the CA certificate is stored at
+    pub(crate) fn get_endpoint(&self, certificate_path: String) -> CommunicatorEndpoint {
+        CommunicatorEndpoint::new(STAND_IN_ADDRESS.into(), certificate_path)
+            .with_port(Some(self.get_port()))
+            .with_tls_server_name(Some(STAND_IN_SERVER_NAME.into()))
+            .with_timeouts(Some(5), Some(5), Some(10))
+            .with_retry_policy(Some(3), Some(10), Some(50))
+    }
+
+    pub(crate) fn get_tls_server_name(&self) -> &str {
+        STAND_IN_SERVER_NAME
+    }
+
+    pub(crate) fn get_address(&self) -> &str {
+        STAND_IN_ADDRESS
+    }
+}
+
+type TaskStream = Pin<Box<dyn Stream<Item = Result<Task, Status>> + Send>>;
+
+#[async_trait]
+impl Mpc for MeesignStandIn {
+    type SubscribeUpdatesStream = TaskStream;
+
+    async fn get_server_info(
+        &self,
+        _request: Request<ServerInfoRequest>,
+    ) -> Result<Response<ServerInfo>, Status> {
+        if !self.reports_server_info {
+            return Err(Status::unimplemented("GetServerInfo is not implemented"));
+        }
+        Ok(Response::new(ServerInfo {
+            version: self.version.clone(),
+        }))
+    }
+
+    async fn register(
+        &self,
+        request: Request<RegistrationRequest>,
+    ) -> Result<Response<RegistrationResponse>, Status> {
+        let request = request.into_inner();
+        let certificate = self
+            .certificate_authority
+            .issue_device_certificate(&request.csr)?;
+        let device_id = Uuid::new_v4().as_bytes().to_vec();
+        self.devices.lock().unwrap().push(Device {
+            identifier: device_id.clone(),
+            name: request.name,
+            certificate: certificate.clone(),
+            last_active: 0,
+        });
+        Ok(Response::new(RegistrationResponse {
+            device_id,
+            certificate,
+        }))
+    }
+
+    async fn sign(&self, request: Request<SignRequest>) -> Result<Response<Task>, Status> {
+        self.check_availability()?;
+        let request = request.into_inner();
+        let group = self.find_group(&request.group_id)?;
+
+        let task_type = match group.key_type {
+            GroupKeyType::SignChallenge => TaskType::SignChallenge,
+            GroupKeyType::SignPdf => TaskType::SignPdf,
+            GroupKeyType::Decrypt => {
+                return Err(Status::invalid_argument("The group can't sign"));
+            }
+        };
+        let signature = group.sign(&request.data);
+        let task = self.create_task(task_type, request.encode_to_vec(), signature);
+        if take_one(&self.lost_responses) {
+            return Err(Status::unavailable("Connection interrupted"));
+        }
+        Ok(Response::new(task))
+    }
+
+    async fn decrypt(&self, request: Request<DecryptRequest>) -> Result<Response<Task>, Status> {
+        self.check_availability()?;
+        let request = request.into_inner();
+        let group = self.find_group(&request.group_id)?;
+        if group.key_type != GroupKeyType::Decrypt {
+            return Err(Status::invalid_argument("The group can't decrypt"));
+        }
+
+        let plaintext = group
+            .decrypt(&request.data)
+            .ok_or_else(|| Status::invalid_argument("Invalid ciphertext"))?;
+        let task = self.create_task(TaskType::Decrypt, request.encode_to_vec(), plaintext);
+        if take_one(&self.lost_responses) {
+            return Err(Status::unavailable("Connection interrupted"));
+        }
+        Ok(Response::new(task))
+    }
+
+    async fn derive_key(
+        &self,
+        request: Request<DeriveKeyRequest>,
+    ) -> Result<Response<Task>, Status> {
+        if !self.supports_key_agreement {
+            return Err(Status::unimplemented("DeriveKey is not implemented"));
+        }
+        self.check_availability()?;
+        let request = request.into_inner();
+        let group = self.find_group(&request.group_id)?;
+        if group.key_type != GroupKeyType::Decrypt {
+            return Err(Status::invalid_argument("The group can't derive keys"));
+        }
+
+        let shared_secret = group
+            .derive_shared_secret(&request.public_key)
+            .ok_or_else(|| Status::invalid_argument("Invalid public key"))?;
+        let task = self.create_task(TaskType::DeriveKey, request.encode_to_vec(), shared_secret);
+        Ok(Response::new(task))
+    }
+
+    /// Generates the group's key locally, the group is available right away
+    /// and its identifier is the result of the task
+    async fn group(&self, request: Request<GroupRequest>) -> Result<Response<Task>, Status> {
+        self.check_availability()?;
+        let request = request.into_inner();
+        if request.threshold == 0 || request.threshold as usize > request.device_ids.len() {
+            return Err(Status::invalid_argument("Invalid threshold"));
+        }
+        let key_type = get_group_key_type(request.key_type)
+            .ok_or_else(|| Status::invalid_argument("Unknown key type"))?;
+        let curve = get_group_curve(request.protocol, request.curve, key_type)
+            .ok_or_else(|| Status::invalid_argument("Unsupported protocol"))?;
+
+        let group = StandInGroup {
+            threshold: request.threshold,
+            device_ids: request.device_ids.clone(),
+            ..StandInGroup::new(request.name.clone(), key_type, curve)
+        };
+        let group_id = group.get_identifier();
+        self.groups.lock().unwrap().push(group);
+        let task = self.create_task(TaskType::Group, request.encode_to_vec(), group_id);
+        if take_one(&self.lost_responses) {
+            return Err(Status::unavailable("Connection interrupted"));
+        }
+        Ok(Response::new(task))
+    }
+
+    async fn get_task(&self, request: Request<TaskRequest>) -> Result<Response<Task>, Status> {
+        self.check_availability()?;
+        let tasks = self.tasks.lock().unwrap();
+        let task = tasks
+            .get(&request.get_ref().task_id)
+            .ok_or_else(|| Status::not_found("Unknown task"))?;
+        Ok(Response::new(task.clone()))
+    }
+
+    async fn update_task(&self, _request: Request<TaskUpdate>) -> Result<Response<Resp>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    async fn decide_task(&self, _request: Request<TaskDecision>) -> Result<Response<Resp>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    async fn acknowledge_task(
+        &self,
+        _request: Request<TaskAcknowledgement>,
+    ) -> Result<Response<Resp>, Status> {
+        Err(Status::unimplemented(""))
+    }
+
+    async fn get_tasks(&self, _request: Request<TasksRequest>) -> Result<Response<Tasks>, Status> {
+        self.check_availability()?;
+        let tasks = self
+            .tasks
+            .lock()
+            .unwrap()
+            .values()
+            .map(|task| Task {
+                request: None,
+                ..task.clone()
+            })
+            .collect();
+        Ok(Response::new(Tasks { tasks }))
+    }
+
+    async fn get_groups(
+        &self,
+        _request: Request<GroupsRequest>,
+    ) -> Result<Response<Groups>, Status> {
+        self.check_availability()?;
+        let groups = self
+            .groups
+            .lock()
+            .unwrap()
+            .iter()
+            .map(StandInGroup::to_proto)
+            .collect();
+        Ok(Response::new(Groups { groups }))
+    }
+
+    async fn get_devices(
+        &self,
+        _request: Request<DevicesRequest>,
+    ) -> Result<Response<Devices>, Status> {
+        self.check_availability()?;
+        let devices = self.devices.lock().unwrap().clone();
+        Ok(Response::new(Devices { devices }))
+    }
+
+    async fn log(&self, request: Request<LogRequest>) -> Result<Response<Resp>, Status> {
+        self.logs.lock().unwrap().push(request.into_inner().message);
+        Ok(Response::new(Resp {
+            message: "OK".into(),
+        }))
+    }
+
+    async fn subscribe_updates(
+        &self,
+        request: Request<SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeUpdatesStream>, Status> {
+        if !self.supports_streaming {
+            return Err(Status::unimplemented("Streaming is not supported"));
+        }
+        if request.peer_certs().is_none() {
+            return Err(Status::unauthenticated("Device certificate is required"));
+        }
+        let updates = BroadcastStream::new(self.updates.subscribe())
+            .filter_map(|update| update.ok())
+            .map(Ok);
+        Ok(Response::new(Box::pin(updates)))
+    }
+}
+
+/// Decrements the counter unless it is zero, returns whether it was decremented
+fn take_one(counter: &AtomicUsize) -> bool {
+    counter
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+            count.checked_sub(1)
+        })
+        .is_ok()
+}
+
+/// Verifies a signature created by a stand-in P-256 group
+///
+/// # Arguments
+///
+/// * `group_id` - the identifier of the group, i.e., its public key
+/// * `digest` - the signed digest
+/// * `signature` - the signature, as the concatenated `r` and `s` values
+pub(crate) fn verify_signature(group_id: &GroupId, digest: &RequestData, signature: &[u8]) -> bool {
+    verify_signature_on_curve(GroupCurve::P256, group_id, digest, signature)
+}
+
+/// Verifies a signature created by a stand-in group on the given curve
+///
+/// # Arguments
+///
+/// * `curve` - the curve of the group's key
+/// * `group_id` - the identifier of the group, i.e., its public key
+/// * `data` - the signed digest, or the signed message for Ed25519 groups
+/// * `signature` - the signature, as the concatenated `r` and `s` values
+pub(crate) fn verify_signature_on_curve(
+    curve: GroupCurve,
+    group_id: &GroupId,
+    data: &RequestData,
+    signature: &[u8],
+) -> bool {
+    let curve_name = match curve {
+        GroupCurve::P256 => Nid::X9_62_PRIME256V1,
+        GroupCurve::Secp256k1 => Nid::SECP256K1,
+        GroupCurve::Ed25519 => {
+            let public_key = PKey::public_key_from_raw_bytes(group_id, Id::ED25519).unwrap();
+            let mut verifier = Verifier::new_without_digest(&public_key).unwrap();
+            return verifier.verify_oneshot(signature, data).unwrap();
+        }
+    };
+    let group = EcGroup::from_curve_name(curve_name).unwrap();
+    let mut context = BigNumContext::new().unwrap();
+    let point = EcPoint::from_bytes(&group, group_id, &mut context).unwrap();
+    let public_key: EcKey<Public> = EcKey::from_public_key(&group, &point).unwrap();
+    let (r, s) = signature.split_at(signature.len() / 2);
+    let signature = EcdsaSig::from_private_components(
+        BigNum::from_slice(r).unwrap(),
+        BigNum::from_slice(s).unwrap(),
+    )
+    .unwrap();
+    signature.verify(data, &public_key).unwrap()
+}
+
+/// Encrypts the plaintext for a stand-in decryption group using ECIES.
+/// The ciphertext consists of the uncompressed ephemeral public key,
+/// the AES-256-GCM nonce, the encrypted plaintext and the tag.
+/// The key is the SHA-256 of the x coordinate of the shared point.
+///
+/// # Arguments
+///
+/// * `group_id` - the identifier of the group, i.e., its public key
+/// * `plaintext` - the data to be encrypted
+pub(crate) fn encrypt_for_group(group_id: &GroupId, plaintext: &[u8]) -> Vec<u8> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+    let mut context = BigNumContext::new().unwrap();
+    let group_key = EcPoint::from_bytes(&group, group_id, &mut context).unwrap();
+    let ephemeral_key = generate_key();
+    let key = derive_ecies_key(&ephemeral_key, &group_key);
+    let mut nonce = [0; ECIES_NONCE_LENGTH];
+    rand_bytes(&mut nonce).unwrap();
+    let mut tag = [0; ECIES_TAG_LENGTH];
+    let encrypted = encrypt_aead(
+        Cipher::aes_256_gcm(),
+        &key,
+        Some(&nonce),
+        &[],
+        plaintext,
+        &mut tag,
+    )
+    .unwrap();
+
+    let mut ciphertext = ephemeral_key
+        .public_key()
+        .to_bytes(&group, PointConversionForm::UNCOMPRESSED, &mut context)
+        .unwrap();
+    ciphertext.extend(nonce);
+    ciphertext.extend(encrypted);
+    ciphertext.extend(tag);
+    ciphertext
+}
+
+/// Derives the ECIES key from the shared point of the private and the public key
+fn derive_ecies_key(private_key: &EcKey<Private>, public_key: &EcPoint) -> Vec<u8> {
+    hash(
+        MessageDigest::sha256(),
+        &get_shared_secret(private_key, public_key),
+    )
+    .unwrap()
+    .to_vec()
+}
+
+/// Returns the x coordinate of the shared point of the private and the public key
+fn get_shared_secret(private_key: &EcKey<Private>, public_key: &EcPoint) -> Vec<u8> {
+    let group = private_key.group();
+    let mut context = BigNumContext::new().unwrap();
+    let mut shared_point = EcPoint::new(group).unwrap();
+    shared_point
+        .mul(group, public_key, private_key.private_key(), &context)
+        .unwrap();
+    let mut x = BigNum::new().unwrap();
+    let mut y = BigNum::new().unwrap();
+    shared_point
+        .affine_coordinates(group, &mut x, &mut y, &mut context)
+        .unwrap();
+    x.to_vec_padded(SIGNATURE_COORDINATE_LENGTH).unwrap()
+}
+
+fn generate_key() -> EcKey<Private> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+    EcKey::generate(&group).unwrap()
+}
+
+fn generate_group_key(curve: GroupCurve) -> PKey<Private> {
+    let curve_name = match curve {
+        GroupCurve::P256 => Nid::X9_62_PRIME256V1,
+        GroupCurve::Secp256k1 => Nid::SECP256K1,
+        GroupCurve::Ed25519 => return PKey::generate_ed25519().unwrap(),
+    };
+    let group = EcGroup::from_curve_name(curve_name).unwrap();
+    PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+}
+
+fn build_name(common_name: &str) -> openssl::x509::X509Name {
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_nid(Nid::COMMONNAME, common_name)
+        .unwrap();
+    name.build()
+}
+
+/// Prepares an unsigned certificate valid for a day
+fn build_certificate<T>(
+    subject: &X509NameRef,
+    public_key: &PKeyRef<T>,
+    issuer: &X509NameRef,
+) -> openssl::x509::X509Builder
+where
+    T: openssl::pkey::HasPublic,
+{
+    let mut certificate = X509::builder().unwrap();
+    certificate.set_version(2).unwrap();
+    let serial_number = BigNum::from_slice(Uuid::new_v4().as_bytes())
+        .unwrap()
+        .to_asn1_integer()
+        .unwrap();
+    certificate.set_serial_number(&serial_number).unwrap();
+    certificate.set_subject_name(subject).unwrap();
+    certificate.set_issuer_name(issuer).unwrap();
+    certificate.set_pubkey(public_key).unwrap();
+    certificate
+        .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    certificate
+        .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+        .unwrap();
+    certificate
+}
@@ -1,6 +1,9 @@
 use super::{
-    communicator_error::CommunicatorError, AuthResponse, ByteVector, Communicator, Group, GroupId,
-    RequestData, TaskId,
+    communicator_error::CommunicatorError,
+    group::{GroupCurve, GroupKeyType, GroupParameters},
+    request_context::RequestContext,
+    server_info::ServerInfo,
+    AuthResponse, ByteVector, Communicator, Group, GroupId, RequestData, TaskId,
 };
 use aes::cipher::generic_array::GenericArray;
 use p256::ecdsa::{signature::hazmat::PrehashSigner, SigningKey, VerifyingKey};
@@ -8,17 +11,22 @@ use tonic::async_trait;
 
 type GroupPublicKey = ByteVector;
 
+static MOCKED_SERVER_VERSION: &str = "0.3.0";
+static MOCKED_SERVER_ADDRESS: &str = "mocked";
+
 /// MockedMeesign is used for integration tests in CI/CD.
 /// The struct should never be used to perform cryptographic operations.
 /// This whole module compiles only for debug builds to ensure the security.
 ///
 /// Currently, it mockes MeeSign by providing a single authentication group,
 /// and signing all authentication requests.
+#[derive(Clone)]
 pub(crate) struct MockedMeesign {
     group_name: String,
     group_public_key: GroupPublicKey,
     private_key: SigningKey,
     signature: Option<AuthResponse>,
+    server_info: ServerInfo,
 }
 
 impl MockedMeesign {
@@ -38,34 +46,83 @@ impl MockedMeesign {
             private_key,
             group_public_key,
             signature: None,
+            server_info: ServerInfo::from_reported_version(MOCKED_SERVER_VERSION).unwrap(),
         }
     }
 }
 
 #[async_trait]
 impl Communicator for MockedMeesign {
+    fn get_server_info(&self) -> &ServerInfo {
+        &self.server_info
+    }
+
+    fn get_server_address(&self) -> String {
+        MOCKED_SERVER_ADDRESS.into()
+    }
+
     async fn get_groups(&mut self) -> Result<Vec<Group>, CommunicatorError> {
         Ok(vec![Group::new(
             self.group_public_key.clone(),
             self.group_name.clone(),
+            GroupKeyType::SignChallenge,
+            GroupCurve::P256,
         )])
     }
 
     async fn send_auth_request(
         &mut self,
         _group_id: GroupId,
+        _key_type: GroupKeyType,
         data: RequestData,
-        _request_originator: Option<String>,
+        _request_context: RequestContext,
     ) -> Result<TaskId, CommunicatorError> {
         let (signature, _) = self.private_key.sign_prehash(&data)?;
         self.signature = Some(signature.to_vec());
         Ok(vec![])
     }
 
+    async fn send_decryption_request(
+        &mut self,
+        _group_id: GroupId,
+        _data: RequestData,
+        _request_context: RequestContext,
+    ) -> Result<TaskId, CommunicatorError> {
+        // the mocked group can only sign
+        Err(CommunicatorError::InvalidRequestData)
+    }
+
+    async fn send_key_agreement_request(
+        &mut self,
+        _group_id: GroupId,
+        _public_key: RequestData,
+        _request_context: RequestContext,
+    ) -> Result<TaskId, CommunicatorError> {
+        // the mocked group can only sign
+        Err(CommunicatorError::InvalidRequestData)
+    }
+
+    async fn send_group_request(
+        &mut self,
+        _parameters: GroupParameters,
+    ) -> Result<TaskId, CommunicatorError> {
+        // the mocked communicator provides only its fixed group
+        Err(CommunicatorError::InvalidRequestData)
+    }
+
     async fn get_auth_response(
         &mut self,
         _task_id: TaskId,
     ) -> Result<Option<AuthResponse>, CommunicatorError> {
         Ok(self.signature.clone())
     }
+
+    async fn cancel_task(&mut self, _task_id: TaskId) -> Result<(), CommunicatorError> {
+        self.signature = None;
+        Ok(())
+    }
+
+    fn clone_box(&self) -> Box<dyn Communicator> {
+        Box::new(self.clone())
+    }
 }
@@ -0,0 +1,47 @@
+mod ssh_userauth;
+mod webauthn;
+mod x509;
+
+use self::{ssh_userauth::SshUserauthParser, webauthn::WebAuthnParser, x509::X509Parser};
+
+/// Recognizes a kind of data to be signed and summarizes it for the approvers
+pub(crate) trait PayloadParser: Send + Sync {
+    /// Returns a human-readable summary of the payload,
+    /// `None` if the payload is not of the recognized kind
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - the full data to be signed
+    fn summarize(&self, payload: &[u8]) -> Option<String>;
+}
+
+/// Summarizes the data to be signed using the first parser recognizing it.
+/// New kinds of data are supported by adding a parser to the list.
+pub(crate) struct PayloadSummarizer {
+    parsers: Vec<Box<dyn PayloadParser>>,
+}
+
+impl PayloadSummarizer {
+    /// Creates a summarizer recognizing SSH userauth requests,
+    /// WebAuthn authenticator data, and X.509 certificates and CSRs
+    pub(crate) fn new() -> Self {
+        Self {
+            parsers: vec![
+                Box::new(SshUserauthParser {}),
+                Box::new(WebAuthnParser {}),
+                Box::new(X509Parser {}),
+            ],
+        }
+    }
+
+    /// Returns a summary of the payload, if any of the parsers recognizes it
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - the full data to be signed
+    pub(crate) fn summarize(&self, payload: &[u8]) -> Option<String> {
+        self.parsers
+            .iter()
+            .find_map(|parser| parser.summarize(payload))
+    }
+}
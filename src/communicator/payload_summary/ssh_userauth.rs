@@ -0,0 +1,109 @@
+use super::PayloadParser;
+
+static SSH_MSG_USERAUTH_REQUEST: u8 = 50;
+static PUBLICKEY_METHOD: &[u8] = b"publickey";
+
+/// Recognizes the data signed during SSH public key authentication, see RFC 4252, section 7
+pub(super) struct SshUserauthParser {}
+
+impl PayloadParser for SshUserauthParser {
+    fn summarize(&self, payload: &[u8]) -> Option<String> {
+        let mut reader = SshReader { data: payload };
+        let _session_id = reader.read_string()?;
+        if reader.read_byte()? != SSH_MSG_USERAUTH_REQUEST {
+            return None;
+        }
+        let user = reader.read_utf8()?;
+        let service = reader.read_utf8()?;
+        if reader.read_string()? != PUBLICKEY_METHOD || reader.read_byte()? != 1 {
+            return None;
+        }
+        let algorithm = reader.read_utf8()?;
+        let _public_key = reader.read_string()?;
+        if !reader.data.is_empty() {
+            return None;
+        }
+        Some(format!("SSH login as {user} ({service}, {algorithm} key)"))
+    }
+}
+
+/// Reads values encoded as defined by RFC 4251, section 5
+struct SshReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> SshReader<'a> {
+    fn read_byte(&mut self) -> Option<u8> {
+        let (&byte, rest) = self.data.split_first()?;
+        self.data = rest;
+        Some(byte)
+    }
+
+    fn read_string(&mut self) -> Option<&'a [u8]> {
+        let length = self.data.get(..4)?;
+        let length = u32::from_be_bytes(length.try_into().ok()?) as usize;
+        // the length is untrusted, it may overflow on 32-bit targets
+        let end = 4usize.checked_add(length)?;
+        let string = self.data.get(4..end)?;
+        self.data = &self.data[end..];
+        Some(string)
+    }
+
+    fn read_utf8(&mut self) -> Option<&'a str> {
+        let string = std::str::from_utf8(self.read_string()?).ok()?;
+        let is_printable = !string.is_empty() && string.chars().all(|c| !c.is_control());
+        is_printable.then_some(string)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn push_string(data: &mut Vec<u8>, string: &[u8]) {
+        data.extend((string.len() as u32).to_be_bytes());
+        data.extend(string);
+    }
+
+    fn get_userauth_request(method: &[u8]) -> Vec<u8> {
+        let mut data = vec![];
+        push_string(&mut data, &[0x5a; 32]);
+        data.push(SSH_MSG_USERAUTH_REQUEST);
+        push_string(&mut data, b"git");
+        push_string(&mut data, b"ssh-connection");
+        push_string(&mut data, method);
+        data.push(1);
+        push_string(&mut data, b"ecdsa-sha2-nistp256");
+        push_string(&mut data, &[0x04; 65]);
+        data
+    }
+
+    #[test]
+    fn given_userauth_request_user_service_and_algorithm_are_shown() {
+        let summary = SshUserauthParser {}.summarize(&get_userauth_request(PUBLICKEY_METHOD));
+        assert_eq!(
+            summary.as_deref(),
+            Some("SSH login as git (ssh-connection, ecdsa-sha2-nistp256 key)")
+        );
+    }
+
+    #[test]
+    fn given_other_data_they_are_not_recognized() {
+        let parser = SshUserauthParser {};
+        assert!(parser
+            .summarize(&get_userauth_request(b"password"))
+            .is_none());
+        assert!(parser.summarize(&[0xab; 32]).is_none());
+    }
+
+    #[test]
+    fn given_string_length_beyond_the_data_it_is_not_recognized() {
+        let mut data = vec![];
+        push_string(&mut data, &[0x5a; 32]);
+        data.push(SSH_MSG_USERAUTH_REQUEST);
+        data.extend(u32::MAX.to_be_bytes());
+        data.extend(b"git");
+
+        assert!(SshUserauthParser {}.summarize(&data).is_none());
+    }
+}
@@ -0,0 +1,86 @@
+use super::PayloadParser;
+
+static RP_ID_HASH_LENGTH: usize = 32;
+static AUTHENTICATOR_DATA_LENGTH: usize = RP_ID_HASH_LENGTH + 1 + 4;
+static CLIENT_DATA_HASH_LENGTH: usize = 32;
+/// Number of bytes of the RP ID hash shown to the approvers
+static SHORT_HASH_LENGTH: usize = 8;
+
+static USER_PRESENT: u8 = 0x01;
+static USER_VERIFIED: u8 = 0x04;
+static BACKUP_ELIGIBLE: u8 = 0x08;
+static BACKED_UP: u8 = 0x10;
+/// Bits that are never set in assertions, i.e., the reserved ones,
+/// attested credential data and extension data
+static UNEXPECTED_FLAGS: u8 = 0x02 | 0x20 | 0x40 | 0x80;
+
+/// Recognizes the data signed by a WebAuthn assertion, i.e., the authenticator data
+/// followed by the client data hash
+pub(super) struct WebAuthnParser {}
+
+impl PayloadParser for WebAuthnParser {
+    fn summarize(&self, payload: &[u8]) -> Option<String> {
+        if payload.len() != AUTHENTICATOR_DATA_LENGTH + CLIENT_DATA_HASH_LENGTH {
+            return None;
+        }
+        let (rp_id_hash, rest) = payload.split_at(RP_ID_HASH_LENGTH);
+        let flags = rest[0];
+        if flags & UNEXPECTED_FLAGS != 0 || (flags & BACKED_UP != 0 && flags & BACKUP_ELIGIBLE == 0)
+        {
+            return None;
+        }
+        let sign_count = u32::from_be_bytes(rest[1..5].try_into().ok()?);
+
+        let flag_names = [
+            (USER_PRESENT, "user present"),
+            (USER_VERIFIED, "user verified"),
+        ]
+        .into_iter()
+        .filter(|(flag, _)| flags & flag != 0)
+        .map(|(_, name)| name)
+        .collect::<Vec<&str>>();
+        let flag_names = match flag_names.is_empty() {
+            true => "no user interaction".into(),
+            false => flag_names.join(", "),
+        };
+        Some(format!(
+            "WebAuthn assertion for RP ID hash {}, {flag_names}, sign count {sign_count}",
+            hex::encode(&rp_id_hash[..SHORT_HASH_LENGTH])
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use openssl::sha::sha256;
+
+    use super::*;
+
+    fn get_assertion_data(flags: u8) -> Vec<u8> {
+        let mut data = sha256(b"example.com").to_vec();
+        data.push(flags);
+        data.extend(7u32.to_be_bytes());
+        data.extend([0xcd; CLIENT_DATA_HASH_LENGTH]);
+        data
+    }
+
+    #[test]
+    fn given_assertion_data_rp_id_hash_and_flags_are_shown() {
+        let summary =
+            WebAuthnParser {}.summarize(&get_assertion_data(USER_PRESENT | USER_VERIFIED));
+        let rp_id_hash = hex::encode(&sha256(b"example.com")[..SHORT_HASH_LENGTH]);
+        assert_eq!(
+            summary,
+            Some(format!(
+                "WebAuthn assertion for RP ID hash {rp_id_hash}, user present, user verified, sign count 7"
+            ))
+        );
+    }
+
+    #[test]
+    fn given_attested_credential_flag_data_are_not_recognized() {
+        assert!(WebAuthnParser {}
+            .summarize(&get_assertion_data(USER_PRESENT | 0x40))
+            .is_none());
+    }
+}
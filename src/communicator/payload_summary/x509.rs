@@ -0,0 +1,188 @@
+use openssl::x509::{X509NameRef, X509Req, X509};
+
+use super::PayloadParser;
+
+static DER_SEQUENCE_TAG: u8 = 0x30;
+/// AlgorithmIdentifier of ecdsa-with-SHA256, completes the structures to be parsed,
+/// the signature itself is never verified
+static SIGNATURE_ALGORITHM: &[u8] = &[
+    0x30, 0x0a, 0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02,
+];
+/// An empty BIT STRING standing in for the signature
+static EMPTY_SIGNATURE: &[u8] = &[0x03, 0x01, 0x00];
+
+/// Recognizes the to-be-signed part of an X.509 certificate (TBSCertificate)
+/// and of a certificate signing request (CertificationRequestInfo)
+pub(super) struct X509Parser {}
+
+impl PayloadParser for X509Parser {
+    fn summarize(&self, payload: &[u8]) -> Option<String> {
+        let (element, rest) = read_der_element(payload)?;
+        if element.first() != Some(&DER_SEQUENCE_TAG) || !rest.is_empty() {
+            return None;
+        }
+        // the signed structures are completed, so that they can be parsed as a whole
+        let signed_structure = wrap_signed_structure(payload);
+        if let Ok(certificate) = X509::from_der(&signed_structure) {
+            return Some(format!(
+                "X.509 certificate for {} issued by {}",
+                format_name(certificate.subject_name()),
+                format_name(certificate.issuer_name())
+            ));
+        }
+        if let Ok(request) = X509Req::from_der(&signed_structure) {
+            return Some(format!(
+                "certificate signing request for {}",
+                format_name(request.subject_name())
+            ));
+        }
+        None
+    }
+}
+
+/// Splits off the first DER element, returning the element, including its header,
+/// and the remaining data
+fn read_der_element(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let first_length_byte = *data.get(1)?;
+    let (header_length, content_length) = match first_length_byte {
+        length if length < 0x80 => (2, length as usize),
+        0x81..=0x84 => {
+            let length_bytes = (first_length_byte & 0x7f) as usize;
+            let length = data
+                .get(2..2 + length_bytes)?
+                .iter()
+                .fold(0usize, |length, &byte| (length << 8) | byte as usize);
+            (2 + length_bytes, length)
+        }
+        _ => return None,
+    };
+    let element_length = header_length.checked_add(content_length)?;
+    (data.len() >= element_length).then(|| data.split_at(element_length))
+}
+
+/// Wraps the to-be-signed structure into a SEQUENCE together with
+/// a signature algorithm and an empty signature
+fn wrap_signed_structure(to_be_signed: &[u8]) -> Vec<u8> {
+    let content_length = to_be_signed.len() + SIGNATURE_ALGORITHM.len() + EMPTY_SIGNATURE.len();
+    let mut structure = vec![DER_SEQUENCE_TAG];
+    structure.extend(encode_der_length(content_length));
+    structure.extend(to_be_signed);
+    structure.extend(SIGNATURE_ALGORITHM);
+    structure.extend(EMPTY_SIGNATURE);
+    structure
+}
+
+fn encode_der_length(length: usize) -> Vec<u8> {
+    if length < 0x80 {
+        return vec![length as u8];
+    }
+    let length_bytes: Vec<u8> = length
+        .to_be_bytes()
+        .into_iter()
+        .skip_while(|&byte| byte == 0)
+        .collect();
+    let mut encoded = vec![0x80 | length_bytes.len() as u8];
+    encoded.extend(length_bytes);
+    encoded
+}
+
+/// Formats the name as comma-separated `key=value` pairs, e.g., `CN=alice, O=Example`
+fn format_name(name: &X509NameRef) -> String {
+    name.entries()
+        .map(|entry| {
+            let key = entry.object().nid().short_name().unwrap_or("?");
+            let value = entry
+                .data()
+                .as_utf8()
+                .map(|value| value.to_string())
+                .unwrap_or_default();
+            format!("{key}={value}")
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod test {
+    use openssl::{
+        asn1::Asn1Time,
+        ec::{EcGroup, EcKey},
+        hash::MessageDigest,
+        nid::Nid,
+        pkey::{PKey, Private},
+        x509::{X509Name, X509NameBuilder, X509ReqBuilder},
+    };
+
+    use super::*;
+
+    fn get_key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+    }
+
+    fn get_name(common_name: &str) -> X509Name {
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_nid(Nid::COMMONNAME, common_name)
+            .unwrap();
+        name.append_entry_by_nid(Nid::ORGANIZATIONNAME, "Example")
+            .unwrap();
+        name.build()
+    }
+
+    /// Returns the first element of the signed structure, i.e., the to-be-signed part
+    fn get_to_be_signed(signed_structure: &[u8]) -> Vec<u8> {
+        let header_length = match signed_structure[1] {
+            length if length < 0x80 => 2,
+            length => 2 + (length & 0x7f) as usize,
+        };
+        let (to_be_signed, _) = read_der_element(&signed_structure[header_length..]).unwrap();
+        to_be_signed.to_vec()
+    }
+
+    #[test]
+    fn given_tbs_certificate_subject_and_issuer_are_shown() {
+        let key = get_key();
+        let mut builder = X509::builder().unwrap();
+        builder.set_subject_name(&get_name("alice")).unwrap();
+        builder.set_issuer_name(&get_name("Example CA")).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        let certificate = builder.build().to_der().unwrap();
+
+        let summary = X509Parser {}.summarize(&get_to_be_signed(&certificate));
+
+        assert_eq!(
+            summary.as_deref(),
+            Some("X.509 certificate for CN=alice, O=Example issued by CN=Example CA, O=Example")
+        );
+    }
+
+    #[test]
+    fn given_certification_request_info_subject_is_shown() {
+        let key = get_key();
+        let mut builder = X509ReqBuilder::new().unwrap();
+        builder.set_subject_name(&get_name("alice")).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        let request = builder.build().to_der().unwrap();
+
+        let summary = X509Parser {}.summarize(&get_to_be_signed(&request));
+
+        assert_eq!(
+            summary.as_deref(),
+            Some("certificate signing request for CN=alice, O=Example")
+        );
+    }
+
+    #[test]
+    fn given_non_der_data_they_are_not_recognized() {
+        assert!(X509Parser {}.summarize(&[0x30, 0x03, 0x02, 0x01]).is_none());
+        assert!(X509Parser {}.summarize(b"challenge").is_none());
+    }
+}
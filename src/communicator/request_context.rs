@@ -0,0 +1,31 @@
+/// Describes a signing request to the approvers
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct RequestContext {
+    /// Originator of the request, usually the website domain name
+    originator: Option<String>,
+
+    /// Human-readable summary of the data to be signed, e.g., the SSH login being authorized
+    payload_summary: Option<String>,
+}
+
+impl RequestContext {
+    pub(crate) fn new(originator: Option<String>) -> Self {
+        Self {
+            originator,
+            payload_summary: None,
+        }
+    }
+
+    pub(crate) fn with_payload_summary(mut self, payload_summary: Option<String>) -> Self {
+        self.payload_summary = payload_summary;
+        self
+    }
+
+    pub(crate) fn get_originator(&self) -> Option<&str> {
+        self.originator.as_deref()
+    }
+
+    pub(crate) fn get_payload_summary(&self) -> Option<&str> {
+        self.payload_summary.as_deref()
+    }
+}
@@ -0,0 +1,51 @@
+use std::fmt;
+
+use super::group::GroupKeyType;
+
+/// Kind of the task requested from a group
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RequestKind {
+    /// Signs an authentication challenge
+    Authentication,
+    /// Signs a PDF document
+    DocumentSigning,
+    /// Decrypts data encrypted for the group's public key
+    Decryption,
+    /// Multiplies a peer's public key by the group's private key, i.e., ECDH
+    KeyAgreement,
+}
+
+impl RequestKind {
+    /// Returns a byte distinguishing the requests of different kinds with the same data
+    pub(crate) fn to_byte(self) -> u8 {
+        match self {
+            Self::Authentication => 0,
+            Self::DocumentSigning => 1,
+            Self::Decryption => 2,
+            Self::KeyAgreement => 3,
+        }
+    }
+}
+
+/// The kind of the requests a group handles by default
+impl From<GroupKeyType> for RequestKind {
+    fn from(key_type: GroupKeyType) -> Self {
+        match key_type {
+            GroupKeyType::SignChallenge => Self::Authentication,
+            GroupKeyType::SignPdf => Self::DocumentSigning,
+            GroupKeyType::Decrypt => Self::Decryption,
+        }
+    }
+}
+
+impl fmt::Display for RequestKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            Self::Authentication => "authentication request",
+            Self::DocumentSigning => "document signing request",
+            Self::Decryption => "decryption request",
+            Self::KeyAgreement => "key agreement request",
+        };
+        write!(f, "{description}")
+    }
+}
@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+/// Determines how many times and how often an interrupted RPC is retried
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RetryPolicy {
+    /// Maximum number of attempts, including the first one
+    max_attempts: u32,
+
+    /// Delay before the first retry, doubled with each further retry
+    initial_backoff: Duration,
+
+    /// Upper bound of the delay between two attempts
+    max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub(crate) fn new(max_attempts: u32, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            initial_backoff,
+            max_backoff,
+        }
+    }
+
+    /// Returns whether another attempt is allowed
+    ///
+    /// # Arguments
+    ///
+    /// * `attempt` - the zero-based index of the failed attempt
+    pub(crate) fn allows_retry(&self, attempt: u32) -> bool {
+        attempt + 1 < self.max_attempts
+    }
+
+    /// Returns the delay before the next attempt
+    ///
+    /// # Arguments
+    ///
+    /// * `attempt` - the zero-based index of the failed attempt
+    pub(crate) fn get_backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn given_failed_attempts_backoff_doubles_up_to_the_limit() {
+        let policy = RetryPolicy::new(5, Duration::from_millis(100), Duration::from_millis(300));
+        let backoffs: Vec<Duration> = (0..4).map(|attempt| policy.get_backoff(attempt)).collect();
+        assert_eq!(
+            backoffs,
+            vec![
+                Duration::from_millis(100),
+                Duration::from_millis(200),
+                Duration::from_millis(300),
+                Duration::from_millis(300)
+            ]
+        );
+        assert!(policy.allows_retry(3));
+        assert!(!policy.allows_retry(4));
+    }
+
+    #[test]
+    fn given_zero_attempts_the_call_is_still_made_once() {
+        let policy = RetryPolicy::new(0, Duration::ZERO, Duration::ZERO);
+        assert!(!policy.allows_retry(0));
+    }
+}
@@ -0,0 +1,134 @@
+use std::{fmt, str::FromStr};
+
+use super::communicator_error::CommunicatorError;
+
+/// The version shown for servers that don't report their version
+static UNKNOWN_SERVER_VERSION: ServerVersion = ServerVersion::new(0, 0, 0);
+
+/// Semantic version of a server
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct ServerVersion {
+    major: u32,
+    minor: u32,
+    patch: u32,
+}
+
+impl ServerVersion {
+    pub(crate) const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    pub(crate) fn get_major(&self) -> u32 {
+        self.major
+    }
+
+    pub(crate) fn get_minor(&self) -> u32 {
+        self.minor
+    }
+}
+
+impl FromStr for ServerVersion {
+    type Err = CommunicatorError;
+
+    /// Parses versions like `0.3.1`, a leading `v`,
+    /// pre-release and build suffixes are ignored
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        let invalid_version =
+            || CommunicatorError::UnsupportedServer(format!("version {version:?} can't be parsed"));
+        let core = version
+            .trim()
+            .trim_start_matches('v')
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default();
+        let components = core
+            .split('.')
+            .map(|component| component.parse::<u32>().map_err(|_| invalid_version()))
+            .collect::<Result<Vec<u32>, CommunicatorError>>()?;
+        match components[..] {
+            [major, minor, patch] => Ok(Self::new(major, minor, patch)),
+            [major, minor] => Ok(Self::new(major, minor, 0)),
+            _ => Err(invalid_version()),
+        }
+    }
+}
+
+impl fmt::Display for ServerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Information about a server. The features the bridge uses are not derived
+/// from the version, as MeeSign releases don't document which RPCs they add.
+/// An RPC the server doesn't implement is refused with `Unimplemented` when called.
+#[derive(Clone, Debug)]
+pub(crate) struct ServerInfo {
+    version: ServerVersion,
+}
+
+impl ServerInfo {
+    /// Parses the version reported by the server
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - the version reported by the server
+    pub(crate) fn from_reported_version(version: &str) -> Result<Self, CommunicatorError> {
+        Ok(Self {
+            version: version.parse()?,
+        })
+    }
+
+    /// Returns the information about servers released before `GetServerInfo`,
+    /// which don't report their version
+    pub(crate) fn legacy() -> Self {
+        Self {
+            version: UNKNOWN_SERVER_VERSION,
+        }
+    }
+
+    pub(crate) fn get_version(&self) -> ServerVersion {
+        self.version
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("0.3.1", ServerVersion::new(0, 3, 1))]
+    #[case("v0.2.0", ServerVersion::new(0, 2, 0))]
+    #[case("0.4.0-rc.1+build.5", ServerVersion::new(0, 4, 0))]
+    #[case("0.2", ServerVersion::new(0, 2, 0))]
+    fn given_version_string_it_is_parsed(#[case] version: &str, #[case] expected: ServerVersion) {
+        assert_eq!(version.parse::<ServerVersion>().unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("latest")]
+    #[case("0.1.2.3")]
+    #[case("0.x.0")]
+    fn given_malformed_version_it_is_refused(#[case] version: &str) {
+        assert!(version.parse::<ServerVersion>().is_err());
+    }
+
+    #[rstest]
+    #[case("0.0.9", ServerVersion::new(0, 0, 9))]
+    #[case("0.300.0", ServerVersion::new(0, 300, 0))]
+    #[case("2.1.0", ServerVersion::new(2, 1, 0))]
+    fn given_any_well_formed_version_the_server_is_accepted(
+        #[case] version: &str,
+        #[case] expected: ServerVersion,
+    ) {
+        let server_info = ServerInfo::from_reported_version(version).unwrap();
+        assert_eq!(server_info.get_version(), expected);
+    }
+}
@@ -1,51 +1,363 @@
-use crate::configuration::EffectiveInterfaceType;
+use std::{
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use super::communicator_error::CommunicatorError;
+use openssl::sha::sha256;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    configuration::{ConfigurationProviderError, EffectiveInterfaceType},
+    process_identity::{ProcessIdentity, TrustPolicy},
+};
+
+use super::{
+    request_context::RequestContext,
+    request_kind::RequestKind,
+    task_name_template::{Placeholder, TaskNameTemplate},
+};
+
+/// Number of bytes of the data hash shown to the approvers
+static SHORT_HASH_LENGTH: usize = 8;
+
+/// Determines how task names are sent to the communicator
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TaskNameFormat {
+    /// The rendered template only
+    #[default]
+    Text,
+    /// A JSON object holding the rendered template, together with the values
+    /// of the placeholders the template uses, so that clients can render the request
+    /// on their own. The values the template doesn't use are not sent.
+    Json,
+}
+
+impl FromStr for TaskNameFormat {
+    type Err = ConfigurationProviderError;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(ConfigurationProviderError::InvalidFormat),
+        }
+    }
+}
+
+/// Describes the request a task is created for, by the values of the placeholders
+#[derive(Serialize)]
+struct TaskDescription {
+    /// The rendered template
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    interface: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    binary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exe: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exe_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parents: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cmdline: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    originator: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payload: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    host: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cwd: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<String>,
+}
+
+impl TaskDescription {
+    fn get_value(&self, placeholder: Placeholder) -> Option<String> {
+        match placeholder {
+            Placeholder::Interface => self.interface.clone(),
+            Placeholder::RequestKind => self.request_kind.clone(),
+            Placeholder::Binary => self.binary.clone(),
+            Placeholder::Executable => self.exe.clone(),
+            Placeholder::ExecutableHash => self.exe_hash.clone(),
+            Placeholder::Parents => self
+                .parents
+                .as_ref()
+                .map(|parents| parents.join(" < "))
+                .filter(|chain| !chain.is_empty()),
+            Placeholder::Cmdline => self
+                .cmdline
+                .as_ref()
+                .map(|cmdline| cmdline.join(" "))
+                .filter(|cmdline| !cmdline.is_empty()),
+            Placeholder::Originator => self.originator.clone(),
+            Placeholder::Payload => self.payload.clone(),
+            Placeholder::User => self.user.clone(),
+            Placeholder::Uid => self.uid.map(|uid| uid.to_string()),
+            Placeholder::Host => self.host.clone(),
+            Placeholder::Pid => self.pid.map(|pid| pid.to_string()),
+            Placeholder::Cwd => self.cwd.clone(),
+            Placeholder::DataHash => self.data_hash.clone(),
+            Placeholder::Timestamp => self.timestamp.clone(),
+        }
+    }
+
+    /// Leaves out the value of the placeholder
+    fn clear(&mut self, placeholder: Placeholder) {
+        match placeholder {
+            Placeholder::Interface => self.interface = None,
+            Placeholder::RequestKind => self.request_kind = None,
+            Placeholder::Binary => self.binary = None,
+            Placeholder::Executable => self.exe = None,
+            Placeholder::ExecutableHash => self.exe_hash = None,
+            Placeholder::Parents => self.parents = None,
+            Placeholder::Cmdline => self.cmdline = None,
+            Placeholder::Originator => self.originator = None,
+            Placeholder::Payload => self.payload = None,
+            Placeholder::User => self.user = None,
+            Placeholder::Uid => self.uid = None,
+            Placeholder::Host => self.host = None,
+            Placeholder::Pid => self.pid = None,
+            Placeholder::Cwd => self.cwd = None,
+            Placeholder::DataHash => self.data_hash = None,
+            Placeholder::Timestamp => self.timestamp = None,
+        }
+    }
+}
 
 /// Provides names for tasks delegated to the communicator
-pub(crate) struct TaskNameProvider {}
+#[derive(Clone, Default)]
+pub(crate) struct TaskNameProvider {
+    template: TaskNameTemplate,
+    format: TaskNameFormat,
+}
 
 impl TaskNameProvider {
     pub(crate) fn new() -> Self {
-        Self {}
+        Self::default()
+    }
+
+    pub(crate) fn with_template(mut self, template: Option<TaskNameTemplate>) -> Self {
+        self.template = template.unwrap_or_default();
+        self
+    }
+
+    pub(crate) fn with_format(mut self, format: Option<TaskNameFormat>) -> Self {
+        self.format = format.unwrap_or_default();
+        self
     }
 
     /// Returns a task name informing using which interface the authentication
     /// request was created with, which tool, and in some cases, for which domain.
+    /// The wording is determined by the configured template.
     ///
     /// # Arguments
     ///
-    /// * `originator` - Optional originator of the request, e.g., domain name
-    pub(crate) fn get_task_name(&self, originator: Option<String>) -> String {
-        let effective_interface_type = EffectiveInterfaceType::from_environment();
-        let binary_name = get_binary_name().unwrap_or(None);
-
-        let request_info = Some(format!("{effective_interface_type} authentication request"));
-        let mut binary_info = binary_name.map(|binary_name| format!("using {}", binary_name));
-        let originator_info = originator.map(|originator| format!("for {originator}"));
+    /// * `request_kind` - Kind of the task requested from the group
+    /// * `request_context` - Describes the request, e.g., its originator
+    /// * `data` - The data to be signed
+    pub(crate) fn get_task_name(
+        &self,
+        request_kind: RequestKind,
+        request_context: &RequestContext,
+        data: &[u8],
+    ) -> String {
+        let description = self.describe(request_kind, request_context, data);
+        match self.format {
+            TaskNameFormat::Text => description.name,
+            TaskNameFormat::Json => {
+                serde_json::to_string(&description).expect("Task description is serializable")
+            }
+        }
+    }
 
-        if effective_interface_type == EffectiveInterfaceType::WebAuthn {
+    fn describe(
+        &self,
+        request_kind: RequestKind,
+        request_context: &RequestContext,
+        data: &[u8],
+    ) -> TaskDescription {
+        let effective_interface_type = EffectiveInterfaceType::from_environment();
+        let process_identity = ProcessIdentity::current();
+        let process = process_identity.get_process();
+        let binary = match effective_interface_type {
             // there is no need to say, that a WebAuthn task was created using softfido
-            binary_info = None;
+            EffectiveInterfaceType::WebAuthn => None,
+            EffectiveInterfaceType::Cryptoki => {
+                process_identity.get_tool_name(TrustPolicy::from_system_configuration())
+            }
+        };
+        let mut description = TaskDescription {
+            name: String::new(),
+            interface: Some(effective_interface_type.to_string()),
+            request_kind: Some(request_kind.to_string()),
+            binary,
+            exe: process
+                .get_executable()
+                .map(|executable| executable.to_string_lossy().into_owned()),
+            exe_hash: process.get_short_executable_hash().map(String::from),
+            parents: Some(process_identity.get_ancestor_names()),
+            cmdline: Some(process.get_cmdline().to_vec()),
+            originator: request_context.get_originator().map(String::from),
+            payload: request_context.get_payload_summary().map(String::from),
+            user: get_user_name(),
+            uid: process.get_uid(),
+            host: get_hostname(),
+            pid: Some(process.get_pid()),
+            cwd: std::env::current_dir()
+                .ok()
+                .map(|cwd| cwd.to_string_lossy().into_owned()),
+            data_hash: Some(hex::encode(&sha256(data)[..SHORT_HASH_LENGTH])),
+            timestamp: Some(format_timestamp(SystemTime::now())),
         };
+        // the values may hold secrets, e.g., in the arguments, only the values
+        // the administrator chose to show leave the machine
+        for placeholder in Placeholder::ALL {
+            if !self.template.uses(placeholder) {
+                description.clear(placeholder);
+            }
+        }
+        description.name = self
+            .template
+            .render(|placeholder| description.get_value(placeholder));
+        description
+    }
+}
+
+fn get_user_name() -> Option<String> {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok()
+}
+
+#[cfg(unix)]
+fn get_hostname() -> Option<String> {
+    let mut hostname = [0u8; 256];
+    // SAFETY: the buffer is valid for the given length
+    let result = unsafe { libc::gethostname(hostname.as_mut_ptr().cast(), hostname.len()) };
+    if result != 0 {
+        return None;
+    }
+    let length = hostname.iter().position(|&byte| byte == 0)?;
+    String::from_utf8(hostname[..length].to_vec()).ok()
+}
+
+#[cfg(not(unix))]
+fn get_hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
+
+/// Formats the time as `YYYY-MM-DD HH:MM:SS UTC`
+fn format_timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let (year, month, day) = get_civil_date(seconds / 86400);
+    let seconds_of_day = seconds % 86400;
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+/// Converts the number of days since the unix epoch into a (year, month, day) date
+/// of the proleptic Gregorian calendar
+fn get_civil_date(days_since_epoch: u64) -> (u64, u64, u64) {
+    // shifts the epoch to 0000-03-01, so that leap days end the years
+    let days = days_since_epoch + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
 
-        let task_parts = vec![request_info, binary_info, originator_info];
-        let task_parts_joined = task_parts
-            .into_iter()
-            .flatten()
-            .collect::<Vec<String>>()
-            .join(" ");
-        let task_name = format!("{task_parts_joined}.");
-        task_name
-    }
-}
-
-/// Don't rely on this function for security purposes. The filename can be easily spoofed.
-pub(crate) fn get_binary_name() -> Result<Option<String>, CommunicatorError> {
-    let filename = std::env::current_exe()?;
-    let filename = filename
-        .file_name()
-        .map(|filename| filename.to_str().map(|value: &str| value.to_string()))
-        .and_then(|value| value);
-    Ok(filename)
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(0, "1970-01-01 00:00:00 UTC")]
+    #[case(951_782_400, "2000-02-29 00:00:00 UTC")]
+    #[case(1_792_238_399, "2026-10-17 11:59:59 UTC")]
+    fn given_time_it_is_formatted_in_utc(#[case] seconds: u64, #[case] expected: &str) {
+        let time = UNIX_EPOCH + Duration::from_secs(seconds);
+        assert_eq!(format_timestamp(time), expected);
+    }
+
+    #[test]
+    fn given_default_template_originator_and_payload_are_appended() {
+        let provider = TaskNameProvider::new();
+        let request_context = RequestContext::new(Some("example.com".into()))
+            .with_payload_summary(Some("SSH login as git".into()));
+        let name =
+            provider.get_task_name(RequestKind::Authentication, &request_context, b"challenge");
+        // the test binary isn't installed in a trusted directory, so it isn't named
+        assert!(name.starts_with("Cryptoki authentication request for "));
+        assert!(name.ends_with(" for example.com. SSH login as git."));
+    }
+
+    #[test]
+    fn given_json_format_task_name_holds_the_values() {
+        let provider = TaskNameProvider::new()
+            .with_template(Some("{request_kind} {data_hash}".parse().unwrap()))
+            .with_format(Some(TaskNameFormat::Json));
+        let name = provider.get_task_name(
+            RequestKind::DocumentSigning,
+            &RequestContext::default(),
+            b"%PDF-",
+        );
+        let description: serde_json::Value = serde_json::from_str(&name).unwrap();
+        let data_hash = hex::encode(&sha256(b"%PDF-")[..SHORT_HASH_LENGTH]);
+
+        assert_eq!(
+            description["name"],
+            format!("document signing request {data_hash}")
+        );
+        assert_eq!(description["data_hash"], data_hash);
+        assert!(description.get("originator").is_none());
+    }
+
+    #[test]
+    fn given_json_format_values_unused_by_the_template_are_left_out() {
+        let provider = TaskNameProvider::new()
+            .with_template(Some("{request_kind} by {pid}".parse().unwrap()))
+            .with_format(Some(TaskNameFormat::Json));
+        let name =
+            provider.get_task_name(RequestKind::Authentication, &RequestContext::default(), b"");
+        let description: serde_json::Value = serde_json::from_str(&name).unwrap();
+
+        assert_eq!(description["pid"], std::process::id());
+        let mut keys: Vec<&String> = description.as_object().unwrap().keys().collect();
+        keys.sort();
+        assert_eq!(keys, ["name", "pid", "request_kind"]);
+    }
 }
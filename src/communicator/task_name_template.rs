@@ -0,0 +1,298 @@
+use std::{
+    iter::Peekable,
+    str::{Chars, FromStr},
+};
+
+use crate::configuration::ConfigurationProviderError;
+
+/// The template reproducing the default task names, e.g.,
+/// `Cryptoki authentication request using ssh for example.com.`,
+/// followed by the summary of the data to be signed, if there is any
+pub(crate) static DEFAULT_TASK_NAME_TEMPLATE: &str =
+    "{interface} {request_kind}[ using {binary}][ for {originator}].[ {payload}.]";
+
+/// Values that can be used in task name templates
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Placeholder {
+    /// `{interface}` - the interface the request was created with, e.g., `Cryptoki`
+    Interface,
+    /// `{request_kind}` - e.g., `authentication request`
+    RequestKind,
+    /// `{binary}` - the name of the tool that loaded the library, not available for WebAuthn,
+    /// nor for tools not trusted by the process trust policy
+    Binary,
+    /// `{exe}` - the path of the executable that loaded the library
+    Executable,
+    /// `{exe_hash}` - a short hash of the executable that loaded the library
+    ExecutableHash,
+    /// `{parents}` - the chain of the parent processes, e.g., `bash < sshd`
+    Parents,
+    /// `{cmdline}` - the arguments of the process
+    Cmdline,
+    /// `{originator}` - the originator of the request, e.g., a domain name
+    Originator,
+    /// `{payload}` - a summary of the data to be signed, e.g., the SSH login being authorized,
+    /// available only for mechanisms passing the full data
+    Payload,
+    /// `{user}` - the user running the process
+    User,
+    /// `{uid}` - the id of the user running the process
+    Uid,
+    /// `{host}` - the hostname of the machine
+    Host,
+    /// `{pid}` - the id of the process
+    Pid,
+    /// `{cwd}` - the working directory of the process
+    Cwd,
+    /// `{data_hash}` - a short hash of the data to be signed
+    DataHash,
+    /// `{timestamp}` - the UTC time of the request
+    Timestamp,
+}
+
+impl Placeholder {
+    pub(crate) const ALL: [Self; 16] = [
+        Self::Interface,
+        Self::RequestKind,
+        Self::Binary,
+        Self::Executable,
+        Self::ExecutableHash,
+        Self::Parents,
+        Self::Cmdline,
+        Self::Originator,
+        Self::Payload,
+        Self::User,
+        Self::Uid,
+        Self::Host,
+        Self::Pid,
+        Self::Cwd,
+        Self::DataHash,
+        Self::Timestamp,
+    ];
+}
+
+impl FromStr for Placeholder {
+    type Err = ConfigurationProviderError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let placeholder = match name {
+            "interface" => Self::Interface,
+            "request_kind" => Self::RequestKind,
+            "binary" => Self::Binary,
+            "exe" => Self::Executable,
+            "exe_hash" => Self::ExecutableHash,
+            "parents" => Self::Parents,
+            "cmdline" => Self::Cmdline,
+            "originator" => Self::Originator,
+            "payload" => Self::Payload,
+            "user" => Self::User,
+            "uid" => Self::Uid,
+            "host" => Self::Host,
+            "pid" => Self::Pid,
+            "cwd" => Self::Cwd,
+            "data_hash" => Self::DataHash,
+            "timestamp" => Self::Timestamp,
+            _ => {
+                return Err(ConfigurationProviderError::InvalidTaskNameTemplate(
+                    format!("unknown placeholder {{{name}}}"),
+                ))
+            }
+        };
+        Ok(placeholder)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Placeholder(Placeholder),
+    /// Left out if any of its placeholders has no value
+    Optional(Vec<Segment>),
+}
+
+/// A template of task names shown to the approvers.
+///
+/// Placeholders are enclosed in braces, e.g., `{user}@{host}`, see [`Placeholder`]
+/// for the available ones. A part of the template enclosed in square brackets
+/// is left out if any of its placeholders has no value, e.g., `[ for {originator}]`.
+/// Optional parts can't be nested. Literal braces and brackets are written twice,
+/// e.g., `{{` or `[[`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct TaskNameTemplate {
+    segments: Vec<Segment>,
+}
+
+impl TaskNameTemplate {
+    /// Fills in the placeholders
+    ///
+    /// # Arguments
+    ///
+    /// * `get_value` - returns the value of the placeholder, if there is any
+    pub(crate) fn render<F>(&self, get_value: F) -> String
+    where
+        F: Fn(Placeholder) -> Option<String>,
+    {
+        let mut name = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Optional(segments) => {
+                    if let Some(rendered) = render_segments(segments, &get_value) {
+                        name.push_str(&rendered);
+                    }
+                }
+                Segment::Text(text) => name.push_str(text),
+                Segment::Placeholder(placeholder) => {
+                    name.push_str(&get_value(*placeholder).unwrap_or_default())
+                }
+            }
+        }
+        name
+    }
+
+    /// Returns whether the template references the placeholder
+    ///
+    /// # Arguments
+    ///
+    /// * `placeholder` - the placeholder looked for
+    pub(crate) fn uses(&self, placeholder: Placeholder) -> bool {
+        self.segments.iter().any(|segment| match segment {
+            Segment::Text(_) => false,
+            Segment::Placeholder(used) => *used == placeholder,
+            Segment::Optional(segments) => segments.contains(&Segment::Placeholder(placeholder)),
+        })
+    }
+}
+
+impl Default for TaskNameTemplate {
+    fn default() -> Self {
+        DEFAULT_TASK_NAME_TEMPLATE
+            .parse()
+            .expect("The default template is valid")
+    }
+}
+
+/// Renders the segments of an optional part, `None` if a placeholder has no value
+fn render_segments<F>(segments: &[Segment], get_value: &F) -> Option<String>
+where
+    F: Fn(Placeholder) -> Option<String>,
+{
+    let mut rendered = String::new();
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => rendered.push_str(text),
+            Segment::Placeholder(placeholder) => rendered.push_str(&get_value(*placeholder)?),
+            Segment::Optional(_) => unreachable!("optional parts can't be nested"),
+        }
+    }
+    Some(rendered)
+}
+
+impl FromStr for TaskNameTemplate {
+    type Err = ConfigurationProviderError;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            |reason: &str| ConfigurationProviderError::InvalidTaskNameTemplate(reason.into());
+        let mut characters = template.chars().peekable();
+        let mut segments = vec![];
+        let mut optional: Option<Vec<Segment>> = None;
+        let mut text = String::new();
+        while let Some(character) = characters.next() {
+            let is_escaped = matches!(character, '{' | '}' | '[' | ']')
+                && characters.next_if_eq(&character).is_some();
+            if is_escaped || !matches!(character, '{' | '}' | '[' | ']') {
+                text.push(character);
+                continue;
+            }
+
+            let current_segments = optional.as_mut().unwrap_or(&mut segments);
+            push_text(current_segments, &mut text);
+            match character {
+                '{' => {
+                    let placeholder = parse_placeholder(&mut characters)?;
+                    current_segments.push(Segment::Placeholder(placeholder));
+                }
+                '[' if optional.is_none() => optional = Some(vec![]),
+                '[' => return Err(invalid("optional parts can't be nested")),
+                ']' => match optional.take() {
+                    Some(optional_segments) => segments.push(Segment::Optional(optional_segments)),
+                    None => return Err(invalid("unmatched ]")),
+                },
+                _ => return Err(invalid("unmatched }")),
+            }
+        }
+        if optional.is_some() {
+            return Err(invalid("unclosed ["));
+        }
+        push_text(&mut segments, &mut text);
+        Ok(Self { segments })
+    }
+}
+
+fn push_text(segments: &mut Vec<Segment>, text: &mut String) {
+    if !text.is_empty() {
+        segments.push(Segment::Text(std::mem::take(text)));
+    }
+}
+
+/// Parses the placeholder name following an opening brace
+fn parse_placeholder(
+    characters: &mut Peekable<Chars<'_>>,
+) -> Result<Placeholder, ConfigurationProviderError> {
+    let mut name = String::new();
+    for character in characters.by_ref() {
+        if character == '}' {
+            return name.parse();
+        }
+        name.push(character);
+    }
+    Err(ConfigurationProviderError::InvalidTaskNameTemplate(
+        "unclosed {".into(),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn get_value(placeholder: Placeholder) -> Option<String> {
+        match placeholder {
+            Placeholder::User => Some("alice".into()),
+            Placeholder::Host => Some("laptop".into()),
+            Placeholder::Pid => Some("4242".into()),
+            _ => None,
+        }
+    }
+
+    #[rstest]
+    #[case("{user}@{host} (PID {pid})", "alice@laptop (PID 4242)")]
+    #[case("Request[ for {originator}][ by {user}]", "Request by alice")]
+    #[case("{{{user}}} [[{cwd}]]", "{alice} []")]
+    fn given_template_placeholders_are_filled_in(#[case] template: &str, #[case] expected: &str) {
+        let template: TaskNameTemplate = template.parse().unwrap();
+        assert_eq!(template.render(get_value), expected);
+    }
+
+    #[rstest]
+    #[case("{unknown}")]
+    #[case("{user")]
+    #[case("user}")]
+    #[case("[ for {originator}")]
+    #[case("[[ for {originator}]")]
+    #[case("[ for [{originator}]]")]
+    fn given_malformed_template_it_is_refused(#[case] template: &str) {
+        assert!(template.parse::<TaskNameTemplate>().is_err());
+    }
+
+    #[test]
+    fn given_template_only_its_placeholders_are_used() {
+        let template: TaskNameTemplate = "{user}[ for {originator}] {{cwd}}".parse().unwrap();
+
+        assert!(template.uses(Placeholder::User));
+        assert!(template.uses(Placeholder::Originator));
+        assert!(!template.uses(Placeholder::Cwd));
+        assert!(!template.uses(Placeholder::Cmdline));
+    }
+}
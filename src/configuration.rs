@@ -1,9 +1,17 @@
+mod communicator_endpoint;
 mod configuration_provider;
 mod effective_interface_type;
+mod group_route;
 mod interface_configuration;
+mod library_parameters;
 
+pub(crate) use communicator_endpoint::CommunicatorEndpoint;
 pub(crate) use configuration_provider::configuration_provider_error::ConfigurationProviderError;
-pub(crate) use configuration_provider::controller_configuration::ControllerConfiguration;
-pub(crate) use configuration_provider::env_configuration::EnvConfiguration;
-pub(crate) use configuration_provider::ConfigurationProvider;
+pub(crate) use configuration_provider::file_configuration::SYSTEM_CONFIGURATION_PATH;
+pub(crate) use configuration_provider::{
+    announce_configuration_source, select_configuration_provider, ConfigurationSource,
+};
 pub(crate) use effective_interface_type::EffectiveInterfaceType;
+pub(crate) use group_route::{route_originator, GroupRoute, GroupRouting};
+pub(crate) use interface_configuration::InterfaceConfiguration;
+pub(crate) use library_parameters::LibraryParameters;
@@ -0,0 +1,192 @@
+use std::{net::SocketAddr, time::Duration};
+
+use serde::Deserialize;
+
+use crate::communicator::retry_policy::RetryPolicy;
+
+static DEFAULT_COMMUNICATOR_PORT: u16 = 1337;
+static DEFAULT_CONNECT_TIMEOUT_SECONDS: u64 = 10;
+static DEFAULT_REQUEST_TIMEOUT_SECONDS: u64 = 30;
+static DEFAULT_APPROVAL_TIMEOUT_SECONDS: u64 = 120;
+static DEFAULT_RETRY_ATTEMPTS: u32 = 3;
+static DEFAULT_RETRY_INITIAL_BACKOFF_MILLISECONDS: u64 = 500;
+static DEFAULT_RETRY_MAX_BACKOFF_MILLISECONDS: u64 = 5000;
+
+/// Holds the connection settings of a single communicator instance
+#[derive(Deserialize, Clone)]
+pub(crate) struct CommunicatorEndpoint {
+    /// Hostname used to connect to the communicator
+    hostname: String,
+
+    /// Port the communicator listens on
+    port: Option<u16>,
+
+    /// Path to the CA certificate the communicator certificate is verified against
+    certificate_path: String,
+
+    /// Name expected in the communicator's TLS certificate, if it differs from the hostname,
+    /// e.g., when connecting through a load balancer
+    tls_server_name: Option<String>,
+
+    /// Maximum time spent establishing the connection
+    connect_timeout_seconds: Option<u64>,
+
+    /// Maximum time spent waiting for a response to a single RPC
+    request_timeout_seconds: Option<u64>,
+
+    /// Maximum time spent waiting for a task to be approved
+    approval_timeout_seconds: Option<u64>,
+
+    /// Maximum number of attempts of an RPC interrupted by a network failure
+    retry_attempts: Option<u32>,
+
+    /// Delay before the first retry, doubled with each further retry
+    retry_initial_backoff_milliseconds: Option<u64>,
+
+    /// Upper bound of the delay between two attempts
+    retry_max_backoff_milliseconds: Option<u64>,
+
+    /// Address of the communicator resolved by the library, connections use it
+    /// instead of resolving the hostname on the runtime
+    #[serde(skip)]
+    resolved_address: Option<SocketAddr>,
+}
+
+impl CommunicatorEndpoint {
+    pub(crate) fn new(hostname: String, certificate_path: String) -> Self {
+        Self {
+            hostname,
+            port: None,
+            certificate_path,
+            tls_server_name: None,
+            connect_timeout_seconds: None,
+            request_timeout_seconds: None,
+            approval_timeout_seconds: None,
+            retry_attempts: None,
+            retry_initial_backoff_milliseconds: None,
+            retry_max_backoff_milliseconds: None,
+            resolved_address: None,
+        }
+    }
+
+    pub(crate) fn with_port(mut self, port: Option<u16>) -> Self {
+        self.port = port;
+        self
+    }
+
+    pub(crate) fn with_tls_server_name(mut self, tls_server_name: Option<String>) -> Self {
+        self.tls_server_name = tls_server_name;
+        self
+    }
+
+    pub(crate) fn with_timeouts(
+        mut self,
+        connect_timeout_seconds: Option<u64>,
+        request_timeout_seconds: Option<u64>,
+        approval_timeout_seconds: Option<u64>,
+    ) -> Self {
+        self.connect_timeout_seconds = connect_timeout_seconds;
+        self.request_timeout_seconds = request_timeout_seconds;
+        self.approval_timeout_seconds = approval_timeout_seconds;
+        self
+    }
+
+    pub(crate) fn with_retry_policy(
+        mut self,
+        retry_attempts: Option<u32>,
+        retry_initial_backoff_milliseconds: Option<u64>,
+        retry_max_backoff_milliseconds: Option<u64>,
+    ) -> Self {
+        self.retry_attempts = retry_attempts;
+        self.retry_initial_backoff_milliseconds = retry_initial_backoff_milliseconds;
+        self.retry_max_backoff_milliseconds = retry_max_backoff_milliseconds;
+        self
+    }
+
+    pub(crate) fn with_resolved_address(mut self, resolved_address: Option<SocketAddr>) -> Self {
+        self.resolved_address = resolved_address;
+        self
+    }
+
+    pub(crate) fn get_hostname(&self) -> &str {
+        &self.hostname
+    }
+
+    pub(crate) fn get_port(&self) -> u16 {
+        self.port.unwrap_or(DEFAULT_COMMUNICATOR_PORT)
+    }
+
+    pub(crate) fn get_resolved_address(&self) -> Option<SocketAddr> {
+        self.resolved_address
+    }
+
+    pub(crate) fn get_certificate_path(&self) -> &str {
+        &self.certificate_path
+    }
+
+    /// Returns the name the server certificate is verified against
+    pub(crate) fn get_tls_server_name(&self) -> &str {
+        self.tls_server_name.as_deref().unwrap_or(&self.hostname)
+    }
+
+    pub(crate) fn get_connect_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.connect_timeout_seconds
+                .unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECONDS),
+        )
+    }
+
+    pub(crate) fn get_request_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.request_timeout_seconds
+                .unwrap_or(DEFAULT_REQUEST_TIMEOUT_SECONDS),
+        )
+    }
+
+    pub(crate) fn get_approval_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.approval_timeout_seconds
+                .unwrap_or(DEFAULT_APPROVAL_TIMEOUT_SECONDS),
+        )
+    }
+
+    pub(crate) fn get_retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::new(
+            self.retry_attempts.unwrap_or(DEFAULT_RETRY_ATTEMPTS),
+            Duration::from_millis(
+                self.retry_initial_backoff_milliseconds
+                    .unwrap_or(DEFAULT_RETRY_INITIAL_BACKOFF_MILLISECONDS),
+            ),
+            Duration::from_millis(
+                self.retry_max_backoff_milliseconds
+                    .unwrap_or(DEFAULT_RETRY_MAX_BACKOFF_MILLISECONDS),
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn given_no_overrides_endpoint_uses_hostname_and_default_port() {
+        let endpoint = CommunicatorEndpoint::new("meesign.local".into(), "ca.pem".into());
+        assert_eq!(endpoint.get_port(), DEFAULT_COMMUNICATOR_PORT);
+        assert_eq!(endpoint.get_tls_server_name(), "meesign.local");
+        assert_eq!(
+            endpoint.get_approval_timeout(),
+            Duration::from_secs(DEFAULT_APPROVAL_TIMEOUT_SECONDS)
+        );
+    }
+
+    #[test]
+    fn given_tls_server_name_endpoint_verifies_against_it() {
+        let endpoint = CommunicatorEndpoint::new("10.0.0.1".into(), "ca.pem".into())
+            .with_port(Some(443))
+            .with_tls_server_name(Some("meesign.example.com".into()));
+        assert_eq!(endpoint.get_hostname(), "10.0.0.1");
+        assert_eq!(endpoint.get_port(), 443);
+        assert_eq!(endpoint.get_tls_server_name(), "meesign.example.com");
+    }
+}
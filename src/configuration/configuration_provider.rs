@@ -1,10 +1,32 @@
 pub(crate) mod configuration_provider_error;
 pub(crate) mod controller_configuration;
 pub(crate) mod env_configuration;
+pub(crate) mod file_configuration;
+#[cfg(all(feature = "mocked_communicator", debug_assertions))]
+pub(crate) mod mocked_configuration;
+pub(crate) mod parameter_configuration;
 
-use self::configuration_provider_error::ConfigurationProviderError;
+use std::{
+    env, fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use super::interface_configuration::InterfaceConfiguration;
+use self::{
+    configuration_provider_error::ConfigurationProviderError,
+    controller_configuration::ControllerConfiguration,
+    env_configuration::EnvConfiguration,
+    file_configuration::{
+        get_user_configuration_path, FileConfiguration, SYSTEM_CONFIGURATION_PATH,
+    },
+    parameter_configuration::ParameterConfiguration,
+};
+
+use crate::state::ThreadingModel;
+
+use super::{interface_configuration::InterfaceConfiguration, LibraryParameters};
+
+static VERBOSE_ENV_NAME: &str = "CRYPTOKI_BRIDGE_VERBOSE";
 
 /// Provides the configuration for this interface
 pub(crate) trait ConfigurationProvider: Send + Sync {
@@ -12,4 +34,134 @@ pub(crate) trait ConfigurationProvider: Send + Sync {
     fn get_interface_configuration(
         &self,
     ) -> Result<InterfaceConfiguration, ConfigurationProviderError>;
+
+    /// Returns where the configuration comes from
+    fn get_source(&self) -> ConfigurationSource;
+}
+
+/// Where the configuration comes from, in order of precedence
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ConfigurationSource {
+    Environment,
+
+    /// `config.toml` in the cryptoki directory of the user
+    UserFile(PathBuf),
+
+    /// The system-wide configuration file, shared by all users
+    SystemFile(PathBuf),
+    Controller,
+
+    /// Library parameters passed to `C_Initialize`, overriding the configuration of the process
+    LibraryParameters(Box<ConfigurationSource>),
+
+    /// The default configuration of the mocked communicator
+    #[cfg(all(feature = "mocked_communicator", debug_assertions))]
+    Mocked,
+}
+
+impl ConfigurationSource {
+    /// Returns the path of the configuration file, if the source is a file
+    pub(crate) fn get_path(&self) -> Option<&Path> {
+        match self {
+            Self::UserFile(path) | Self::SystemFile(path) => Some(path),
+            Self::Environment | Self::Controller | Self::LibraryParameters(_) => None,
+            #[cfg(all(feature = "mocked_communicator", debug_assertions))]
+            Self::Mocked => None,
+        }
+    }
+}
+
+impl fmt::Display for ConfigurationSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Environment => write!(f, "the environment variables"),
+            Self::UserFile(path) | Self::SystemFile(path) => write!(f, "{}", path.display()),
+            Self::Controller => write!(f, "the controller"),
+            Self::LibraryParameters(base) => write!(f, "the library parameters over {base}"),
+            #[cfg(all(feature = "mocked_communicator", debug_assertions))]
+            Self::Mocked => write!(f, "the mocked configuration"),
+        }
+    }
+}
+
+/// Picks the provider of the configuration, the first available source wins:
+/// 0. the library parameters passed to `C_Initialize` override the settings they contain
+///    in the configuration picked from the remaining sources, see [`ParameterConfiguration`],
+/// 1. the environment variables, see [`EnvConfiguration`],
+/// 2. `config.toml` in the cryptoki directory,
+/// 3. the system-wide `/etc/cryptoki-bridge/config.toml`,
+/// 4. the controller, which is always picked as the last resort, except for builds
+///    with the mocked communicator, which fall back to the mocked configuration.
+///
+/// The configuration is read once, when the library is initialized.
+/// The source that won is printed by [`announce_configuration_source`].
+///
+/// A file is available only if it has a section for the current interface.
+/// Sources that are present, but invalid, fail the selection instead of being skipped.
+///
+/// # Arguments
+///
+/// * `cryptoki_directory` - the directory holding the data of the library
+/// * `parameters` - the library parameters, if the application passed any
+/// * `threading_model` - how the providers may use threads
+pub(crate) fn select_configuration_provider(
+    cryptoki_directory: &Path,
+    parameters: Option<LibraryParameters>,
+    threading_model: ThreadingModel,
+) -> Result<Arc<dyn ConfigurationProvider>, ConfigurationProviderError> {
+    let base = select_process_configuration_provider(cryptoki_directory, threading_model)?;
+    Ok(match parameters {
+        Some(parameters) => Arc::new(ParameterConfiguration::new(parameters, base)),
+        None => base,
+    })
+}
+
+/// Prints the source of the configuration to stderr, if the `CRYPTOKI_BRIDGE_VERBOSE`
+/// env variable is set. The library runs inside other applications,
+/// so their output is left alone unless the user asks for it.
+///
+/// # Arguments
+///
+/// * `source` - where the configuration comes from
+pub(crate) fn announce_configuration_source(source: &ConfigurationSource) {
+    if env::var_os(VERBOSE_ENV_NAME).is_some() {
+        eprintln!("Using the configuration from {source}");
+    }
+}
+
+fn select_process_configuration_provider(
+    cryptoki_directory: &Path,
+    threading_model: ThreadingModel,
+) -> Result<Arc<dyn ConfigurationProvider>, ConfigurationProviderError> {
+    let env_configuration = EnvConfiguration::new().map_err(|err| {
+        eprintln!(
+            "Env configuration is not done properly. Please, consult the project documentation."
+        );
+        err
+    })?;
+    if let Some(env_configuration) = env_configuration {
+        return Ok(Arc::new(env_configuration));
+    }
+
+    let file_sources = [
+        ConfigurationSource::UserFile(get_user_configuration_path(cryptoki_directory)),
+        ConfigurationSource::SystemFile(SYSTEM_CONFIGURATION_PATH.into()),
+    ];
+    for source in file_sources {
+        let file_configuration = FileConfiguration::new(source).map_err(|err| {
+            eprintln!("{err}");
+            err
+        })?;
+        if let Some(file_configuration) = file_configuration {
+            return Ok(Arc::new(file_configuration));
+        }
+    }
+
+    #[cfg(all(feature = "mocked_communicator", debug_assertions))]
+    {
+        let _ = threading_model;
+        Ok(Arc::new(mocked_configuration::MockedConfiguration))
+    }
+    #[cfg(not(all(feature = "mocked_communicator", debug_assertions)))]
+    Ok(Arc::new(ControllerConfiguration::new(threading_model)))
 }
@@ -1,4 +1,4 @@
-use std::env::VarError;
+use std::{env::VarError, io};
 
 use hex::FromHexError;
 use thiserror::Error;
@@ -11,6 +11,14 @@ pub(crate) enum ConfigurationProviderError {
     InvalidFormat,
     #[error("Reqwest error: {0}")]
     ReqwestError(#[from] reqwest::Error),
+    #[error("Task name template is not valid: {0}")]
+    InvalidTaskNameTemplate(String),
+    #[error("Configuration file {0} is not valid: {1}")]
+    InvalidFile(String, String),
+    #[error("Library parameters are not valid: {0}")]
+    InvalidLibraryParameters(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
 }
 
 impl From<FromHexError> for ConfigurationProviderError {
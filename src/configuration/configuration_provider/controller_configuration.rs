@@ -1,15 +1,20 @@
 mod interface_configuration_response;
 
 use crate::{
-    communicator::task_name_provider::get_binary_name,
-    configuration::{interface_configuration::InterfaceConfiguration, EffectiveInterfaceType},
+    configuration::{
+        interface_configuration::InterfaceConfiguration, ConfigurationSource,
+        EffectiveInterfaceType,
+    },
+    process_identity::{ProcessIdentity, TrustPolicy},
+    state::{ensure_outside_of_runtime, resolve_on_calling_thread, ThreadingModel},
 };
 
 pub(crate) use self::interface_configuration_response::InterfaceConfigurationResponse;
 
 use super::{configuration_provider_error::ConfigurationProviderError, ConfigurationProvider};
 
-static CONTROLLER_PORT: &str = "11115";
+static CONTROLLER_HOSTNAME: &str = "www.localhost";
+static CONTROLLER_PORT: u16 = 11115;
 
 /// Provides the configuration from the controller component
 /// that was configured by the user
@@ -17,17 +22,23 @@ pub(crate) struct ControllerConfiguration {
     /// Current effective interface type, e.g., Cryptoki
     effective_interface_type: EffectiveInterfaceType,
 
-    /// Name of the tool that is using the library, e.g., ssh
+    /// Name of the tool that is using the library, e.g., ssh,
+    /// `None` if the tool isn't trusted by the process trust policy
     tool_name: Option<String>,
+
+    /// Whether the configuration can be fetched on a thread of its own
+    threading_model: ThreadingModel,
 }
 
 impl ControllerConfiguration {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(threading_model: ThreadingModel) -> Self {
         let effective_interface_type = EffectiveInterfaceType::from_environment();
-        let tool_name = get_binary_name().unwrap_or(None);
+        let tool_name =
+            ProcessIdentity::current().get_tool_name(TrustPolicy::from_system_configuration());
         Self {
             effective_interface_type,
             tool_name,
+            threading_model,
         }
     }
 }
@@ -43,12 +54,40 @@ impl ConfigurationProvider for ControllerConfiguration {
             .map(map_auxiliary_tools)
             .map(|tool_name| format!("tool={}", tool_name))
             .unwrap_or_default();
-        let configuration: InterfaceConfigurationResponse = reqwest::blocking::get(format!(
-            "http://www.localhost:{CONTROLLER_PORT}/{effective_interface_type}/configuration?{tool_parameter}"
-        ))?
-        .json()?;
+        // both clients block on a runtime of their own
+        ensure_outside_of_runtime()?;
+        let url = format!(
+            "http://{CONTROLLER_HOSTNAME}:{CONTROLLER_PORT}/{effective_interface_type}/configuration?{tool_parameter}"
+        );
+        let configuration: InterfaceConfigurationResponse = match self.threading_model {
+            ThreadingModel::MultiThreaded => reqwest::blocking::get(url)?.json()?,
+            ThreadingModel::CallingThreadOnly => fetch_on_calling_thread(&url)?,
+        };
         Ok(configuration.into())
     }
+
+    fn get_source(&self) -> ConfigurationSource {
+        ConfigurationSource::Controller
+    }
+}
+
+/// Fetches the configuration without spawning threads, unlike the blocking client,
+/// which runs its own runtime on a thread of its own. The runtime built here lives
+/// only for the fetch, the configuration is fetched once when the library is initialized.
+///
+/// # Arguments
+///
+/// * `url` - the configuration endpoint of the controller
+fn fetch_on_calling_thread(
+    url: &str,
+) -> Result<InterfaceConfigurationResponse, ConfigurationProviderError> {
+    let controller_address = resolve_on_calling_thread(CONTROLLER_HOSTNAME, CONTROLLER_PORT)?;
+    let client = reqwest::Client::builder()
+        .resolve(CONTROLLER_HOSTNAME, controller_address)
+        .build()?;
+    let runtime = ThreadingModel::CallingThreadOnly.build_runtime()?;
+    let configuration = runtime.block_on(async { client.get(url).send().await?.json().await })?;
+    Ok(configuration)
 }
 
 /// Maps names of auxiliary tools to the actual tool names, e.g.,
@@ -59,7 +98,7 @@ impl ConfigurationProvider for ControllerConfiguration {
 /// # Arguments
 ///
 /// * `tool_name` - The name of the tool to map.
-fn map_auxiliary_tools(tool_name: &String) -> String {
+pub(super) fn map_auxiliary_tools(tool_name: &String) -> String {
     match tool_name.as_str() {
         "ssh-keygen" => "ssh".to_string(),
         _ => tool_name.into(),
@@ -1,13 +1,45 @@
 use serde::Deserialize;
 
-use crate::communicator::GroupId;
+use crate::{
+    communicator::{task_name_provider::TaskNameFormat, GroupId},
+    configuration::{CommunicatorEndpoint, GroupRoute},
+};
 
 /// Used to deserialize the response from the controller server
 #[derive(Deserialize, Clone)]
 pub(crate) struct InterfaceConfigurationResponse {
     communicator_hostname: String,
     communicator_certificate_path: String,
-    group_id: GroupId,
+    #[serde(default)]
+    group_id: Option<GroupId>,
+    #[serde(default)]
+    communicator_port: Option<u16>,
+    #[serde(default)]
+    communicator_tls_server_name: Option<String>,
+    #[serde(default)]
+    communicator_connect_timeout_seconds: Option<u64>,
+    #[serde(default)]
+    communicator_request_timeout_seconds: Option<u64>,
+    #[serde(default)]
+    communicator_approval_timeout_seconds: Option<u64>,
+    #[serde(default)]
+    communicator_retry_attempts: Option<u32>,
+    #[serde(default)]
+    communicator_retry_initial_backoff_milliseconds: Option<u64>,
+    #[serde(default)]
+    communicator_retry_max_backoff_milliseconds: Option<u64>,
+    #[serde(default)]
+    additional_communicators: Vec<CommunicatorEndpoint>,
+    #[serde(default)]
+    pending_task_reuse_window_seconds: Option<u64>,
+    #[serde(default)]
+    forward_diagnostics: Option<bool>,
+    #[serde(default)]
+    task_name_template: Option<String>,
+    #[serde(default)]
+    task_name_format: Option<TaskNameFormat>,
+    #[serde(default)]
+    group_routes: Vec<GroupRoute>,
 }
 
 impl InterfaceConfigurationResponse {
@@ -15,11 +47,67 @@ impl InterfaceConfigurationResponse {
         &self.communicator_hostname
     }
 
-    pub fn get_group_id(&self) -> &GroupId {
-        &self.group_id
+    pub fn get_group_id(&self) -> Option<&GroupId> {
+        self.group_id.as_ref()
     }
 
     pub fn get_communicator_certificate_path(&self) -> &str {
         &self.communicator_certificate_path
     }
+
+    pub fn get_communicator_port(&self) -> Option<u16> {
+        self.communicator_port
+    }
+
+    pub fn get_communicator_tls_server_name(&self) -> Option<&str> {
+        self.communicator_tls_server_name.as_deref()
+    }
+
+    pub fn get_communicator_connect_timeout_seconds(&self) -> Option<u64> {
+        self.communicator_connect_timeout_seconds
+    }
+
+    pub fn get_communicator_request_timeout_seconds(&self) -> Option<u64> {
+        self.communicator_request_timeout_seconds
+    }
+
+    pub fn get_communicator_approval_timeout_seconds(&self) -> Option<u64> {
+        self.communicator_approval_timeout_seconds
+    }
+
+    pub fn get_communicator_retry_attempts(&self) -> Option<u32> {
+        self.communicator_retry_attempts
+    }
+
+    pub fn get_communicator_retry_initial_backoff_milliseconds(&self) -> Option<u64> {
+        self.communicator_retry_initial_backoff_milliseconds
+    }
+
+    pub fn get_communicator_retry_max_backoff_milliseconds(&self) -> Option<u64> {
+        self.communicator_retry_max_backoff_milliseconds
+    }
+
+    pub fn get_additional_communicators(&self) -> &[CommunicatorEndpoint] {
+        &self.additional_communicators
+    }
+
+    pub fn get_pending_task_reuse_window_seconds(&self) -> Option<u64> {
+        self.pending_task_reuse_window_seconds
+    }
+
+    pub fn get_forward_diagnostics(&self) -> Option<bool> {
+        self.forward_diagnostics
+    }
+
+    pub fn get_task_name_template(&self) -> Option<&str> {
+        self.task_name_template.as_deref()
+    }
+
+    pub fn get_task_name_format(&self) -> Option<TaskNameFormat> {
+        self.task_name_format
+    }
+
+    pub fn get_group_routes(&self) -> &[GroupRoute] {
+        &self.group_routes
+    }
 }
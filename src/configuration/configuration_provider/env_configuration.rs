@@ -1,7 +1,14 @@
-use std::env::{self, VarError};
+use std::{
+    env::{self, VarError},
+    str::FromStr,
+};
 
 use crate::{
-    communicator::GroupId, configuration::interface_configuration::InterfaceConfiguration,
+    communicator::GroupId,
+    configuration::{
+        communicator_endpoint::CommunicatorEndpoint,
+        interface_configuration::InterfaceConfiguration, ConfigurationSource, GroupRoute,
+    },
 };
 
 use super::{configuration_provider_error::ConfigurationProviderError, ConfigurationProvider};
@@ -9,6 +16,21 @@ use super::{configuration_provider_error::ConfigurationProviderError, Configurat
 static COMMUNICATOR_HOSTNAME_ENV_NAME: &str = "COMMUNICATOR_HOSTNAME";
 static GROUP_ID_ENV_NAME: &str = "GROUP_ID";
 static COMMUNICATOR_CERTIFICATE_PATH_ENV_NAME: &str = "COMMUNICATOR_CERTIFICATE_PATH";
+static COMMUNICATOR_PORT_ENV_NAME: &str = "COMMUNICATOR_PORT";
+static COMMUNICATOR_TLS_SERVER_NAME_ENV_NAME: &str = "COMMUNICATOR_TLS_SERVER_NAME";
+static COMMUNICATOR_CONNECT_TIMEOUT_ENV_NAME: &str = "COMMUNICATOR_CONNECT_TIMEOUT_SECONDS";
+static COMMUNICATOR_REQUEST_TIMEOUT_ENV_NAME: &str = "COMMUNICATOR_REQUEST_TIMEOUT_SECONDS";
+static COMMUNICATOR_APPROVAL_TIMEOUT_ENV_NAME: &str = "COMMUNICATOR_APPROVAL_TIMEOUT_SECONDS";
+static COMMUNICATOR_RETRY_ATTEMPTS_ENV_NAME: &str = "COMMUNICATOR_RETRY_ATTEMPTS";
+static COMMUNICATOR_RETRY_INITIAL_BACKOFF_ENV_NAME: &str =
+    "COMMUNICATOR_RETRY_INITIAL_BACKOFF_MILLISECONDS";
+static COMMUNICATOR_RETRY_MAX_BACKOFF_ENV_NAME: &str =
+    "COMMUNICATOR_RETRY_MAX_BACKOFF_MILLISECONDS";
+static PENDING_TASK_REUSE_WINDOW_ENV_NAME: &str = "PENDING_TASK_REUSE_WINDOW_SECONDS";
+static FORWARD_DIAGNOSTICS_ENV_NAME: &str = "FORWARD_DIAGNOSTICS";
+static TASK_NAME_TEMPLATE_ENV_NAME: &str = "TASK_NAME_TEMPLATE";
+static TASK_NAME_FORMAT_ENV_NAME: &str = "TASK_NAME_FORMAT";
+static GROUP_ROUTES_ENV_NAME: &str = "GROUP_ROUTES";
 
 /// Provides configuration from the environment variables
 pub(crate) struct EnvConfiguration {
@@ -29,10 +51,82 @@ impl EnvConfiguration {
         Ok(Some(group_id))
     }
 
+    /// Parses the comma-separated routes, each written as
+    /// `<originator pattern>=<hex-encoded group ID>`
+    fn get_group_routes() -> Result<Vec<GroupRoute>, ConfigurationProviderError> {
+        let Ok(routes) = env::var(GROUP_ROUTES_ENV_NAME) else {
+            return Ok(vec![]);
+        };
+        split_list(&routes).into_iter().map(str::parse).collect()
+    }
+
     fn get_communicator_certificate_path() -> Result<String, VarError> {
         env::var(COMMUNICATOR_CERTIFICATE_PATH_ENV_NAME)
     }
 
+    /// Parses an optional env variable, failing only if the variable is set
+    /// to a value that can't be parsed
+    ///
+    /// # Arguments
+    ///
+    /// * `env_name` - the name of the env variable
+    fn get_optional_value<T: FromStr>(
+        env_name: &str,
+    ) -> Result<Option<T>, ConfigurationProviderError> {
+        let Ok(value) = env::var(env_name) else {
+            return Ok(None);
+        };
+        let value = value
+            .parse()
+            .map_err(|_| ConfigurationProviderError::InvalidFormat)?;
+        Ok(Some(value))
+    }
+
+    /// Creates an endpoint for each of the comma-separated hostnames.
+    /// Either a single certificate path is shared by all the endpoints,
+    /// or there is one for each hostname. The remaining settings are shared.
+    ///
+    /// # Arguments
+    ///
+    /// * `hostnames` - comma-separated hostnames of the communicators
+    /// * `certificate_paths` - comma-separated certificate paths
+    fn get_communicator_endpoints(
+        hostnames: String,
+        certificate_paths: String,
+    ) -> Result<Vec<CommunicatorEndpoint>, ConfigurationProviderError> {
+        let hostnames = split_list(&hostnames);
+        let certificate_paths = split_list(&certificate_paths);
+        let certificate_paths = match (hostnames.len(), certificate_paths.len()) {
+            (0, _) => return Err(ConfigurationProviderError::InvalidFormat),
+            (_, 1) => vec![certificate_paths[0]; hostnames.len()],
+            (hostname_count, path_count) if hostname_count == path_count => certificate_paths,
+            _ => return Err(ConfigurationProviderError::InvalidFormat),
+        };
+
+        let port = Self::get_optional_value(COMMUNICATOR_PORT_ENV_NAME)?;
+        let tls_server_name: Option<String> =
+            Self::get_optional_value(COMMUNICATOR_TLS_SERVER_NAME_ENV_NAME)?;
+        let connect_timeout = Self::get_optional_value(COMMUNICATOR_CONNECT_TIMEOUT_ENV_NAME)?;
+        let request_timeout = Self::get_optional_value(COMMUNICATOR_REQUEST_TIMEOUT_ENV_NAME)?;
+        let approval_timeout = Self::get_optional_value(COMMUNICATOR_APPROVAL_TIMEOUT_ENV_NAME)?;
+        let retry_attempts = Self::get_optional_value(COMMUNICATOR_RETRY_ATTEMPTS_ENV_NAME)?;
+        let retry_initial_backoff =
+            Self::get_optional_value(COMMUNICATOR_RETRY_INITIAL_BACKOFF_ENV_NAME)?;
+        let retry_max_backoff = Self::get_optional_value(COMMUNICATOR_RETRY_MAX_BACKOFF_ENV_NAME)?;
+        let endpoints = hostnames
+            .into_iter()
+            .zip(certificate_paths)
+            .map(|(hostname, certificate_path)| {
+                CommunicatorEndpoint::new(hostname.into(), certificate_path.into())
+                    .with_port(port)
+                    .with_tls_server_name(tls_server_name.clone())
+                    .with_timeouts(connect_timeout, request_timeout, approval_timeout)
+                    .with_retry_policy(retry_attempts, retry_initial_backoff, retry_max_backoff)
+            })
+            .collect();
+        Ok(endpoints)
+    }
+
     pub(crate) fn new() -> Result<Option<Self>, ConfigurationProviderError> {
         let hostname = Self::get_communicator_hostname();
         let cert_path = Self::get_communicator_certificate_path();
@@ -42,7 +136,17 @@ impl EnvConfiguration {
         // that the hostname and cert_path are correctly specified
         let configuration = match (hostname, cert_path, group_id) {
             (Ok(hostname), Ok(cert_path), Ok(group_id)) => {
-                InterfaceConfiguration::new(hostname, group_id, cert_path)
+                let endpoints = Self::get_communicator_endpoints(hostname, cert_path)?;
+                let reuse_window = Self::get_optional_value(PENDING_TASK_REUSE_WINDOW_ENV_NAME)?;
+                let forward_diagnostics = Self::get_optional_value(FORWARD_DIAGNOSTICS_ENV_NAME)?;
+                InterfaceConfiguration::new(endpoints, group_id)
+                    .with_pending_task_reuse_window(reuse_window)
+                    .with_diagnostics_forwarding(forward_diagnostics)
+                    .with_task_names(
+                        Self::get_optional_value(TASK_NAME_TEMPLATE_ENV_NAME)?,
+                        Self::get_optional_value(TASK_NAME_FORMAT_ENV_NAME)?,
+                    )
+                    .with_group_routes(Self::get_group_routes()?)
             }
             (Err(VarError::NotPresent), Err(VarError::NotPresent), Ok(None)) => return Ok(None),
             (hostname, id, path) => {
@@ -56,10 +160,53 @@ impl EnvConfiguration {
     }
 }
 
+/// Splits a comma-separated list, ignoring empty items
+fn split_list(list: &str) -> Vec<&str> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
 impl ConfigurationProvider for EnvConfiguration {
     fn get_interface_configuration(
         &self,
     ) -> Result<InterfaceConfiguration, ConfigurationProviderError> {
         Ok(self.configuration.clone())
     }
+
+    fn get_source(&self) -> ConfigurationSource {
+        ConfigurationSource::Environment
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn given_shared_certificate_each_hostname_gets_an_endpoint() {
+        let endpoints = EnvConfiguration::get_communicator_endpoints(
+            "staging.meesign.local, meesign.local".into(),
+            "ca.pem".into(),
+        )
+        .unwrap();
+        let hostnames: Vec<&str> = endpoints.iter().map(|e| e.get_hostname()).collect();
+        assert_eq!(hostnames, vec!["staging.meesign.local", "meesign.local"]);
+        assert!(endpoints
+            .iter()
+            .all(|endpoint| endpoint.get_certificate_path() == "ca.pem"));
+    }
+
+    #[test]
+    fn given_mismatched_certificate_count_endpoints_are_refused() {
+        let endpoints = EnvConfiguration::get_communicator_endpoints(
+            "a.local,b.local,c.local".into(),
+            "a.pem,b.pem".into(),
+        );
+        assert!(matches!(
+            endpoints,
+            Err(ConfigurationProviderError::InvalidFormat)
+        ));
+    }
 }
@@ -0,0 +1,252 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use toml::{value::Table, Value};
+
+use crate::{
+    configuration::{
+        interface_configuration::InterfaceConfiguration, ConfigurationSource,
+        EffectiveInterfaceType,
+    },
+    process_identity::{ProcessIdentity, TrustPolicy},
+};
+
+use super::{
+    configuration_provider_error::ConfigurationProviderError,
+    controller_configuration::{map_auxiliary_tools, InterfaceConfigurationResponse},
+    ConfigurationProvider,
+};
+
+pub(crate) static CONFIGURATION_FILE_NAME: &str = "config.toml";
+pub(crate) static SYSTEM_CONFIGURATION_PATH: &str = "/etc/cryptoki-bridge/config.toml";
+static TOOLS_SECTION_NAME: &str = "tools";
+
+/// Provides the configuration from a TOML file. The file holds a section
+/// for each interface, e.g., `[cryptoki]`, with the same keys as the controller's
+/// configuration. Group IDs are hex-encoded. Each interface section may contain
+/// tool sections, e.g., `[cryptoki.tools.ssh]`, whose keys override the interface
+/// keys for the tool, just like the controller's `tool=` query.
+pub(crate) struct FileConfiguration {
+    /// Configuration acquired from the file
+    configuration: InterfaceConfiguration,
+    source: ConfigurationSource,
+}
+
+impl FileConfiguration {
+    /// Reads the configuration of the current interface and tool from the file.
+    /// Returns `None` if the file doesn't exist or has no section for the interface.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - the configuration file to be read
+    pub(crate) fn new(
+        source: ConfigurationSource,
+    ) -> Result<Option<Self>, ConfigurationProviderError> {
+        let Some(path) = source.get_path() else {
+            return Ok(None);
+        };
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(invalid_file(path, err)),
+        };
+        let tool_name = ProcessIdentity::current()
+            .get_tool_name(TrustPolicy::from_system_configuration())
+            .map(|tool_name| map_auxiliary_tools(&tool_name));
+        let configuration = parse_configuration(
+            &content,
+            EffectiveInterfaceType::from_environment().to_interface_string(),
+            tool_name.as_deref(),
+        )
+        .map_err(|err| invalid_file(path, err))?;
+        Ok(configuration.map(|configuration| Self {
+            configuration,
+            source,
+        }))
+    }
+}
+
+impl ConfigurationProvider for FileConfiguration {
+    fn get_interface_configuration(
+        &self,
+    ) -> Result<InterfaceConfiguration, ConfigurationProviderError> {
+        Ok(self.configuration.clone())
+    }
+
+    fn get_source(&self) -> ConfigurationSource {
+        self.source.clone()
+    }
+}
+
+/// Returns the path of the configuration file in the cryptoki directory
+///
+/// # Arguments
+///
+/// * `cryptoki_directory` - the directory holding the data of the library
+pub(crate) fn get_user_configuration_path(cryptoki_directory: &Path) -> PathBuf {
+    cryptoki_directory.join(CONFIGURATION_FILE_NAME)
+}
+
+/// Parses the configuration of the interface, overridden by the section of the tool.
+/// Returns `None` if there is no section for the interface.
+///
+/// # Arguments
+///
+/// * `content` - the content of the configuration file
+/// * `interface` - the name of the interface section, e.g., `cryptoki`
+/// * `tool_name` - the name of the tool using the library, if trusted
+fn parse_configuration(
+    content: &str,
+    interface: &str,
+    tool_name: Option<&str>,
+) -> Result<Option<InterfaceConfiguration>, String> {
+    let mut file: Table = toml::from_str(content).map_err(|err| err.to_string())?;
+    let Some(section) = file.remove(interface) else {
+        return Ok(None);
+    };
+    let Value::Table(mut section) = section else {
+        return Err(format!("[{interface}] is not a section"));
+    };
+
+    let tool_sections = match section.remove(TOOLS_SECTION_NAME) {
+        Some(Value::Table(tool_sections)) => tool_sections,
+        Some(_) => {
+            return Err(format!(
+                "[{interface}.{TOOLS_SECTION_NAME}] is not a section"
+            ))
+        }
+        None => Table::new(),
+    };
+    if let Some(tool_name) = tool_name {
+        match tool_sections.get(tool_name) {
+            Some(Value::Table(tool_section)) => section.extend(tool_section.clone()),
+            Some(_) => {
+                return Err(format!(
+                    "[{interface}.{TOOLS_SECTION_NAME}.{tool_name}] is not a section"
+                ))
+            }
+            None => {}
+        }
+    }
+
+    decode_group_ids(&mut section)?;
+    let response: InterfaceConfigurationResponse = Value::Table(section)
+        .try_into()
+        .map_err(|err: toml::de::Error| err.to_string())?;
+    Ok(Some(response.into()))
+}
+
+/// Replaces the hex-encoded group IDs with the bytes the configuration model expects
+fn decode_group_ids(section: &mut Table) -> Result<(), String> {
+    decode_group_id(section)?;
+    if let Some(Value::Array(routes)) = section.get_mut("group_routes") {
+        for route in routes {
+            if let Value::Table(route) = route {
+                decode_group_id(route)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn decode_group_id(table: &mut Table) -> Result<(), String> {
+    let Some(Value::String(group_id)) = table.get("group_id") else {
+        return Ok(());
+    };
+    let group_id = hex::decode(group_id).map_err(|err| format!("group_id: {err}"))?;
+    let group_id = group_id
+        .into_iter()
+        .map(|byte| Value::Integer(byte.into()))
+        .collect();
+    table.insert("group_id".into(), Value::Array(group_id));
+    Ok(())
+}
+
+fn invalid_file(path: &Path, err: impl ToString) -> ConfigurationProviderError {
+    ConfigurationProviderError::InvalidFile(path.display().to_string(), err.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    static CONFIGURATION: &str = r#"
+        [cryptoki]
+        communicator_hostname = "meesign.local"
+        communicator_certificate_path = "/etc/meesign/ca.pem"
+        group_id = "abcd"
+
+        [[cryptoki.group_routes]]
+        originator = "*.example.com"
+        group_id = "ef01"
+
+        [cryptoki.tools.ssh]
+        group_id = "0123"
+        communicator_port = 1338
+
+        [webauthn]
+        communicator_hostname = "fido.meesign.local"
+        communicator_certificate_path = "/etc/meesign/ca.pem"
+    "#;
+
+    #[test]
+    fn given_interface_section_the_configuration_is_parsed() {
+        let configuration = parse_configuration(CONFIGURATION, "cryptoki", None)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(configuration.get_group_id(), Some(&vec![0xab, 0xcd]));
+        let endpoint = &configuration.get_communicator_endpoints()[0];
+        assert_eq!(endpoint.get_hostname(), "meesign.local");
+        assert_eq!(endpoint.get_certificate_path(), "/etc/meesign/ca.pem");
+        assert_eq!(
+            configuration.get_group_routes()[0].get_group_id(),
+            &vec![0xef, 0x01]
+        );
+    }
+
+    #[test]
+    fn given_tool_section_it_overrides_the_interface_section() {
+        let configuration = parse_configuration(CONFIGURATION, "cryptoki", Some("ssh"))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(configuration.get_group_id(), Some(&vec![0x01, 0x23]));
+        let endpoint = &configuration.get_communicator_endpoints()[0];
+        assert_eq!(endpoint.get_hostname(), "meesign.local");
+        assert_eq!(endpoint.get_port(), 1338);
+    }
+
+    #[test]
+    fn given_unknown_tool_the_interface_section_is_used() {
+        let configuration = parse_configuration(CONFIGURATION, "cryptoki", Some("git"))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(configuration.get_group_id(), Some(&vec![0xab, 0xcd]));
+    }
+
+    #[test]
+    fn given_interface_without_section_no_configuration_is_provided() {
+        let configuration = parse_configuration(
+            "[webauthn]\ncommunicator_hostname = \"a\"\ncommunicator_certificate_path = \"b\"",
+            "cryptoki",
+            None,
+        );
+
+        assert!(matches!(configuration, Ok(None)));
+    }
+
+    #[test]
+    fn given_invalid_group_id_the_file_is_refused() {
+        let configuration = parse_configuration(
+            "[cryptoki]\ncommunicator_hostname = \"a\"\ncommunicator_certificate_path = \"b\"\ngroup_id = \"xyz\"",
+            "cryptoki",
+            None,
+        );
+
+        assert!(configuration.is_err());
+    }
+}
@@ -0,0 +1,21 @@
+use crate::configuration::{interface_configuration::InterfaceConfiguration, ConfigurationSource};
+
+use super::{configuration_provider_error::ConfigurationProviderError, ConfigurationProvider};
+
+/// MockedConfiguration is used together with the mocked communicator
+/// in integration tests in CI/CD, where no controller is running.
+/// It replaces the controller as the last resort, so the mocked communicator
+/// is used with the default configuration.
+pub(crate) struct MockedConfiguration;
+
+impl ConfigurationProvider for MockedConfiguration {
+    fn get_interface_configuration(
+        &self,
+    ) -> Result<InterfaceConfiguration, ConfigurationProviderError> {
+        Ok(InterfaceConfiguration::new(vec![], None))
+    }
+
+    fn get_source(&self) -> ConfigurationSource {
+        ConfigurationSource::Mocked
+    }
+}
@@ -0,0 +1,148 @@
+use std::sync::Arc;
+
+use crate::configuration::{
+    interface_configuration::InterfaceConfiguration, CommunicatorEndpoint, ConfigurationSource,
+    LibraryParameters,
+};
+
+use super::{configuration_provider_error::ConfigurationProviderError, ConfigurationProvider};
+
+/// Provides the configuration of the process, overridden by the library parameters
+/// the application passed to `C_Initialize`
+pub(crate) struct ParameterConfiguration {
+    parameters: LibraryParameters,
+
+    /// Provider of the configuration of the process
+    base: Arc<dyn ConfigurationProvider>,
+}
+
+impl ParameterConfiguration {
+    pub(crate) fn new(parameters: LibraryParameters, base: Arc<dyn ConfigurationProvider>) -> Self {
+        Self { parameters, base }
+    }
+
+    /// Returns the endpoint selected by the parameters, if any
+    fn get_communicator_endpoint(&self) -> Option<CommunicatorEndpoint> {
+        let (Some(hostname), Some(certificate_path)) = (
+            self.parameters.get_hostname(),
+            self.parameters.get_certificate_path(),
+        ) else {
+            return None;
+        };
+        let endpoint = CommunicatorEndpoint::new(hostname.into(), certificate_path.into())
+            .with_port(self.parameters.get_port());
+        Some(endpoint)
+    }
+}
+
+impl ConfigurationProvider for ParameterConfiguration {
+    fn get_interface_configuration(
+        &self,
+    ) -> Result<InterfaceConfiguration, ConfigurationProviderError> {
+        let base_configuration = match self.base.get_interface_configuration() {
+            Ok(configuration) => Some(configuration),
+            // the parameters suffice on their own
+            Err(_) if self.parameters.has_communicator() => None,
+            Err(err) => return Err(err),
+        };
+        let group_id = self.parameters.get_group_id().cloned();
+        let configuration = match (base_configuration, self.get_communicator_endpoint()) {
+            // groups of the process configuration belong to another communicator
+            (Some(configuration), Some(endpoint)) => configuration
+                .with_communicator_endpoints(vec![endpoint])
+                .with_group_id(group_id)
+                .with_group_routes(vec![]),
+            (Some(configuration), None) if group_id.is_some() => {
+                configuration.with_group_id(group_id)
+            }
+            (Some(configuration), None) => configuration,
+            (None, endpoint) => {
+                InterfaceConfiguration::new(endpoint.into_iter().collect(), group_id)
+            }
+        };
+        Ok(configuration)
+    }
+
+    fn get_source(&self) -> ConfigurationSource {
+        ConfigurationSource::LibraryParameters(Box::new(self.base.get_source()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct FailingConfiguration;
+
+    impl ConfigurationProvider for FailingConfiguration {
+        fn get_interface_configuration(
+            &self,
+        ) -> Result<InterfaceConfiguration, ConfigurationProviderError> {
+            Err(ConfigurationProviderError::InvalidFormat)
+        }
+
+        fn get_source(&self) -> ConfigurationSource {
+            ConfigurationSource::Controller
+        }
+    }
+
+    struct StaticConfiguration(InterfaceConfiguration);
+
+    impl ConfigurationProvider for StaticConfiguration {
+        fn get_interface_configuration(
+            &self,
+        ) -> Result<InterfaceConfiguration, ConfigurationProviderError> {
+            Ok(self.0.clone())
+        }
+
+        fn get_source(&self) -> ConfigurationSource {
+            ConfigurationSource::Environment
+        }
+    }
+
+    #[test]
+    fn given_group_id_parameter_it_overrides_the_process_configuration() {
+        let base = InterfaceConfiguration::new(
+            vec![CommunicatorEndpoint::new(
+                "meesign.local".into(),
+                "ca.pem".into(),
+            )],
+            Some(vec![1; 33]),
+        );
+        let parameters = "group_id=abcd".parse().unwrap();
+        let provider = ParameterConfiguration::new(parameters, Arc::new(StaticConfiguration(base)));
+
+        let configuration = provider.get_interface_configuration().unwrap();
+
+        assert_eq!(configuration.get_group_id(), Some(&vec![0xab, 0xcd]));
+        assert_eq!(
+            configuration.get_communicator_endpoints()[0].get_hostname(),
+            "meesign.local"
+        );
+    }
+
+    #[test]
+    fn given_communicator_parameters_process_configuration_is_not_needed() {
+        let parameters = "hostname=other.local certificate_path=other.pem port=1338"
+            .parse()
+            .unwrap();
+        let provider = ParameterConfiguration::new(parameters, Arc::new(FailingConfiguration));
+
+        let configuration = provider.get_interface_configuration().unwrap();
+
+        let endpoints = configuration.get_communicator_endpoints();
+        assert_eq!(endpoints.len(), 1);
+        assert_eq!(endpoints[0].get_hostname(), "other.local");
+        assert_eq!(endpoints[0].get_certificate_path(), "other.pem");
+        assert_eq!(endpoints[0].get_port(), 1338);
+        assert_eq!(configuration.get_group_id(), None);
+    }
+
+    #[test]
+    fn given_group_id_parameter_only_process_configuration_is_needed() {
+        let parameters = "group_id=abcd".parse().unwrap();
+        let provider = ParameterConfiguration::new(parameters, Arc::new(FailingConfiguration));
+
+        assert!(provider.get_interface_configuration().is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::communicator::GroupId;
+
+use super::ConfigurationProviderError;
+
+/// Routes requests of matching originators to a group, e.g., the relying party
+/// of a WebAuthn request or the host an SSH client connects to
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct GroupRoute {
+    /// Case-insensitive pattern of the originator, where `*` stands
+    /// for any sequence of characters and `?` for a single character
+    originator: String,
+    group_id: GroupId,
+}
+
+impl GroupRoute {
+    pub fn new(originator: String, group_id: GroupId) -> Self {
+        Self {
+            originator,
+            group_id,
+        }
+    }
+
+    pub fn get_originator(&self) -> &str {
+        &self.originator
+    }
+
+    pub fn get_group_id(&self) -> &GroupId {
+        &self.group_id
+    }
+
+    /// Returns whether the route applies to the originator
+    ///
+    /// # Arguments
+    ///
+    /// * `originator` - the originator of the request
+    pub fn matches(&self, originator: &str) -> bool {
+        let pattern: Vec<char> = self.originator.to_lowercase().chars().collect();
+        let originator: Vec<char> = originator.to_lowercase().chars().collect();
+        matches_pattern(&pattern, &originator)
+    }
+}
+
+/// Parses a route written as `<originator pattern>=<hex-encoded group ID>`
+impl FromStr for GroupRoute {
+    type Err = ConfigurationProviderError;
+
+    fn from_str(route: &str) -> Result<Self, Self::Err> {
+        let (originator, group_id) = route
+            .rsplit_once('=')
+            .ok_or(ConfigurationProviderError::InvalidFormat)?;
+        let originator = originator.trim();
+        if originator.is_empty() {
+            return Err(ConfigurationProviderError::InvalidFormat);
+        }
+        let group_id = hex::decode(group_id.trim())?;
+        Ok(Self::new(originator.into(), group_id))
+    }
+}
+
+/// The group a request is routed to
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum GroupRouting<'a> {
+    /// No routes are configured, the request uses the key chosen by the caller
+    Unrouted,
+
+    /// The request is signed by the group of the first matching route
+    Routed(&'a GroupId),
+
+    /// Routes are configured, but none of them matches the originator
+    Refused,
+}
+
+/// Picks the group of the first route matching the originator.
+/// Requests without an originator are matched as an empty originator,
+/// so only a catch-all route accepts them.
+///
+/// # Arguments
+///
+/// * `routes` - the configured routes, in order of precedence
+/// * `originator` - the originator of the request, if known
+pub(crate) fn route_originator<'a>(
+    routes: &'a [GroupRoute],
+    originator: Option<&str>,
+) -> GroupRouting<'a> {
+    if routes.is_empty() {
+        return GroupRouting::Unrouted;
+    }
+    let originator = originator.unwrap_or_default();
+    routes
+        .iter()
+        .find(|route| route.matches(originator))
+        .map_or(GroupRouting::Refused, |route| {
+            GroupRouting::Routed(route.get_group_id())
+        })
+}
+
+/// Matches the text against the wildcard pattern in linear space and at most
+/// quadratic time. On a mismatch, the last `*` is retried with one more character
+/// of the text; earlier stars never need to be revisited, as the last one can
+/// absorb anything they could.
+fn matches_pattern(pattern: &[char], text: &[char]) -> bool {
+    let (mut pattern_index, mut text_index) = (0, 0);
+    // the position of the last `*` and of the text it was retried at
+    let mut backtrack: Option<(usize, usize)> = None;
+    while text_index < text.len() {
+        match pattern.get(pattern_index) {
+            Some('*') => {
+                backtrack = Some((pattern_index, text_index));
+                pattern_index += 1;
+            }
+            Some(&expected) if expected == '?' || expected == text[text_index] => {
+                pattern_index += 1;
+                text_index += 1;
+            }
+            _ => match backtrack {
+                Some((star_index, star_text_index)) => {
+                    backtrack = Some((star_index, star_text_index + 1));
+                    pattern_index = star_index + 1;
+                    text_index = star_text_index + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[pattern_index..]
+        .iter()
+        .all(|character| *character == '*')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn get_routes() -> Vec<GroupRoute> {
+        vec![
+            GroupRoute::new("*.corp.example.com".into(), vec![1; 33]),
+            GroupRoute::new("git@github.com".into(), vec![2; 33]),
+            GroupRoute::new("github.com".into(), vec![2; 33]),
+        ]
+    }
+
+    #[test]
+    fn given_matching_originator_the_first_matching_route_is_picked() {
+        let routes = get_routes();
+
+        assert_eq!(
+            route_originator(&routes, Some("sso.CORP.example.com")),
+            GroupRouting::Routed(&vec![1; 33])
+        );
+        assert_eq!(
+            route_originator(&routes, Some("github.com")),
+            GroupRouting::Routed(&vec![2; 33])
+        );
+    }
+
+    #[test]
+    fn given_unmatched_or_missing_originator_the_request_is_refused() {
+        let routes = get_routes();
+
+        assert_eq!(
+            route_originator(&routes, Some("corp.example.com.evil.org")),
+            GroupRouting::Refused
+        );
+        assert_eq!(route_originator(&routes, None), GroupRouting::Refused);
+    }
+
+    #[test]
+    fn given_no_routes_requests_are_unrouted() {
+        assert_eq!(
+            route_originator(&[], Some("github.com")),
+            GroupRouting::Unrouted
+        );
+    }
+
+    #[test]
+    fn given_route_string_it_is_parsed() {
+        let route: GroupRoute = "*.example.com=abcd".parse().unwrap();
+
+        assert_eq!(route.get_originator(), "*.example.com");
+        assert_eq!(route.get_group_id(), &vec![0xab, 0xcd]);
+        assert!("abcd".parse::<GroupRoute>().is_err());
+        assert!("=abcd".parse::<GroupRoute>().is_err());
+    }
+
+    #[test]
+    fn given_wildcards_the_pattern_matches_like_a_glob() {
+        let matches = |pattern: &str, originator: &str| {
+            GroupRoute::new(pattern.into(), vec![]).matches(originator)
+        };
+
+        assert!(matches("*", ""));
+        assert!(matches("a*b*c", "aXXbYYc"));
+        assert!(matches("a*b*c", "abbbc"));
+        assert!(matches("?.example.*", "a.example.org"));
+        assert!(matches("**git**", "git"));
+        assert!(!matches("a*b*c", "aXXbYY"));
+        assert!(!matches("?", ""));
+        assert!(!matches("github.com", "github.co"));
+    }
+
+    #[test]
+    fn given_many_stars_and_a_near_miss_the_pattern_is_matched_quickly() {
+        let pattern = "*a".repeat(64) + "b";
+        let originator = "a".repeat(512);
+
+        assert!(!GroupRoute::new(pattern, vec![]).matches(&originator));
+    }
+}
@@ -1,49 +1,164 @@
+use std::time::Duration;
+
 use serde::Deserialize;
 
-use crate::communicator::GroupId;
+use crate::communicator::{task_name_provider::TaskNameFormat, GroupId};
+
+use super::{
+    communicator_endpoint::CommunicatorEndpoint,
+    configuration_provider::controller_configuration::InterfaceConfigurationResponse,
+    group_route::GroupRoute,
+};
 
-use super::configuration_provider::controller_configuration::InterfaceConfigurationResponse;
+/// Used when the configuration can't be obtained
+pub(crate) static DEFAULT_PENDING_TASK_REUSE_WINDOW: Duration = Duration::from_secs(300);
 
 /// A model holding interface configuration attributes
 #[derive(Deserialize, Clone)]
 pub(crate) struct InterfaceConfiguration {
-    communicator_hostname: String,
-    communicator_certificate_path: String,
+    communicator_endpoints: Vec<CommunicatorEndpoint>,
     group_id: Option<GroupId>,
+
+    /// How long a signing task stays available to a repeated request
+    /// after it was created, e.g., when the process restarts before collecting the result.
+    /// A signing task is available to the same tool repeating the request,
+    /// decryption and key agreement tasks only to the process that created them.
+    pending_task_reuse_window_seconds: Option<u64>,
+
+    /// Whether warnings and errors are forwarded to the server's log endpoint
+    forward_diagnostics: Option<bool>,
+
+    /// Template of the task names shown to the approvers,
+    /// see [`crate::communicator::task_name_template::TaskNameTemplate`]
+    task_name_template: Option<String>,
+
+    /// Whether task names are sent as plain text or as JSON
+    task_name_format: Option<TaskNameFormat>,
+
+    /// Routes requests to groups based on their originator, see [`GroupRoute`]
+    #[serde(default)]
+    group_routes: Vec<GroupRoute>,
 }
 
 impl InterfaceConfiguration {
     pub fn new(
-        communicator_hostname: String,
+        communicator_endpoints: Vec<CommunicatorEndpoint>,
         group_id: Option<GroupId>,
-        communicator_certificate_path: String,
     ) -> Self {
         Self {
-            communicator_hostname,
+            communicator_endpoints,
             group_id,
-            communicator_certificate_path,
+            pending_task_reuse_window_seconds: None,
+            forward_diagnostics: None,
+            task_name_template: None,
+            task_name_format: None,
+            group_routes: vec![],
         }
     }
 
-    pub fn get_communicator_hostname(&self) -> &str {
-        &self.communicator_hostname
+    pub fn with_pending_task_reuse_window(
+        mut self,
+        pending_task_reuse_window_seconds: Option<u64>,
+    ) -> Self {
+        self.pending_task_reuse_window_seconds = pending_task_reuse_window_seconds;
+        self
+    }
+
+    pub fn with_diagnostics_forwarding(mut self, forward_diagnostics: Option<bool>) -> Self {
+        self.forward_diagnostics = forward_diagnostics;
+        self
+    }
+
+    pub fn with_task_names(
+        mut self,
+        task_name_template: Option<String>,
+        task_name_format: Option<TaskNameFormat>,
+    ) -> Self {
+        self.task_name_template = task_name_template;
+        self.task_name_format = task_name_format;
+        self
+    }
+
+    pub fn with_communicator_endpoints(
+        mut self,
+        communicator_endpoints: Vec<CommunicatorEndpoint>,
+    ) -> Self {
+        self.communicator_endpoints = communicator_endpoints;
+        self
+    }
+
+    pub fn with_group_id(mut self, group_id: Option<GroupId>) -> Self {
+        self.group_id = group_id;
+        self
+    }
+
+    pub fn with_group_routes(mut self, group_routes: Vec<GroupRoute>) -> Self {
+        self.group_routes = group_routes;
+        self
+    }
+
+    pub fn get_communicator_endpoints(&self) -> &[CommunicatorEndpoint] {
+        &self.communicator_endpoints
     }
 
     pub fn get_group_id(&self) -> Option<&GroupId> {
         self.group_id.as_ref()
     }
 
-    pub fn get_communicator_certificate_path(&self) -> &str {
-        &self.communicator_certificate_path
+    pub fn get_group_routes(&self) -> &[GroupRoute] {
+        &self.group_routes
+    }
+
+    pub fn get_pending_task_reuse_window(&self) -> Duration {
+        self.pending_task_reuse_window_seconds
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_PENDING_TASK_REUSE_WINDOW)
+    }
+
+    pub fn get_task_name_template(&self) -> Option<&str> {
+        self.task_name_template.as_deref()
+    }
+
+    pub fn get_task_name_format(&self) -> Option<TaskNameFormat> {
+        self.task_name_format
+    }
+
+    pub fn forwards_diagnostics(&self) -> bool {
+        self.forward_diagnostics.unwrap_or(false)
     }
 }
 
 impl From<InterfaceConfigurationResponse> for InterfaceConfiguration {
     fn from(response: InterfaceConfigurationResponse) -> Self {
-        Self {
-            communicator_hostname: response.get_communicator_hostname().to_string(),
-            group_id: Some(response.get_group_id().clone()),
-            communicator_certificate_path: response.get_communicator_certificate_path().to_string(),
-        }
+        let communicator_endpoint = CommunicatorEndpoint::new(
+            response.get_communicator_hostname().to_string(),
+            response.get_communicator_certificate_path().to_string(),
+        )
+        .with_port(response.get_communicator_port())
+        .with_tls_server_name(
+            response
+                .get_communicator_tls_server_name()
+                .map(String::from),
+        )
+        .with_timeouts(
+            response.get_communicator_connect_timeout_seconds(),
+            response.get_communicator_request_timeout_seconds(),
+            response.get_communicator_approval_timeout_seconds(),
+        )
+        .with_retry_policy(
+            response.get_communicator_retry_attempts(),
+            response.get_communicator_retry_initial_backoff_milliseconds(),
+            response.get_communicator_retry_max_backoff_milliseconds(),
+        );
+        let mut communicator_endpoints = vec![communicator_endpoint];
+        communicator_endpoints.extend_from_slice(response.get_additional_communicators());
+        Self::new(communicator_endpoints, response.get_group_id().cloned())
+            .with_pending_task_reuse_window(response.get_pending_task_reuse_window_seconds())
+            .with_diagnostics_forwarding(response.get_forward_diagnostics())
+            .with_task_names(
+                response.get_task_name_template().map(String::from),
+                response.get_task_name_format(),
+            )
+            .with_group_routes(response.get_group_routes().to_vec())
     }
 }
@@ -0,0 +1,163 @@
+use std::{path::PathBuf, str::FromStr};
+
+use crate::communicator::GroupId;
+
+use super::ConfigurationProviderError;
+
+/// Parameters passed by the application in the `pReserved` field of `CK_C_INITIALIZE_ARGS`,
+/// e.g., NSS "LibraryParameters". They override the configuration of the process,
+/// so that applications on the same machine can use different groups.
+///
+/// The parameters are written as whitespace-separated `key=value` pairs, values containing
+/// whitespace can be enclosed in double or single quotes, e.g.,
+/// `hostname=meesign.local certificate_path="/etc/meesign/ca cert.pem" group_id=03ab...`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct LibraryParameters {
+    hostname: Option<String>,
+    certificate_path: Option<String>,
+    port: Option<u16>,
+    group_id: Option<GroupId>,
+
+    /// Directory holding the data of the library instead of `~/.cryptoki-bridge`
+    data_directory: Option<PathBuf>,
+}
+
+impl LibraryParameters {
+    pub fn get_hostname(&self) -> Option<&str> {
+        self.hostname.as_deref()
+    }
+
+    pub fn get_certificate_path(&self) -> Option<&str> {
+        self.certificate_path.as_deref()
+    }
+
+    pub fn get_port(&self) -> Option<u16> {
+        self.port
+    }
+
+    pub fn get_group_id(&self) -> Option<&GroupId> {
+        self.group_id.as_ref()
+    }
+
+    pub fn get_data_directory(&self) -> Option<&PathBuf> {
+        self.data_directory.as_ref()
+    }
+
+    /// Returns whether the parameters select a communicator, so that the configuration
+    /// of the process is not needed
+    pub fn has_communicator(&self) -> bool {
+        self.hostname.is_some() && self.certificate_path.is_some()
+    }
+}
+
+impl FromStr for LibraryParameters {
+    type Err = ConfigurationProviderError;
+
+    fn from_str(parameters: &str) -> Result<Self, Self::Err> {
+        let mut library_parameters = Self::default();
+        for parameter in split_parameters(parameters)? {
+            let (key, value) = parameter.split_once('=').ok_or_else(|| {
+                ConfigurationProviderError::InvalidLibraryParameters(format!(
+                    "{parameter} is not a key=value pair"
+                ))
+            })?;
+            match key {
+                "hostname" => library_parameters.hostname = Some(value.into()),
+                "certificate_path" => library_parameters.certificate_path = Some(value.into()),
+                "port" => {
+                    let port = value.parse().map_err(|_| {
+                        ConfigurationProviderError::InvalidLibraryParameters(format!(
+                            "{value} is not a port"
+                        ))
+                    })?;
+                    library_parameters.port = Some(port);
+                }
+                "group_id" => library_parameters.group_id = Some(hex::decode(value)?),
+                "data_directory" => library_parameters.data_directory = Some(value.into()),
+                _ => {
+                    return Err(ConfigurationProviderError::InvalidLibraryParameters(
+                        format!("{key} is not a known parameter"),
+                    ))
+                }
+            }
+        }
+        if library_parameters.hostname.is_some() != library_parameters.certificate_path.is_some() {
+            return Err(ConfigurationProviderError::InvalidLibraryParameters(
+                "hostname and certificate_path must be passed together".into(),
+            ));
+        }
+        Ok(library_parameters)
+    }
+}
+
+/// Splits the parameters on whitespace outside of quotes, removing the quotes
+fn split_parameters(parameters: &str) -> Result<Vec<String>, ConfigurationProviderError> {
+    let mut split_parameters = vec![];
+    let mut current = String::new();
+    let mut quote = None;
+    for character in parameters.chars() {
+        match (quote, character) {
+            (None, '"' | '\'') => quote = Some(character),
+            (Some(opening), _) if opening == character => quote = None,
+            (None, _) if character.is_whitespace() => {
+                if !current.is_empty() {
+                    split_parameters.push(std::mem::take(&mut current));
+                }
+            }
+            _ => current.push(character),
+        }
+    }
+    if quote.is_some() {
+        return Err(ConfigurationProviderError::InvalidLibraryParameters(
+            "a quote is not closed".into(),
+        ));
+    }
+    if !current.is_empty() {
+        split_parameters.push(current);
+    }
+    Ok(split_parameters)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn given_parameter_string_parameters_are_parsed() {
+        let parameters: LibraryParameters = r#"hostname=meesign.local port=1338
+            certificate_path="/etc/meesign/ca cert.pem" group_id=abcd data_directory='/tmp/app'"#
+            .parse()
+            .unwrap();
+
+        assert_eq!(parameters.get_hostname(), Some("meesign.local"));
+        assert_eq!(
+            parameters.get_certificate_path(),
+            Some("/etc/meesign/ca cert.pem")
+        );
+        assert_eq!(parameters.get_port(), Some(1338));
+        assert_eq!(parameters.get_group_id(), Some(&vec![0xab, 0xcd]));
+        assert_eq!(
+            parameters.get_data_directory(),
+            Some(&PathBuf::from("/tmp/app"))
+        );
+        assert!(parameters.has_communicator());
+    }
+
+    #[test]
+    fn given_empty_string_no_parameters_are_set() {
+        let parameters: LibraryParameters = "  ".parse().unwrap();
+
+        assert_eq!(parameters, LibraryParameters::default());
+    }
+
+    #[test]
+    fn given_invalid_parameters_they_are_refused() {
+        assert!("hostname=meesign.local"
+            .parse::<LibraryParameters>()
+            .is_err());
+        assert!("group=abcd".parse::<LibraryParameters>().is_err());
+        assert!("group_id=xyz".parse::<LibraryParameters>().is_err());
+        assert!("data_directory='/tmp".parse::<LibraryParameters>().is_err());
+        assert!("verbose".parse::<LibraryParameters>().is_err());
+    }
+}
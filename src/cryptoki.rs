@@ -8,8 +8,11 @@ pub mod object_management;
 pub mod session_management;
 pub mod signing;
 pub mod slot_token;
+#[cfg(all(test, not(feature = "mocked_communicator")))]
+mod stand_in_library;
 pub mod unsupported;
 pub(crate) mod utils;
+pub(crate) mod vendor_defined;
 pub(crate) mod bindings {
     #![allow(non_upper_case_globals)]
     #![allow(non_camel_case_types)]
@@ -2,7 +2,13 @@ use std::ptr;
 
 use aes::cipher::{generic_array::GenericArray, BlockDecrypt};
 
-use crate::state::StateAccessor;
+use crate::{
+    communicator::{
+        group::GroupKeyType, request_context::RequestContext, request_kind::RequestKind,
+    },
+    cryptoki_error::CryptokiError,
+    state::{session::single_session::Decryptor, StateAccessor},
+};
 
 use super::{
     bindings::{
@@ -10,7 +16,9 @@ use super::{
         CK_SESSION_HANDLE, CK_ULONG, CK_ULONG_PTR,
     },
     encryption::C_EncryptInit,
+    signing::get_request_originator,
     utils::FromPointer,
+    vendor_defined::{CKA_MEESIGN_KEY_TYPE, CKM_MEESIGN_DECRYPT},
 };
 
 /// Initializes a decryption operation
@@ -26,7 +34,37 @@ pub unsafe fn C_DecryptInit(
     pMechanism: CK_MECHANISM_PTR,
     hKey: CK_OBJECT_HANDLE,
 ) -> CK_RV {
-    unsafe { C_EncryptInit(hSession, pMechanism, hKey) }
+    if pMechanism.is_null() {
+        return CKR_ARGUMENTS_BAD as CK_RV;
+    }
+    let state_accessor = StateAccessor::new();
+    let mechanism = unsafe { *pMechanism };
+    if mechanism.mechanism != CKM_MEESIGN_DECRYPT {
+        if let Err(err) = state_accessor.set_decryptor(&hSession, None) {
+            return err.into_ck_rv();
+        }
+        return unsafe { C_EncryptInit(hSession, pMechanism, hKey) };
+    }
+
+    let key = match state_accessor.get_object(&hSession, &hKey) {
+        Ok(key) => key,
+        Err(err) => return err.into_ck_rv(),
+    };
+    let key_type = key
+        .get_attribute(CKA_MEESIGN_KEY_TYPE)
+        .and_then(|value| GroupKeyType::from_attribute_value(&value));
+    if key_type != Some(GroupKeyType::Decrypt) {
+        return CryptokiError::KeyTypeInconsistent.into_ck_rv();
+    }
+    let request_originator = unsafe { get_request_originator(&mechanism) };
+
+    if let Err(err) =
+        state_accessor.set_decryptor(&hSession, Some(Decryptor::new(key, request_originator)))
+    {
+        return err.into_ck_rv();
+    }
+
+    CKR_OK as CK_RV
 }
 
 /// Decrypts encrypted data in a single part
@@ -46,34 +84,191 @@ pub unsafe fn C_Decrypt(
     pData: CK_BYTE_PTR,
     pulDataLen: CK_ULONG_PTR,
 ) -> CK_RV {
-    // TODO: use C_Encrypt instead of copy-and-paste
     if pEncryptedData.is_null() || pulDataLen.is_null() {
         return CKR_ARGUMENTS_BAD as CK_RV;
     }
     let accessor = StateAccessor::new();
-    let encryptor = match accessor.get_encryptor(&hSession) {
+    let decryptor = match accessor.get_decryptor(&hSession) {
+        Ok(decryptor) => decryptor,
+        Err(err) => return err.into_ck_rv(),
+    };
+    let data = unsafe { Vec::from_pointer(pEncryptedData, ulEncryptedDataLen as usize) };
+    match decryptor {
+        Some(decryptor) => unsafe {
+            decrypt_using_group(&accessor, hSession, decryptor, data, pData, pulDataLen)
+        },
+        None => unsafe { decrypt_locally(&accessor, hSession, data, pData, pulDataLen) },
+    }
+}
+
+/// Sends the ciphertext to the decryption group and waits for the plaintext.
+/// The plaintext is kept until it is retrieved, so that the caller
+/// can query its length first.
+///
+/// # Arguments
+///
+/// * `accessor` - accesses the state of the library
+/// * `session_handle` - the session’s handle
+/// * `decryptor` - the decryptor of the session
+/// * `data` - the encrypted data
+/// * `output` - points to the location that receives the recovered data
+/// * `output_length` - points to the location that holds the length of the recovered data
+unsafe fn decrypt_using_group(
+    accessor: &StateAccessor,
+    session_handle: CK_SESSION_HANDLE,
+    decryptor: Decryptor,
+    data: Vec<u8>,
+    output: CK_BYTE_PTR,
+    output_length: CK_ULONG_PTR,
+) -> CK_RV {
+    let plaintext = match decryptor.response {
+        Some(plaintext) => plaintext,
+        None => {
+            let group_id = decryptor.key.get_value().unwrap();
+            let request_context = RequestContext::new(decryptor.request_originator);
+            let communicator_id = match accessor.get_communicator_id(&session_handle) {
+                Ok(communicator_id) => communicator_id,
+                Err(err) => return err.into_ck_rv(),
+            };
+            let cancellation_token = match accessor.start_cancellable_operation(&session_handle) {
+                Ok(cancellation_token) => cancellation_token,
+                Err(err) => return err.into_ck_rv(),
+            };
+            let plaintext = match accessor.send_request_wait_for_response(
+                communicator_id,
+                group_id,
+                RequestKind::Decryption,
+                data,
+                request_context,
+                cancellation_token,
+            ) {
+                Ok(plaintext) => plaintext,
+                Err(err) => {
+                    println!("Decryption request failed.");
+                    return err.into_ck_rv();
+                }
+            };
+            if let Err(err) = accessor.store_decryption_response(&session_handle, plaintext.clone())
+            {
+                return err.into_ck_rv();
+            }
+            plaintext
+        }
+    };
+
+    unsafe {
+        *output_length = plaintext.len() as CK_ULONG;
+    }
+    if !output.is_null() {
+        unsafe {
+            ptr::copy(plaintext.as_ptr(), output, plaintext.len());
+        }
+    }
+
+    CKR_OK as CK_RV
+}
+
+/// Decrypts the data using the AES key set by `C_DecryptInit`
+///
+/// # Arguments
+///
+/// * `accessor` - accesses the state of the library
+/// * `session_handle` - the session’s handle
+/// * `data` - the encrypted data
+/// * `output` - points to the location that receives the recovered data
+/// * `output_length` - points to the location that holds the length of the recovered data
+unsafe fn decrypt_locally(
+    accessor: &StateAccessor,
+    session_handle: CK_SESSION_HANDLE,
+    data: Vec<u8>,
+    output: CK_BYTE_PTR,
+    output_length: CK_ULONG_PTR,
+) -> CK_RV {
+    // TODO: use C_Encrypt instead of copy-and-paste
+    let encryptor = match accessor.get_encryptor(&session_handle) {
         Ok(encryptor) => encryptor,
         Err(err) => return err.into_ck_rv(),
     };
 
-    let data = unsafe { Vec::from_pointer(pEncryptedData, ulEncryptedDataLen as usize) };
     let mut cipher_length = 0;
     // TODO: check block length
     for block_i in 0..(data.len() / 16) {
         let mut block =
             GenericArray::from_slice(&data[(16 * block_i)..(16 * (block_i + 1))]).to_owned();
         encryptor.decrypt_block(&mut block);
-        if !pData.is_null() {
+        if !output.is_null() {
             unsafe {
-                ptr::copy(block.as_ptr(), pData.add(block_i * 16), block.len());
+                ptr::copy(block.as_ptr(), output.add(block_i * 16), block.len());
             }
         }
         cipher_length += block.len();
     }
 
     unsafe {
-        *pulDataLen = cipher_length as CK_ULONG;
+        *output_length = cipher_length as CK_ULONG;
     }
 
     CKR_OK as CK_RV
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(not(feature = "mocked_communicator"))]
+    #[test]
+    fn given_pending_request_c_cancel_function_cancels_c_decrypt() {
+        use std::thread;
+
+        use crate::{
+            communicator::meesign::stand_in::{encrypt_for_group, MeesignStandIn, TaskState},
+            cryptoki::{
+                bindings::{CKR_FUNCTION_CANCELED, CK_MECHANISM, CK_VOID_PTR, NULL_PTR},
+                session_management::C_CancelFunction,
+                stand_in_library::StandInLibrary,
+            },
+        };
+
+        // the task is never decided
+        let library = StandInLibrary::start(
+            MeesignStandIn::new()
+                .with_group("secrets", GroupKeyType::Decrypt)
+                .with_task_script(vec![TaskState::Created, TaskState::Running]),
+        );
+        assert_eq!(library.initialize(), CKR_OK as CK_RV);
+        let (session_handle, private_key) = library.open_session_with_group_key();
+
+        let mut decryption_mechanism = CK_MECHANISM {
+            mechanism: CKM_MEESIGN_DECRYPT,
+            pParameter: NULL_PTR as CK_VOID_PTR,
+            ulParameterLen: 0,
+        };
+        assert_eq!(
+            unsafe { C_DecryptInit(session_handle, &mut decryption_mechanism, private_key) },
+            CKR_OK as CK_RV
+        );
+        let group_id = StateAccessor::new()
+            .get_object(&session_handle, &private_key)
+            .unwrap()
+            .get_value()
+            .unwrap();
+        let mut ciphertext = encrypt_for_group(&group_id, b"secret");
+
+        let decryption = thread::spawn(move || {
+            let mut plaintext_length: CK_ULONG = 0;
+            unsafe {
+                C_Decrypt(
+                    session_handle,
+                    ciphertext.as_mut_ptr(),
+                    ciphertext.len() as CK_ULONG,
+                    ptr::null_mut(),
+                    &mut plaintext_length,
+                )
+            }
+        });
+        library.wait_for_task();
+
+        assert_eq!(C_CancelFunction(session_handle), CKR_OK as CK_RV);
+        assert_eq!(decryption.join().unwrap(), CKR_FUNCTION_CANCELED as CK_RV);
+    }
+}
@@ -1,19 +1,20 @@
-use std::mem;
+use std::{ffi::CStr, mem, os::raw::c_char};
 
 use super::{
     bindings::{
-        CKR_ARGUMENTS_BAD, CKR_HOST_MEMORY, CKR_OK, CK_FUNCTION_LIST, CK_FUNCTION_LIST_PTR_PTR,
-        CK_INFO, CK_INFO_PTR, CK_RV, CK_VERSION, CK_VOID_PTR,
+        CKF_LIBRARY_CANT_CREATE_OS_THREADS, CKF_OS_LOCKING_OK, CKR_ARGUMENTS_BAD, CKR_CANT_LOCK,
+        CKR_HOST_MEMORY, CKR_OK, CK_C_INITIALIZE_ARGS, CK_FLAGS, CK_FUNCTION_LIST,
+        CK_FUNCTION_LIST_PTR_PTR, CK_INFO, CK_INFO_PTR, CK_RV, CK_VERSION, CK_VOID_PTR,
     },
     decryption::{C_Decrypt, C_DecryptInit},
     encryption::{C_Encrypt, C_EncryptInit},
-    key_management::{C_GenerateKey, C_GenerateKeyPair, C_UnwrapKey, C_WrapKey},
+    key_management::{C_DeriveKey, C_GenerateKey, C_GenerateKeyPair, C_UnwrapKey, C_WrapKey},
     message_digesting::{C_Digest, C_DigestInit},
     object_management::{
         C_CreateObject, C_DestroyObject, C_FindObjects, C_FindObjectsFinal, C_FindObjectsInit,
         C_GetAttributeValue,
     },
-    session_management::{C_CloseSession, C_Login, C_Logout, C_OpenSession},
+    session_management::{C_CancelFunction, C_CloseSession, C_Login, C_Logout, C_OpenSession},
     signing::{C_Sign, C_SignInit},
     slot_token::{C_GetSlotInfo, C_GetSlotList, C_GetTokenInfo},
     unsupported,
@@ -22,7 +23,10 @@ use crate::package_info::{
     IMPLEMENTATION_MAJOR_VERSION, IMPLEMENTATION_MINOR_VERSION, STANDARD_MAJOR_VERSION,
     STANDARD_MINOR_VERSION,
 };
-use crate::state::StateAccessor;
+use crate::{
+    configuration::{ConfigurationProviderError, LibraryParameters},
+    state::{StateAccessor, ThreadingModel},
+};
 
 /// Initializes the Cryptoki library
 ///
@@ -30,14 +34,85 @@ use crate::state::StateAccessor;
 ///
 /// * `pInitArgs` - either has the value NULL_PTR or points to a CK_C_INITIALIZE_ARGS structure containing information on how the library should deal with multi-threaded access
 #[cryptoki_macros::cryptoki_function]
-pub fn C_Initialize(_pInitArgs: CK_VOID_PTR) -> CK_RV {
+pub unsafe fn C_Initialize(pInitArgs: CK_VOID_PTR) -> CK_RV {
+    let threading_model = match unsafe { get_threading_model(pInitArgs) } {
+        Ok(threading_model) => threading_model,
+        Err(rv) => return rv,
+    };
+    // malformed parameters are refused only once the library is known to be uninitialized
+    let parameters = unsafe { get_library_parameters(pInitArgs) };
     let state_accessor = StateAccessor::new();
-    if let Err(err) = state_accessor.initialize_state() {
+    if let Err(err) = state_accessor.initialize_state(parameters, threading_model) {
         return err.into_ck_rv();
     }
     CKR_OK as CK_RV
 }
 
+/// Validates the locking and threading flags of the initialization arguments
+/// and returns how the library may use threads. The mutex callbacks must be passed
+/// either all or none. The library locks its state with OS primitives, so the callbacks
+/// are acceptable only if the application allows OS locking too.
+///
+/// # Safety
+///
+/// The arguments must be NULL_PTR or point to a CK_C_INITIALIZE_ARGS structure
+///
+/// # Arguments
+///
+/// * `init_args` - the arguments passed to `C_Initialize`
+unsafe fn get_threading_model(init_args: CK_VOID_PTR) -> Result<ThreadingModel, CK_RV> {
+    if init_args.is_null() {
+        return Ok(ThreadingModel::default());
+    }
+    let init_args = unsafe { &*(init_args as *const CK_C_INITIALIZE_ARGS) };
+    let callbacks = [
+        init_args.CreateMutex.is_some(),
+        init_args.DestroyMutex.is_some(),
+        init_args.LockMutex.is_some(),
+        init_args.UnlockMutex.is_some(),
+    ];
+    let has_callbacks = callbacks.iter().all(|&is_set| is_set);
+    if !has_callbacks && callbacks.iter().any(|&is_set| is_set) {
+        return Err(CKR_ARGUMENTS_BAD as CK_RV);
+    }
+    let flags = init_args.flags;
+    if has_callbacks && flags & CKF_OS_LOCKING_OK as CK_FLAGS == 0 {
+        return Err(CKR_CANT_LOCK as CK_RV);
+    }
+    if flags & CKF_LIBRARY_CANT_CREATE_OS_THREADS as CK_FLAGS != 0 {
+        return Ok(ThreadingModel::CallingThreadOnly);
+    }
+    Ok(ThreadingModel::MultiThreaded)
+}
+
+/// Returns the library parameters passed as a NUL-terminated string in the `pReserved`
+/// field of the initialization arguments, see [`LibraryParameters`]
+///
+/// # Safety
+///
+/// The arguments must be NULL_PTR or point to a CK_C_INITIALIZE_ARGS structure,
+/// whose `pReserved` field is NULL_PTR or points to a NUL-terminated string
+///
+/// # Arguments
+///
+/// * `init_args` - the arguments passed to `C_Initialize`
+unsafe fn get_library_parameters(
+    init_args: CK_VOID_PTR,
+) -> Result<Option<LibraryParameters>, ConfigurationProviderError> {
+    if init_args.is_null() {
+        return Ok(None);
+    }
+    let init_args = unsafe { &*(init_args as *const CK_C_INITIALIZE_ARGS) };
+    if init_args.pReserved.is_null() {
+        return Ok(None);
+    }
+    let parameters = unsafe { CStr::from_ptr(init_args.pReserved as *const c_char) };
+    let parameters = parameters.to_str().map_err(|_| {
+        ConfigurationProviderError::InvalidLibraryParameters("not a UTF-8 string".into())
+    })?;
+    parameters.parse().map(Some)
+}
+
 /// The function is called to indicate that an application is finished with the Cryptoki library.
 /// It should be the last Cryptoki call made by an application
 ///
@@ -164,11 +239,11 @@ pub unsafe fn C_GetFunctionList(ppFunctionList: CK_FUNCTION_LIST_PTR_PTR) -> CK_
         C_GenerateKeyPair: Some(C_GenerateKeyPair),
         C_WrapKey: Some(C_WrapKey),
         C_UnwrapKey: Some(C_UnwrapKey),
-        C_DeriveKey: Some(unsupported::C_DeriveKey),
+        C_DeriveKey: Some(C_DeriveKey),
         C_SeedRandom: Some(unsupported::C_SeedRandom),
         C_GenerateRandom: Some(unsupported::C_GenerateRandom),
         C_GetFunctionStatus: Some(unsupported::C_GetFunctionStatus),
-        C_CancelFunction: Some(unsupported::C_CancelFunction),
+        C_CancelFunction: Some(C_CancelFunction),
         C_WaitForSlotEvent: Some(unsupported::C_WaitForSlotEvent),
     };
 
@@ -185,12 +260,17 @@ pub unsafe fn C_GetFunctionList(ppFunctionList: CK_FUNCTION_LIST_PTR_PTR) -> CK_
 
 #[cfg(test)]
 mod test {
+    use std::{ffi::CString, ptr};
+
     use crate::cryptoki::{
         bindings::{
-            CKR_ARGUMENTS_BAD, CKR_OK, CK_FUNCTION_LIST_PTR, CK_FUNCTION_LIST_PTR_PTR, CK_RV,
+            CKF_LIBRARY_CANT_CREATE_OS_THREADS, CKF_OS_LOCKING_OK, CKR_ARGUMENTS_BAD,
+            CKR_CANT_LOCK, CKR_OK, CK_C_INITIALIZE_ARGS, CK_FLAGS, CK_FUNCTION_LIST_PTR,
+            CK_FUNCTION_LIST_PTR_PTR, CK_RV, CK_VOID_PTR, CK_VOID_PTR_PTR,
         },
-        general_purpose::C_GetFunctionList,
+        general_purpose::{get_library_parameters, get_threading_model, C_GetFunctionList},
     };
+    use crate::state::ThreadingModel;
 
     #[test]
     fn c_get_function_list_returns_ckr_ok() {
@@ -215,4 +295,197 @@ mod test {
             "C_GetFunctionList didn't return CKR_ARGUMENTS_BAD",
         );
     }
+
+    fn get_init_args(reserved: CK_VOID_PTR) -> CK_C_INITIALIZE_ARGS {
+        CK_C_INITIALIZE_ARGS {
+            CreateMutex: None,
+            DestroyMutex: None,
+            LockMutex: None,
+            UnlockMutex: None,
+            flags: 0,
+            pReserved: reserved,
+        }
+    }
+
+    #[test]
+    fn given_parameter_string_in_reserved_field_library_parameters_are_read() {
+        let parameters = CString::new("group_id=abcd data_directory=/tmp/app").unwrap();
+        let mut init_args = get_init_args(parameters.as_ptr() as CK_VOID_PTR);
+
+        let parameters = unsafe { get_library_parameters(&mut init_args as *mut _ as CK_VOID_PTR) }
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(parameters.get_group_id(), Some(&vec![0xab, 0xcd]));
+    }
+
+    #[test]
+    fn given_no_reserved_field_no_library_parameters_are_read() {
+        let mut init_args = get_init_args(ptr::null_mut());
+
+        let from_args = unsafe { get_library_parameters(&mut init_args as *mut _ as CK_VOID_PTR) };
+        let from_null = unsafe { get_library_parameters(ptr::null_mut()) };
+
+        assert!(matches!(from_args, Ok(None)));
+        assert!(matches!(from_null, Ok(None)));
+    }
+
+    #[cfg(not(feature = "mocked_communicator"))]
+    #[test]
+    fn given_library_parameters_c_initialize_connects_to_the_given_server() {
+        use crate::{
+            communicator::{group::GroupKeyType, meesign::stand_in::MeesignStandIn},
+            cryptoki::{slot_token::C_GetSlotList, stand_in_library::StandInLibrary},
+        };
+
+        let library = StandInLibrary::start(
+            MeesignStandIn::new()
+                .with_group("first", GroupKeyType::SignChallenge)
+                .with_group("second", GroupKeyType::SignPdf),
+        );
+        assert_eq!(library.initialize(), CKR_OK as CK_RV);
+
+        let mut slot_count = 0;
+        assert_eq!(
+            unsafe { C_GetSlotList(0, ptr::null_mut(), &mut slot_count) },
+            CKR_OK as CK_RV
+        );
+        assert_eq!(slot_count, 2);
+        // the device identity is kept in the data directory of the parameters
+        assert_eq!(library.get_stand_in().get_device_ids().len(), 1);
+    }
+
+    #[cfg(not(feature = "mocked_communicator"))]
+    #[test]
+    fn given_initialized_library_the_lifecycle_follows_the_return_codes_of_the_spec() {
+        use crate::{
+            communicator::{group::GroupKeyType, meesign::stand_in::MeesignStandIn},
+            cryptoki::{
+                bindings::{CKR_CRYPTOKI_ALREADY_INITIALIZED, CKR_CRYPTOKI_NOT_INITIALIZED},
+                general_purpose::C_Finalize,
+                slot_token::C_GetSlotList,
+                stand_in_library::StandInLibrary,
+            },
+        };
+
+        let library = StandInLibrary::start(
+            MeesignStandIn::new().with_group("lifecycle", GroupKeyType::SignChallenge),
+        );
+        assert_eq!(library.initialize(), CKR_OK as CK_RV);
+        assert_eq!(
+            library.initialize(),
+            CKR_CRYPTOKI_ALREADY_INITIALIZED as CK_RV
+        );
+
+        assert_eq!(C_Finalize(ptr::null_mut()), CKR_OK as CK_RV);
+        assert_eq!(
+            C_Finalize(ptr::null_mut()),
+            CKR_CRYPTOKI_NOT_INITIALIZED as CK_RV
+        );
+        let mut slot_count = 0;
+        assert_eq!(
+            unsafe { C_GetSlotList(0, ptr::null_mut(), &mut slot_count) },
+            CKR_CRYPTOKI_NOT_INITIALIZED as CK_RV
+        );
+
+        // the torn down state is rebuilt from scratch
+        assert_eq!(library.initialize(), CKR_OK as CK_RV);
+        library.get_slot();
+    }
+
+    #[cfg(not(feature = "mocked_communicator"))]
+    #[test]
+    fn given_initialized_library_malformed_parameters_report_the_second_initialization() {
+        use crate::{
+            communicator::{group::GroupKeyType, meesign::stand_in::MeesignStandIn},
+            cryptoki::{
+                bindings::CKR_CRYPTOKI_ALREADY_INITIALIZED, general_purpose::C_Initialize,
+                stand_in_library::StandInLibrary,
+            },
+        };
+
+        let library = StandInLibrary::start(
+            MeesignStandIn::new().with_group("lifecycle", GroupKeyType::SignChallenge),
+        );
+        let parameters = CString::new("group_id=xyz").unwrap();
+        let mut init_args = get_init_args(parameters.as_ptr() as CK_VOID_PTR);
+        let init_args = &mut init_args as *mut _ as CK_VOID_PTR;
+        assert_eq!(
+            unsafe { C_Initialize(init_args) },
+            CKR_ARGUMENTS_BAD as CK_RV
+        );
+
+        assert_eq!(library.initialize(), CKR_OK as CK_RV);
+        assert_eq!(
+            unsafe { C_Initialize(init_args) },
+            CKR_CRYPTOKI_ALREADY_INITIALIZED as CK_RV
+        );
+    }
+
+    unsafe extern "C" fn create_mutex(_mutex: CK_VOID_PTR_PTR) -> CK_RV {
+        CKR_OK as CK_RV
+    }
+
+    unsafe extern "C" fn use_mutex(_mutex: CK_VOID_PTR) -> CK_RV {
+        CKR_OK as CK_RV
+    }
+
+    fn get_threading_model_for(
+        flags: u32,
+        init_args: &mut CK_C_INITIALIZE_ARGS,
+    ) -> Result<ThreadingModel, CK_RV> {
+        init_args.flags = flags as CK_FLAGS;
+        unsafe { get_threading_model(init_args as *mut _ as CK_VOID_PTR) }
+    }
+
+    #[test]
+    fn given_flags_without_callbacks_the_threading_model_follows_the_flags() {
+        let mut init_args = get_init_args(ptr::null_mut());
+
+        assert_eq!(
+            get_threading_model_for(0, &mut init_args),
+            Ok(ThreadingModel::MultiThreaded)
+        );
+        assert_eq!(
+            get_threading_model_for(CKF_LIBRARY_CANT_CREATE_OS_THREADS, &mut init_args),
+            Ok(ThreadingModel::CallingThreadOnly)
+        );
+        assert_eq!(
+            unsafe { get_threading_model(ptr::null_mut()) },
+            Ok(ThreadingModel::MultiThreaded)
+        );
+    }
+
+    #[test]
+    fn given_some_mutex_callbacks_the_arguments_are_bad() {
+        let mut init_args = get_init_args(ptr::null_mut());
+        init_args.CreateMutex = Some(create_mutex);
+        init_args.LockMutex = Some(use_mutex);
+
+        assert_eq!(
+            get_threading_model_for(CKF_OS_LOCKING_OK, &mut init_args),
+            Err(CKR_ARGUMENTS_BAD as CK_RV)
+        );
+    }
+
+    #[test]
+    fn given_mutex_callbacks_os_locking_is_required() {
+        let mut init_args = get_init_args(ptr::null_mut());
+        init_args.CreateMutex = Some(create_mutex);
+        init_args.DestroyMutex = Some(use_mutex);
+        init_args.LockMutex = Some(use_mutex);
+        init_args.UnlockMutex = Some(use_mutex);
+
+        assert_eq!(
+            get_threading_model_for(0, &mut init_args),
+            Err(CKR_CANT_LOCK as CK_RV)
+        );
+        assert_eq!(
+            get_threading_model_for(
+                CKF_OS_LOCKING_OK | CKF_LIBRARY_CANT_CREATE_OS_THREADS,
+                &mut init_args
+            ),
+            Ok(ThreadingModel::CallingThreadOnly)
+        );
+    }
 }
@@ -1,25 +1,43 @@
-use std::{ptr, sync::Arc};
+use std::{mem, ptr, sync::Arc};
 
 use rand::{rngs::OsRng, Rng};
 
 use super::{
     bindings::{
-        CKM_AES_KEY_GEN, CKM_AES_KEY_WRAP, CKM_AES_KEY_WRAP_PAD, CKM_ECDSA_KEY_PAIR_GEN,
-        CKR_ARGUMENTS_BAD, CKR_FUNCTION_NOT_SUPPORTED, CKR_MECHANISM_INVALID, CKR_OK,
-        CK_ATTRIBUTE_PTR, CK_BYTE_PTR, CK_MECHANISM_PTR, CK_MECHANISM_TYPE, CK_OBJECT_HANDLE,
-        CK_OBJECT_HANDLE_PTR, CK_RV, CK_SESSION_HANDLE, CK_ULONG, CK_ULONG_PTR,
+        CKA_CLASS, CKA_EC_PARAMS, CKA_KEY_TYPE, CKA_LABEL, CKA_TOKEN, CKA_VALUE_LEN, CKD_NULL,
+        CKK_ECDSA, CKK_EC_EDWARDS, CKK_GENERIC_SECRET, CKM_AES_KEY_GEN, CKM_AES_KEY_WRAP,
+        CKM_AES_KEY_WRAP_PAD, CKM_ECDH1_DERIVE, CKM_ECDSA_KEY_PAIR_GEN,
+        CKM_EC_EDWARDS_KEY_PAIR_GEN, CKO_SECRET_KEY, CKR_ARGUMENTS_BAD, CKR_FUNCTION_NOT_SUPPORTED,
+        CKR_MECHANISM_INVALID, CKR_MECHANISM_PARAM_INVALID, CKR_OK, CKR_TEMPLATE_INCONSISTENT,
+        CK_ATTRIBUTE_PTR, CK_ATTRIBUTE_TYPE, CK_BYTE_PTR, CK_ECDH1_DERIVE_PARAMS, CK_EC_KDF_TYPE,
+        CK_MECHANISM_PTR, CK_MECHANISM_TYPE, CK_OBJECT_HANDLE, CK_OBJECT_HANDLE_PTR, CK_RV,
+        CK_SESSION_HANDLE, CK_TRUE, CK_ULONG, CK_ULONG_PTR,
     },
     internals::encryption::{
         compute_pkcs7_padded_ciphertext_size, decrypt, destructure_iv_ciphertext, encrypt_pad,
     },
     utils::FromPointer,
+    vendor_defined::{CKA_MEESIGN_DEVICE_IDS, CKA_MEESIGN_KEY_TYPE, CKA_MEESIGN_THRESHOLD},
 };
-use crate::state::{
-    object::{
-        cryptoki_object::CryptokiObject, private_key_object::PrivateKeyObject,
-        secret_key_object::SecretKeyObject, template::Template,
+use crate::{
+    communicator::{
+        device_identity::DeviceId,
+        group::{GroupCurve, GroupKeyType, GroupParameters},
+        request_context::RequestContext,
+        request_kind::RequestKind,
     },
-    StateAccessor,
+    cryptoki_error::CryptokiError,
+    state::{
+        object::{
+            attribute::{Attribute, ToAttributeValue},
+            cryptoki_object::CryptokiObject,
+            private_key_object::PrivateKeyObject,
+            secret_key_object::SecretKeyObject,
+            template::Template,
+        },
+        StateAccessor,
+    },
+    utils::parse_p256_point,
 };
 
 pub(crate) type Aes128CbcEnc = cbc::Encryptor<aes::Aes128>;
@@ -72,6 +90,11 @@ pub unsafe fn C_GenerateKey(
 
 /// Generates a public/private key pair, creating new key objects
 ///
+/// Without the `CKA_MEESIGN_DEVICE_IDS` attribute in the templates, the key pair
+/// of the session's group is returned. Otherwise, a new group is created using
+/// distributed key generation, see [`get_group_parameters`] for the attributes
+/// describing the group. The call returns once the group's members generate the key.
+///
 /// # Arguments
 ///
 /// * `hSession` - the session’s handle
@@ -86,20 +109,50 @@ pub unsafe fn C_GenerateKey(
 pub unsafe fn C_GenerateKeyPair(
     hSession: CK_SESSION_HANDLE,
     pMechanism: CK_MECHANISM_PTR,
-    _pPublicKeyTemplate: CK_ATTRIBUTE_PTR,
-    _ulPublicKeyAttributeCount: CK_ULONG,
-    _pPrivateKeyTemplate: CK_ATTRIBUTE_PTR,
-    _ulPrivateKeyAttributeCount: CK_ULONG,
+    pPublicKeyTemplate: CK_ATTRIBUTE_PTR,
+    ulPublicKeyAttributeCount: CK_ULONG,
+    pPrivateKeyTemplate: CK_ATTRIBUTE_PTR,
+    ulPrivateKeyAttributeCount: CK_ULONG,
     phPublicKey: CK_OBJECT_HANDLE_PTR,
     phPrivateKey: CK_OBJECT_HANDLE_PTR,
 ) -> CK_RV {
+    if pMechanism.is_null()
+        || phPublicKey.is_null()
+        || phPrivateKey.is_null()
+        || (pPublicKeyTemplate.is_null() && ulPublicKeyAttributeCount > 0)
+        || (pPrivateKeyTemplate.is_null() && ulPrivateKeyAttributeCount > 0)
+    {
+        return CKR_ARGUMENTS_BAD as CK_RV;
+    }
     let mechanism = unsafe { *pMechanism };
-    if mechanism.mechanism != CKM_ECDSA_KEY_PAIR_GEN as CK_MECHANISM_TYPE {
-        // we are supporting only ECDSA keys that are already generated externally
+    // we are supporting only EC keys, generated by MeeSign groups
+    if mechanism.mechanism != CKM_ECDSA_KEY_PAIR_GEN as CK_MECHANISM_TYPE
+        && mechanism.mechanism != CKM_EC_EDWARDS_KEY_PAIR_GEN as CK_MECHANISM_TYPE
+    {
         return CKR_MECHANISM_INVALID as CK_RV;
     }
+    let public_key_template = Template::from(unsafe {
+        Vec::from_pointer(pPublicKeyTemplate, ulPublicKeyAttributeCount as usize)
+    });
+    let private_key_template = Template::from(unsafe {
+        Vec::from_pointer(pPrivateKeyTemplate, ulPrivateKeyAttributeCount as usize)
+    });
+
     let state_accessor = StateAccessor::new();
-    let (private_key_handle, pubkey_handle) = match state_accessor.get_keypair(&hSession) {
+    let is_key_generation = [&public_key_template, &private_key_template]
+        .iter()
+        .any(|template| template.get_value(&CKA_MEESIGN_DEVICE_IDS).is_some());
+    let key_pair = match is_key_generation {
+        true => generate_group_key_pair(
+            &state_accessor,
+            &hSession,
+            mechanism.mechanism,
+            &public_key_template,
+            &private_key_template,
+        ),
+        false => get_session_key_pair(&state_accessor, &hSession, mechanism.mechanism),
+    };
+    let (private_key_handle, pubkey_handle) = match key_pair {
         Ok(val) => val,
         Err(err) => return err.into_ck_rv(),
     };
@@ -112,6 +165,148 @@ pub unsafe fn C_GenerateKeyPair(
     CKR_OK as CK_RV
 }
 
+/// Returns the key pair of the session's group, as long as the mechanism
+/// generates keys on the group's curve
+///
+/// # Arguments
+///
+/// * `state_accessor` - accesses the session
+/// * `session_handle` - the session’s handle
+/// * `mechanism` - the key generation mechanism
+fn get_session_key_pair(
+    state_accessor: &StateAccessor,
+    session_handle: &CK_SESSION_HANDLE,
+    mechanism: CK_MECHANISM_TYPE,
+) -> Result<(CK_OBJECT_HANDLE, CK_OBJECT_HANDLE), CryptokiError> {
+    let key_type = match mechanism == CKM_EC_EDWARDS_KEY_PAIR_GEN as CK_MECHANISM_TYPE {
+        true => CKK_EC_EDWARDS,
+        false => CKK_ECDSA,
+    };
+    let (private_key_handle, pubkey_handle) = state_accessor.get_keypair(session_handle)?;
+    let private_key = state_accessor.get_object(session_handle, &private_key_handle)?;
+    // the group's curve can't be changed, e.g., Ed25519 keys come only from FROST groups
+    if private_key.get_attribute(CKA_KEY_TYPE as CK_ATTRIBUTE_TYPE)
+        != Some(key_type.to_attribute_value())
+    {
+        return Err(CryptokiError::MechanismInvalid);
+    }
+    Ok((private_key_handle, pubkey_handle))
+}
+
+/// Creates a new group using distributed key generation and returns its key pair
+///
+/// # Arguments
+///
+/// * `state_accessor` - accesses the session
+/// * `session_handle` - the session’s handle
+/// * `mechanism` - the key generation mechanism
+/// * `public_key_template` - the template for the public key
+/// * `private_key_template` - the template for the private key
+fn generate_group_key_pair(
+    state_accessor: &StateAccessor,
+    session_handle: &CK_SESSION_HANDLE,
+    mechanism: CK_MECHANISM_TYPE,
+    public_key_template: &Template,
+    private_key_template: &Template,
+) -> Result<(CK_OBJECT_HANDLE, CK_OBJECT_HANDLE), CryptokiError> {
+    let ec_params = public_key_template.get_value(&(CKA_EC_PARAMS as CK_ATTRIBUTE_TYPE));
+    let curve = get_key_pair_curve(mechanism, ec_params)?;
+    let parameters = get_group_parameters(curve, public_key_template, private_key_template)?;
+    let cancellation_token = state_accessor.start_cancellable_operation(session_handle)?;
+    state_accessor.create_group_wait_for_key_pair(session_handle, parameters, cancellation_token)
+}
+
+/// Returns the curve of the key pair generated by the mechanism.
+/// `CKA_EC_PARAMS` of the public key template selects the curve of ECDSA keys,
+/// P-256 is used if not specified.
+///
+/// # Arguments
+///
+/// * `mechanism` - the key generation mechanism
+/// * `ec_params` - the `CKA_EC_PARAMS` of the public key template, if any
+fn get_key_pair_curve(
+    mechanism: CK_MECHANISM_TYPE,
+    ec_params: Option<Vec<u8>>,
+) -> Result<GroupCurve, CryptokiError> {
+    let is_edwards_mechanism = mechanism == CKM_EC_EDWARDS_KEY_PAIR_GEN as CK_MECHANISM_TYPE;
+    let curve = match ec_params {
+        Some(ec_params) => {
+            GroupCurve::from_ec_params(&ec_params).ok_or(CryptokiError::DomainParamsInvalid)?
+        }
+        None if is_edwards_mechanism => GroupCurve::Ed25519,
+        None => GroupCurve::P256,
+    };
+    if (curve == GroupCurve::Ed25519) != is_edwards_mechanism {
+        return Err(CryptokiError::DomainParamsInvalid);
+    }
+    Ok(curve)
+}
+
+/// Reads the group to be created from the key pair templates, attributes
+/// of the public key template take precedence over the private key template ones.
+///
+/// * `CKA_LABEL` - the name of the group
+/// * `CKA_MEESIGN_DEVICE_IDS` - the group's devices, see [`CKA_MEESIGN_DEVICE_IDS`]
+/// * `CKA_MEESIGN_THRESHOLD` - the number of devices required to use the key
+/// * `CKA_MEESIGN_KEY_TYPE` - the purpose of the key, authentication if not specified
+///
+/// # Arguments
+///
+/// * `curve` - the curve of the generated key
+/// * `public_key_template` - the template for the public key
+/// * `private_key_template` - the template for the private key
+fn get_group_parameters(
+    curve: GroupCurve,
+    public_key_template: &Template,
+    private_key_template: &Template,
+) -> Result<GroupParameters, CryptokiError> {
+    let get_value = |attribute_type: CK_ATTRIBUTE_TYPE| {
+        public_key_template
+            .get_value(&attribute_type)
+            .or_else(|| private_key_template.get_value(&attribute_type))
+    };
+    let name =
+        get_value(CKA_LABEL as CK_ATTRIBUTE_TYPE).ok_or(CryptokiError::TemplateIncomplete)?;
+    let name = String::from_utf8(name).map_err(|_| CryptokiError::AttributeValueInvalid)?;
+    let device_ids = get_value(CKA_MEESIGN_DEVICE_IDS).ok_or(CryptokiError::TemplateIncomplete)?;
+    let device_ids = parse_device_ids(&device_ids).ok_or(CryptokiError::AttributeValueInvalid)?;
+    let threshold = get_value(CKA_MEESIGN_THRESHOLD).ok_or(CryptokiError::TemplateIncomplete)?;
+    let threshold = threshold
+        .try_into()
+        .ok()
+        .and_then(|threshold| u32::try_from(CK_ULONG::from_le_bytes(threshold)).ok())
+        .filter(|threshold| (1..=device_ids.len()).contains(&(*threshold as usize)))
+        .ok_or(CryptokiError::AttributeValueInvalid)?;
+    let key_type = match get_value(CKA_MEESIGN_KEY_TYPE) {
+        Some(value) => GroupKeyType::from_attribute_value(&value)
+            .ok_or(CryptokiError::AttributeValueInvalid)?,
+        None => GroupKeyType::SignChallenge,
+    };
+
+    Ok(GroupParameters::new(
+        name, device_ids, threshold, key_type, curve,
+    ))
+}
+
+/// Parses the comma-separated list of hex-encoded device IDs,
+/// returns `None` if the list is empty or malformed
+///
+/// # Arguments
+///
+/// * `value` - the value of the `CKA_MEESIGN_DEVICE_IDS` attribute
+fn parse_device_ids(value: &[u8]) -> Option<Vec<DeviceId>> {
+    let device_ids: Vec<DeviceId> = std::str::from_utf8(value)
+        .ok()?
+        .split(',')
+        .map(|device_id| {
+            hex::decode(device_id.trim())
+                .ok()
+                .filter(|id| !id.is_empty())
+        })
+        .collect::<Option<_>>()?;
+    Some(device_ids).filter(|device_ids| !device_ids.is_empty())
+}
+
 /// Wraps (i.e., encrypts) a private or secret key
 ///
 /// # Arguments
@@ -241,3 +436,421 @@ pub unsafe fn C_UnwrapKey(
     }
     CKR_OK as CK_RV
 }
+
+/// Derives a key from a base key, creating a new key object
+///
+/// Only `CKM_ECDH1_DERIVE` without a key derivation function is supported, the base key
+/// has to be the private key of a decryption group. The peer's public key is sent
+/// to the group, so that the group's private key is never assembled in one place.
+/// The shared secret, i.e., the x coordinate of the shared point, becomes the value
+/// of the new secret key.
+///
+/// # Arguments
+///
+/// * `hSession` - the session’s handle
+/// * `pMechanism` - points to the key derivation mechanism
+/// * `hBaseKey` - the handle of the base key
+/// * `pTemplate` - points to the template for the new key
+/// * `ulAttributeCount` - the number of attributes in the template
+/// * `phKey` - points to the location that receives the handle of the derived key
+#[cryptoki_macros::cryptoki_function]
+pub unsafe fn C_DeriveKey(
+    hSession: CK_SESSION_HANDLE,
+    pMechanism: CK_MECHANISM_PTR,
+    hBaseKey: CK_OBJECT_HANDLE,
+    pTemplate: CK_ATTRIBUTE_PTR,
+    ulAttributeCount: CK_ULONG,
+    phKey: CK_OBJECT_HANDLE_PTR,
+) -> CK_RV {
+    if pMechanism.is_null() || phKey.is_null() || (pTemplate.is_null() && ulAttributeCount > 0) {
+        return CKR_ARGUMENTS_BAD as CK_RV;
+    }
+    let mechanism = unsafe { *pMechanism };
+    if mechanism.mechanism != CKM_ECDH1_DERIVE as CK_MECHANISM_TYPE {
+        return CKR_MECHANISM_INVALID as CK_RV;
+    }
+    if mechanism.pParameter.is_null()
+        || mechanism.ulParameterLen as usize != mem::size_of::<CK_ECDH1_DERIVE_PARAMS>()
+    {
+        return CKR_MECHANISM_PARAM_INVALID as CK_RV;
+    }
+    let parameters = unsafe { *(mechanism.pParameter as *const CK_ECDH1_DERIVE_PARAMS) };
+    // the shared secret is used as it is, key derivation functions are up to the caller
+    if parameters.kdf != CKD_NULL as CK_EC_KDF_TYPE
+        || parameters.ulSharedDataLen != 0
+        || parameters.pPublicData.is_null()
+    {
+        return CKR_MECHANISM_PARAM_INVALID as CK_RV;
+    }
+    let public_key =
+        unsafe { Vec::from_pointer(parameters.pPublicData, parameters.ulPublicDataLen as usize) };
+    let Some(public_key) = parse_p256_point(&public_key) else {
+        return CKR_MECHANISM_PARAM_INVALID as CK_RV;
+    };
+
+    let state_accessor = StateAccessor::new();
+    let base_key = match state_accessor.get_object(&hSession, &hBaseKey) {
+        Ok(key) => key,
+        Err(err) => return err.into_ck_rv(),
+    };
+    let key_type = base_key
+        .get_attribute(CKA_MEESIGN_KEY_TYPE)
+        .and_then(|value| GroupKeyType::from_attribute_value(&value));
+    if key_type != Some(GroupKeyType::Decrypt) {
+        return CryptokiError::KeyTypeInconsistent.into_ck_rv();
+    }
+    let attributes = unsafe { Vec::from_pointer(pTemplate, ulAttributeCount as usize) };
+    let Some(template) = get_derived_key_template(Template::from(attributes)) else {
+        return CKR_TEMPLATE_INCONSISTENT as CK_RV;
+    };
+    let value_length = template
+        .get_value(&(CKA_VALUE_LEN as CK_ATTRIBUTE_TYPE))
+        .and_then(|value| Some(CK_ULONG::from_le_bytes(value.try_into().ok()?) as usize));
+    let is_token_object =
+        template.get_value(&(CKA_TOKEN as CK_ATTRIBUTE_TYPE)) == Some(vec![CK_TRUE as u8]);
+
+    let communicator_id = match state_accessor.get_communicator_id(&hSession) {
+        Ok(communicator_id) => communicator_id,
+        Err(err) => return err.into_ck_rv(),
+    };
+    let cancellation_token = match state_accessor.start_cancellable_operation(&hSession) {
+        Ok(cancellation_token) => cancellation_token,
+        Err(err) => return err.into_ck_rv(),
+    };
+    let mut shared_secret = match state_accessor.send_request_wait_for_response(
+        communicator_id,
+        base_key.get_value().unwrap(),
+        RequestKind::KeyAgreement,
+        public_key,
+        RequestContext::default(),
+        cancellation_token,
+    ) {
+        Ok(shared_secret) => shared_secret,
+        Err(err) => {
+            println!("Key agreement request failed.");
+            return err.into_ck_rv();
+        }
+    };
+    if let Some(value_length) = value_length {
+        if value_length > shared_secret.len() {
+            return CKR_TEMPLATE_INCONSISTENT as CK_RV;
+        }
+        shared_secret.truncate(value_length);
+    }
+
+    let mut derived_key = SecretKeyObject::from_template(template);
+    derived_key.store_value(shared_secret);
+    let derived_key = Arc::new(derived_key);
+    let handle = match is_token_object {
+        true => state_accessor.create_object(&hSession, derived_key),
+        false => state_accessor.create_ephemeral_object(&hSession, derived_key),
+    };
+    let handle = match handle {
+        Ok(handle) => handle,
+        Err(err) => return err.into_ck_rv(),
+    };
+    unsafe {
+        *phKey = handle;
+    }
+
+    CKR_OK as CK_RV
+}
+
+/// Completes the caller's template of a derived key, which is a generic secret
+/// unless specified otherwise. Returns `None` if the template describes
+/// an object other than a secret key.
+///
+/// # Arguments
+///
+/// * `template` - the template passed to `C_DeriveKey`
+fn get_derived_key_template(template: Template) -> Option<Template> {
+    let class = CKA_CLASS as CK_ATTRIBUTE_TYPE;
+    let key_type = CKA_KEY_TYPE as CK_ATTRIBUTE_TYPE;
+    let secret_key_class = (CKO_SECRET_KEY as CK_ATTRIBUTE_TYPE).to_le_bytes().to_vec();
+    if template
+        .get_value(&class)
+        .is_some_and(|value| value != secret_key_class)
+    {
+        return None;
+    }
+    let mut attributes = template.into_attributes();
+    attributes.insert(class, Some(secret_key_class));
+    attributes
+        .entry(key_type)
+        .or_insert_with(|| Some(CKK_GENERIC_SECRET.to_attribute_value()));
+    let attributes = attributes
+        .into_iter()
+        .map(|(attribute_type, value)| Attribute::new(attribute_type, value))
+        .collect();
+    Some(Template::from_vec(attributes))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cryptoki::bindings::{CKA_LABEL, CKK_AES, CKO_PRIVATE_KEY};
+
+    use super::*;
+
+    #[cfg(not(feature = "mocked_communicator"))]
+    #[test]
+    fn given_pending_request_c_cancel_function_cancels_c_derive_key() {
+        use std::thread;
+
+        use crate::{
+            communicator::meesign::stand_in::{MeesignStandIn, TaskState},
+            cryptoki::{
+                bindings::{CKR_FUNCTION_CANCELED, CK_MECHANISM, CK_VOID_PTR},
+                session_management::C_CancelFunction,
+                stand_in_library::StandInLibrary,
+            },
+        };
+
+        // the task is never decided
+        let library = StandInLibrary::start(
+            MeesignStandIn::new()
+                .with_group("secrets", GroupKeyType::Decrypt)
+                .with_task_script(vec![TaskState::Created, TaskState::Running]),
+        );
+        assert_eq!(library.initialize(), CKR_OK as CK_RV);
+        let (session_handle, private_key) = library.open_session_with_group_key();
+        // any point on the curve will do, e.g., the group's own public key
+        let public_key = StateAccessor::new()
+            .get_object(&session_handle, &private_key)
+            .unwrap()
+            .get_value()
+            .unwrap();
+
+        let derivation = thread::spawn(move || {
+            let mut public_key = public_key;
+            let mut parameters = CK_ECDH1_DERIVE_PARAMS {
+                kdf: CKD_NULL as CK_EC_KDF_TYPE,
+                ulSharedDataLen: 0,
+                pSharedData: ptr::null_mut(),
+                ulPublicDataLen: public_key.len() as CK_ULONG,
+                pPublicData: public_key.as_mut_ptr(),
+            };
+            let mut mechanism = CK_MECHANISM {
+                mechanism: CKM_ECDH1_DERIVE as CK_MECHANISM_TYPE,
+                pParameter: &mut parameters as *mut _ as CK_VOID_PTR,
+                ulParameterLen: mem::size_of::<CK_ECDH1_DERIVE_PARAMS>() as CK_ULONG,
+            };
+            let mut derived_key: CK_OBJECT_HANDLE = 0;
+            unsafe {
+                C_DeriveKey(
+                    session_handle,
+                    &mut mechanism,
+                    private_key,
+                    ptr::null_mut(),
+                    0,
+                    &mut derived_key,
+                )
+            }
+        });
+        library.wait_for_task();
+
+        assert_eq!(C_CancelFunction(session_handle), CKR_OK as CK_RV);
+        assert_eq!(derivation.join().unwrap(), CKR_FUNCTION_CANCELED as CK_RV);
+    }
+
+    #[cfg(not(feature = "mocked_communicator"))]
+    #[test]
+    fn given_pending_key_generation_c_cancel_function_cancels_c_generate_key_pair() {
+        use std::thread;
+
+        use crate::{
+            communicator::meesign::stand_in::{MeesignStandIn, TaskState},
+            cryptoki::{
+                bindings::{
+                    CKF_SERIAL_SESSION, CKR_FUNCTION_CANCELED, CK_ATTRIBUTE, CK_FLAGS,
+                    CK_MECHANISM, CK_VOID_PTR, NULL_PTR,
+                },
+                session_management::{C_CancelFunction, C_OpenSession},
+                stand_in_library::StandInLibrary,
+            },
+        };
+
+        // the key generation never finishes
+        let library = StandInLibrary::start(
+            MeesignStandIn::new()
+                .with_group("existing", GroupKeyType::SignChallenge)
+                .with_task_script(vec![TaskState::Created, TaskState::Running]),
+        );
+        assert_eq!(library.initialize(), CKR_OK as CK_RV);
+        let mut session_handle = 0;
+        assert_eq!(
+            unsafe {
+                C_OpenSession(
+                    library.get_slot(),
+                    CKF_SERIAL_SESSION as CK_FLAGS,
+                    NULL_PTR as CK_VOID_PTR,
+                    None,
+                    &mut session_handle,
+                )
+            },
+            CKR_OK as CK_RV
+        );
+
+        let generation = thread::spawn(move || {
+            let label = b"new group".to_vec();
+            let device_ids = b"0a0b,0c0d".to_vec();
+            let threshold = 2u32.to_attribute_value();
+            let mut template = [
+                (CKA_LABEL as CK_ATTRIBUTE_TYPE, &label),
+                (CKA_MEESIGN_DEVICE_IDS, &device_ids),
+                (CKA_MEESIGN_THRESHOLD, &threshold),
+            ]
+            .map(|(type_, value)| CK_ATTRIBUTE {
+                type_,
+                pValue: value.as_ptr() as CK_VOID_PTR,
+                ulValueLen: value.len() as CK_ULONG,
+            });
+            let mut mechanism = CK_MECHANISM {
+                mechanism: CKM_ECDSA_KEY_PAIR_GEN as CK_MECHANISM_TYPE,
+                pParameter: NULL_PTR as CK_VOID_PTR,
+                ulParameterLen: 0,
+            };
+            let mut public_key: CK_OBJECT_HANDLE = 0;
+            let mut private_key: CK_OBJECT_HANDLE = 0;
+            unsafe {
+                C_GenerateKeyPair(
+                    session_handle,
+                    &mut mechanism,
+                    template.as_mut_ptr(),
+                    template.len() as CK_ULONG,
+                    ptr::null_mut(),
+                    0,
+                    &mut public_key,
+                    &mut private_key,
+                )
+            }
+        });
+        library.wait_for_task();
+
+        assert_eq!(C_CancelFunction(session_handle), CKR_OK as CK_RV);
+        assert_eq!(generation.join().unwrap(), CKR_FUNCTION_CANCELED as CK_RV);
+    }
+
+    #[test]
+    fn given_template_without_class_derived_key_is_a_generic_secret() {
+        let template = Template::from_vec(vec![Attribute::from_parts(CKA_LABEL, "shared")]);
+        let template = get_derived_key_template(template).unwrap();
+
+        assert_eq!(
+            template.get_value(&(CKA_KEY_TYPE as CK_ATTRIBUTE_TYPE)),
+            Some(CKK_GENERIC_SECRET.to_attribute_value())
+        );
+        assert_eq!(
+            template.get_value(&(CKA_LABEL as CK_ATTRIBUTE_TYPE)),
+            Some(b"shared".to_vec())
+        );
+        SecretKeyObject::from_template(template);
+    }
+
+    #[test]
+    fn given_template_of_other_class_derived_key_template_is_refused() {
+        let template = Template::from_vec(vec![
+            Attribute::from_parts(CKA_CLASS, CKO_SECRET_KEY),
+            Attribute::from_parts(CKA_KEY_TYPE, CKK_AES),
+        ]);
+        let template = get_derived_key_template(template).unwrap();
+        assert_eq!(
+            template.get_value(&(CKA_KEY_TYPE as CK_ATTRIBUTE_TYPE)),
+            Some(CKK_AES.to_attribute_value())
+        );
+
+        let template = Template::from_vec(vec![Attribute::from_parts(CKA_CLASS, CKO_PRIVATE_KEY)]);
+        assert!(get_derived_key_template(template).is_none());
+    }
+
+    #[test]
+    fn given_group_attributes_in_both_templates_group_parameters_are_combined() {
+        let public_key_template = Template::from_vec(vec![
+            Attribute::from_parts(CKA_LABEL, "ssh"),
+            Attribute::new(CKA_MEESIGN_THRESHOLD, Some(2u32.to_attribute_value())),
+        ]);
+        let private_key_template = Template::from_vec(vec![
+            Attribute::from_parts(CKA_LABEL, "ignored"),
+            Attribute::new(CKA_MEESIGN_DEVICE_IDS, Some(b"0a0b, 0c0d,0e0f".to_vec())),
+        ]);
+
+        let parameters = get_group_parameters(
+            GroupCurve::Ed25519,
+            &public_key_template,
+            &private_key_template,
+        )
+        .unwrap();
+
+        assert_eq!(
+            parameters,
+            GroupParameters::new(
+                "ssh".into(),
+                vec![vec![0x0a, 0x0b], vec![0x0c, 0x0d], vec![0x0e, 0x0f]],
+                2,
+                GroupKeyType::SignChallenge,
+                GroupCurve::Ed25519
+            )
+        );
+    }
+
+    #[test]
+    fn given_invalid_group_attributes_group_parameters_are_refused() {
+        let get_parameters = |attributes: Vec<Attribute>| {
+            get_group_parameters(
+                GroupCurve::P256,
+                &Template::from_vec(attributes),
+                &Template::from_vec(vec![]),
+            )
+        };
+        let label = || Attribute::from_parts(CKA_LABEL, "group");
+        let device_ids =
+            |value: &str| Attribute::new(CKA_MEESIGN_DEVICE_IDS, Some(value.as_bytes().to_vec()));
+        let threshold =
+            |value: u32| Attribute::new(CKA_MEESIGN_THRESHOLD, Some(value.to_attribute_value()));
+
+        assert!(matches!(
+            get_parameters(vec![label(), device_ids("0a")]),
+            Err(CryptokiError::TemplateIncomplete)
+        ));
+        assert!(matches!(
+            get_parameters(vec![device_ids("0a"), threshold(1)]),
+            Err(CryptokiError::TemplateIncomplete)
+        ));
+        assert!(matches!(
+            get_parameters(vec![label(), device_ids("0a,zz"), threshold(1)]),
+            Err(CryptokiError::AttributeValueInvalid)
+        ));
+        assert!(matches!(
+            get_parameters(vec![label(), device_ids("0a,0b"), threshold(3)]),
+            Err(CryptokiError::AttributeValueInvalid)
+        ));
+        assert!(matches!(
+            get_parameters(vec![label(), device_ids("0a,0b"), threshold(0)]),
+            Err(CryptokiError::AttributeValueInvalid)
+        ));
+        assert!(get_parameters(vec![label(), device_ids("0a,0b"), threshold(2)]).is_ok());
+    }
+
+    #[test]
+    fn given_mechanism_and_ec_params_key_pair_curve_matches_both() {
+        let ecdsa = CKM_ECDSA_KEY_PAIR_GEN as CK_MECHANISM_TYPE;
+        let eddsa = CKM_EC_EDWARDS_KEY_PAIR_GEN as CK_MECHANISM_TYPE;
+
+        assert_eq!(get_key_pair_curve(ecdsa, None).unwrap(), GroupCurve::P256);
+        assert_eq!(
+            get_key_pair_curve(eddsa, None).unwrap(),
+            GroupCurve::Ed25519
+        );
+        assert_eq!(
+            get_key_pair_curve(ecdsa, Some(GroupCurve::Secp256k1.get_ec_params())).unwrap(),
+            GroupCurve::Secp256k1
+        );
+        assert!(matches!(
+            get_key_pair_curve(ecdsa, Some(GroupCurve::Ed25519.get_ec_params())),
+            Err(CryptokiError::DomainParamsInvalid)
+        ));
+        assert!(matches!(
+            get_key_pair_curve(eddsa, Some(GroupCurve::P256.get_ec_params())),
+            Err(CryptokiError::DomainParamsInvalid)
+        ));
+    }
+}
@@ -52,6 +52,28 @@ pub fn C_CloseSession(hSession: CK_SESSION_HANDLE) -> CK_RV {
     CKR_OK as CK_RV
 }
 
+/// Cancels a function running in parallel with the application,
+/// i.e., a signing, decryption or key agreement request waiting for approval,
+/// or a key generation waiting for the group, in another thread.
+/// The canceled function returns CKR_FUNCTION_CANCELED. This is the only way
+/// to cancel a waiting function, as the library exposes no PKCS#11 3.0 interface
+/// with `C_SessionCancel`. MeeSign offers no way
+/// to withdraw a task, the bridge only stops waiting for it and the task stays
+/// pending on the server.
+///
+/// # Arguments
+///
+/// * `hSession` - the session’s handle
+#[cryptoki_macros::cryptoki_function]
+pub fn C_CancelFunction(hSession: CK_SESSION_HANDLE) -> CK_RV {
+    let state_accessor = StateAccessor::new();
+    if let Err(err) = state_accessor.cancel_operation(&hSession) {
+        return err.into_ck_rv();
+    }
+
+    CKR_OK as CK_RV
+}
+
 /// Logs a user into a token
 ///
 /// # Arguments
@@ -1,16 +1,23 @@
 use std::ptr;
 
-use crate::state::{object::template::Template, session::single_session::Signer, StateAccessor};
-const CKA_REQUEST_ORIGINATOR: CK_ATTRIBUTE_TYPE =
-    (CKA_VENDOR_DEFINED as CK_ATTRIBUTE_TYPE) | 0x000000000000abcd;
+use crate::{
+    communicator::{
+        group::GroupKeyType, payload_summary::PayloadSummarizer, request_context::RequestContext,
+        RequestData,
+    },
+    cryptoki_error::CryptokiError,
+    state::{object::template::Template, session::single_session::Signer, StateAccessor},
+};
 
 use super::{
     bindings::{
-        CKA_VENDOR_DEFINED, CKR_ARGUMENTS_BAD, CKR_OK, CK_ATTRIBUTE_PTR, CK_ATTRIBUTE_TYPE,
-        CK_BYTE_PTR, CK_MECHANISM_PTR, CK_OBJECT_HANDLE, CK_RV, CK_SESSION_HANDLE, CK_ULONG,
+        CKA_KEY_TYPE, CKK_EC_EDWARDS, CKM_ECDSA_SHA256, CKM_EDDSA, CKR_ARGUMENTS_BAD, CKR_OK,
+        CK_ATTRIBUTE_PTR, CK_ATTRIBUTE_TYPE, CK_BYTE_PTR, CK_KEY_TYPE, CK_MECHANISM,
+        CK_MECHANISM_PTR, CK_MECHANISM_TYPE, CK_OBJECT_HANDLE, CK_RV, CK_SESSION_HANDLE, CK_ULONG,
         CK_ULONG_PTR,
     },
     utils::FromPointer,
+    vendor_defined::{CKA_MEESIGN_KEY_TYPE, CKA_REQUEST_ORIGINATOR, CKM_MEESIGN_SIGN_PDF},
 };
 
 /// Initializes a signature operation, where the signature is an appendix to the data
@@ -33,21 +40,41 @@ pub unsafe fn C_SignInit(
     };
 
     let mechanism = unsafe { *pMechanism };
-    let attributes = unsafe {
-        Vec::from_pointer(
-            mechanism.pParameter as CK_ATTRIBUTE_PTR,
-            mechanism.ulParameterLen as usize,
-        )
+    let request_originator = unsafe { get_request_originator(&mechanism) };
+    let (signing_key, communicator_id) = match state_accessor.route_signing_key(
+        &hSession,
+        signing_key,
+        request_originator.as_deref(),
+    ) {
+        Ok(key) => key,
+        Err(err) => return err.into_ck_rv(),
     };
-    let template = Template::from(attributes);
-    let request_originator = template
-        .get_value(&(CKA_REQUEST_ORIGINATOR as CK_ATTRIBUTE_TYPE))
-        .map(|originator| String::from_utf8(originator).ok())
-        .and_then(|x| x);
-
-    if let Err(err) =
-        state_accessor.set_signer(&hSession, Signer::new(signing_key, request_originator))
-    {
+
+    // keys not backed by a MeeSign group, e.g., imported ones, are treated as challenge keys
+    let key_type = signing_key
+        .get_attribute(CKA_MEESIGN_KEY_TYPE)
+        .and_then(|value| GroupKeyType::from_attribute_value(&value))
+        .unwrap_or(GroupKeyType::SignChallenge);
+    if let Err(err) = check_mechanism_for_key_type(mechanism.mechanism, key_type) {
+        return err.into_ck_rv();
+    }
+    let is_edwards_key = signing_key
+        .get_attribute(CKA_KEY_TYPE as CK_ATTRIBUTE_TYPE)
+        .is_some_and(|value| value == (CKK_EC_EDWARDS as CK_KEY_TYPE).to_le_bytes());
+    if let Err(err) = check_mechanism_for_curve(mechanism.mechanism, is_edwards_key) {
+        return err.into_ck_rv();
+    }
+
+    if let Err(err) = state_accessor.set_signer(
+        &hSession,
+        Signer::new(
+            signing_key,
+            key_type,
+            mechanism.mechanism,
+            request_originator,
+            communicator_id,
+        ),
+    ) {
         return err.into_ck_rv();
     }
 
@@ -87,11 +114,21 @@ pub unsafe fn C_Sign(
         let pubkey = signer.key.get_value().unwrap();
 
         let auth_data = unsafe { Vec::from_pointer(pData, ulDataLen as usize) };
+        let payload_summary = summarize_request_data(signer.mechanism, &auth_data);
+        let request_context = RequestContext::new(signer.auth_request_originator)
+            .with_payload_summary(payload_summary);
+        let cancellation_token = match state_accessor.start_cancellable_operation(&hSession) {
+            Ok(cancellation_token) => cancellation_token,
+            Err(err) => return err.into_ck_rv(),
+        };
 
-        let response = match state_accessor.send_signing_request_wait_for_response(
+        let response = match state_accessor.send_request_wait_for_response(
+            signer.communicator_id,
             pubkey,
+            signer.key_type.into(),
             auth_data,
-            signer.auth_request_originator,
+            request_context,
+            cancellation_token,
         ) {
             Ok(response) => response,
             Err(err) => {
@@ -118,3 +155,265 @@ pub unsafe fn C_Sign(
 
     CKR_OK as CK_RV
 }
+
+/// Returns the originator of the request, passed as the `CKA_REQUEST_ORIGINATOR`
+/// attribute in the mechanism parameter
+///
+/// # Safety
+///
+/// The mechanism parameter must point to an array of attributes of the given length
+///
+/// # Arguments
+///
+/// * `mechanism` - the mechanism the operation is initialized with
+pub(super) unsafe fn get_request_originator(mechanism: &CK_MECHANISM) -> Option<String> {
+    let attributes = unsafe {
+        Vec::from_pointer(
+            mechanism.pParameter as CK_ATTRIBUTE_PTR,
+            mechanism.ulParameterLen as usize,
+        )
+    };
+    let template = Template::from(attributes);
+    template
+        .get_value(&CKA_REQUEST_ORIGINATOR)
+        .and_then(|originator| String::from_utf8(originator).ok())
+}
+
+/// Summarizes the data for the approvers. Only mechanisms passing the full data
+/// can be summarized, a digest tells nothing about the data.
+/// The data are sent to the communicator as they were passed to `C_Sign`.
+///
+/// # Arguments
+///
+/// * `mechanism` - the signature mechanism
+/// * `data` - the data passed to `C_Sign`
+fn summarize_request_data(mechanism: CK_MECHANISM_TYPE, data: &RequestData) -> Option<String> {
+    let passes_full_data = mechanism == CKM_EDDSA as CK_MECHANISM_TYPE
+        || mechanism == CKM_ECDSA_SHA256 as CK_MECHANISM_TYPE;
+    if !passes_full_data {
+        return None;
+    }
+    PayloadSummarizer::new().summarize(data)
+}
+
+/// Makes sure document signing keys are used only for document signatures and vice versa,
+/// and that decryption keys don't sign at all
+///
+/// # Arguments
+///
+/// * `mechanism` - the signature mechanism
+/// * `key_type` - the key type of the group backing the signing key
+fn check_mechanism_for_key_type(
+    mechanism: CK_MECHANISM_TYPE,
+    key_type: GroupKeyType,
+) -> Result<(), CryptokiError> {
+    if key_type == GroupKeyType::Decrypt {
+        return Err(CryptokiError::KeyTypeInconsistent);
+    }
+    let is_document_signature = mechanism == CKM_MEESIGN_SIGN_PDF;
+    let is_document_key = key_type == GroupKeyType::SignPdf;
+    if is_document_signature != is_document_key {
+        return Err(CryptokiError::KeyTypeInconsistent);
+    }
+    Ok(())
+}
+
+/// Makes sure EdDSA is used only with Edwards curve keys, e.g., of FROST groups,
+/// and that Weierstrass curve keys sign only using ECDSA
+///
+/// # Arguments
+///
+/// * `mechanism` - the signature mechanism
+/// * `is_edwards_key` - whether the signing key is an Edwards curve key
+fn check_mechanism_for_curve(
+    mechanism: CK_MECHANISM_TYPE,
+    is_edwards_key: bool,
+) -> Result<(), CryptokiError> {
+    let is_eddsa = mechanism == CKM_EDDSA as CK_MECHANISM_TYPE;
+    if is_eddsa != is_edwards_key {
+        return Err(CryptokiError::KeyTypeInconsistent);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cryptoki::bindings::CKM_ECDSA;
+
+    use super::*;
+
+    #[cfg(not(feature = "mocked_communicator"))]
+    #[test]
+    fn given_stand_in_server_c_sign_returns_signature_of_the_group_key() {
+        use crate::{
+            communicator::meesign::stand_in::{verify_signature, MeesignStandIn},
+            cryptoki::{
+                bindings::{CK_ATTRIBUTE, CK_MECHANISM, CK_VOID_PTR},
+                stand_in_library::StandInLibrary,
+            },
+        };
+
+        let library = StandInLibrary::start(
+            MeesignStandIn::new().with_group("e2e", GroupKeyType::SignChallenge),
+        );
+        assert_eq!(library.initialize(), CKR_OK as CK_RV);
+        let (session_handle, private_key) = library.open_session_with_group_key();
+
+        let mut no_attributes: [CK_ATTRIBUTE; 0] = [];
+        let mut signing_mechanism = CK_MECHANISM {
+            mechanism: CKM_ECDSA as CK_MECHANISM_TYPE,
+            pParameter: no_attributes.as_mut_ptr() as CK_VOID_PTR,
+            ulParameterLen: 0,
+        };
+        assert_eq!(
+            unsafe { C_SignInit(session_handle, &mut signing_mechanism, private_key) },
+            CKR_OK as CK_RV
+        );
+
+        let mut digest = vec![0xab; 32];
+        let mut signature_length: CK_ULONG = 0;
+        assert_eq!(
+            unsafe {
+                C_Sign(
+                    session_handle,
+                    digest.as_mut_ptr(),
+                    digest.len() as CK_ULONG,
+                    std::ptr::null_mut(),
+                    &mut signature_length,
+                )
+            },
+            CKR_OK as CK_RV
+        );
+        let mut signature = vec![0; signature_length as usize];
+        assert_eq!(
+            unsafe {
+                C_Sign(
+                    session_handle,
+                    digest.as_mut_ptr(),
+                    digest.len() as CK_ULONG,
+                    signature.as_mut_ptr(),
+                    &mut signature_length,
+                )
+            },
+            CKR_OK as CK_RV
+        );
+
+        let state_accessor = StateAccessor::new();
+        let group_id = state_accessor
+            .get_object(&session_handle, &private_key)
+            .unwrap()
+            .get_value()
+            .unwrap();
+        assert!(verify_signature(&group_id, &digest, &signature));
+        assert_eq!(library.get_stand_in().get_task_count(), 1);
+    }
+
+    #[cfg(not(feature = "mocked_communicator"))]
+    #[test]
+    fn given_pending_request_c_cancel_function_cancels_c_sign() {
+        use std::thread;
+
+        use crate::{
+            communicator::meesign::stand_in::{MeesignStandIn, TaskState},
+            cryptoki::{
+                bindings::{CKR_FUNCTION_CANCELED, CK_ATTRIBUTE, CK_MECHANISM, CK_VOID_PTR},
+                session_management::C_CancelFunction,
+                stand_in_library::StandInLibrary,
+            },
+        };
+
+        // the task is never decided
+        let library = StandInLibrary::start(
+            MeesignStandIn::new()
+                .with_group("pending", GroupKeyType::SignChallenge)
+                .with_task_script(vec![TaskState::Created, TaskState::Running]),
+        );
+        assert_eq!(library.initialize(), CKR_OK as CK_RV);
+        let (session_handle, private_key) = library.open_session_with_group_key();
+
+        let mut no_attributes: [CK_ATTRIBUTE; 0] = [];
+        let mut signing_mechanism = CK_MECHANISM {
+            mechanism: CKM_ECDSA as CK_MECHANISM_TYPE,
+            pParameter: no_attributes.as_mut_ptr() as CK_VOID_PTR,
+            ulParameterLen: 0,
+        };
+        assert_eq!(
+            unsafe { C_SignInit(session_handle, &mut signing_mechanism, private_key) },
+            CKR_OK as CK_RV
+        );
+
+        let signing = thread::spawn(move || {
+            let mut digest = vec![0xab; 32];
+            let mut signature_length: CK_ULONG = 0;
+            unsafe {
+                C_Sign(
+                    session_handle,
+                    digest.as_mut_ptr(),
+                    digest.len() as CK_ULONG,
+                    std::ptr::null_mut(),
+                    &mut signature_length,
+                )
+            }
+        });
+        library.wait_for_task();
+
+        // the waiting request doesn't block the other calls using the communicator
+        library.get_slot();
+        assert_eq!(C_CancelFunction(session_handle), CKR_OK as CK_RV);
+        assert_eq!(signing.join().unwrap(), CKR_FUNCTION_CANCELED as CK_RV);
+    }
+
+    #[test]
+    fn given_challenge_key_document_signature_is_refused() {
+        assert!(check_mechanism_for_key_type(
+            CKM_ECDSA as CK_MECHANISM_TYPE,
+            GroupKeyType::SignChallenge
+        )
+        .is_ok());
+        assert!(
+            check_mechanism_for_key_type(CKM_MEESIGN_SIGN_PDF, GroupKeyType::SignChallenge)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn given_document_key_only_document_signature_is_allowed() {
+        assert!(check_mechanism_for_key_type(CKM_MEESIGN_SIGN_PDF, GroupKeyType::SignPdf).is_ok());
+        assert!(check_mechanism_for_key_type(
+            CKM_ECDSA as CK_MECHANISM_TYPE,
+            GroupKeyType::SignPdf
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn given_decryption_key_no_signature_is_allowed() {
+        assert!(check_mechanism_for_key_type(
+            CKM_ECDSA as CK_MECHANISM_TYPE,
+            GroupKeyType::Decrypt
+        )
+        .is_err());
+        assert!(check_mechanism_for_key_type(CKM_MEESIGN_SIGN_PDF, GroupKeyType::Decrypt).is_err());
+    }
+
+    #[test]
+    fn given_edwards_key_only_eddsa_is_allowed() {
+        assert!(check_mechanism_for_curve(CKM_EDDSA as CK_MECHANISM_TYPE, true).is_ok());
+        assert!(check_mechanism_for_curve(CKM_ECDSA as CK_MECHANISM_TYPE, true).is_err());
+        assert!(check_mechanism_for_curve(CKM_EDDSA as CK_MECHANISM_TYPE, false).is_err());
+        assert!(check_mechanism_for_curve(CKM_ECDSA as CK_MECHANISM_TYPE, false).is_ok());
+        assert!(check_mechanism_for_curve(CKM_MEESIGN_SIGN_PDF, false).is_ok());
+    }
+
+    #[test]
+    fn given_digest_mechanism_data_are_not_summarized() {
+        // WebAuthn assertion data, i.e., RP ID hash, flags, sign count and client data hash
+        let mut data = openssl::sha::sha256(b"example.com").to_vec();
+        data.push(0x01);
+        data.extend(7u32.to_be_bytes());
+        data.extend([0xcd; 32]);
+
+        assert!(summarize_request_data(CKM_ECDSA_SHA256 as CK_MECHANISM_TYPE, &data).is_some());
+        assert!(summarize_request_data(CKM_ECDSA as CK_MECHANISM_TYPE, &data).is_none());
+    }
+}
@@ -3,10 +3,7 @@ use std::{
     sync::{Arc, RwLock},
 };
 
-use crate::{
-    communicator::group::Group, cryptoki_error::CryptokiError, state::token::MeesignToken,
-    state::StateAccessor,
-};
+use crate::{cryptoki_error::CryptokiError, state::token::MeesignToken, state::StateAccessor};
 
 use super::bindings::{
     CKR_ARGUMENTS_BAD, CKR_BUFFER_TOO_SMALL, CKR_OK, CK_BBOOL, CK_RV, CK_SLOT_ID, CK_SLOT_ID_PTR,
@@ -49,9 +46,11 @@ pub unsafe fn C_GetSlotList(
 
     let slot_list: Result<Vec<CK_SLOT_ID>, CryptokiError> = groups
         .into_iter()
-        .map(|group: Group| group.into())
-        .map(|token: MeesignToken| Arc::new(RwLock::new(token)))
-        .map(|token| state_accessor.insert_token(token))
+        .map(|(communicator_id, group)| {
+            let server_version = state_accessor.get_server_version(communicator_id)?;
+            let token = MeesignToken::new(group, communicator_id, server_version);
+            state_accessor.insert_token(Arc::new(RwLock::new(token)))
+        })
         .collect();
     let slot_list = match slot_list {
         Ok(slot_list) => slot_list,
@@ -108,3 +107,35 @@ pub unsafe fn C_GetSlotInfo(slotID: CK_SLOT_ID, pInfo: CK_SLOT_INFO_PTR) -> CK_R
 
     CKR_OK as CK_RV
 }
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "mocked_communicator"))]
+    #[test]
+    fn given_stand_in_server_c_get_token_info_reports_server_version_as_firmware() {
+        use std::mem::MaybeUninit;
+
+        use crate::{
+            communicator::{group::GroupKeyType, meesign::stand_in::MeesignStandIn},
+            cryptoki::{bindings::CK_TOKEN_INFO, stand_in_library::StandInLibrary},
+        };
+
+        use super::*;
+
+        let library = StandInLibrary::start(
+            MeesignStandIn::new()
+                .with_group("firmware", GroupKeyType::SignChallenge)
+                .with_version("0.4.1"),
+        );
+        assert_eq!(library.initialize(), CKR_OK as CK_RV);
+
+        let mut token_info = MaybeUninit::<CK_TOKEN_INFO>::zeroed();
+        assert_eq!(
+            unsafe { C_GetTokenInfo(library.get_slot(), token_info.as_mut_ptr()) },
+            CKR_OK as CK_RV
+        );
+        let token_info = unsafe { token_info.assume_init() };
+        assert_eq!(token_info.firmwareVersion.major, 0);
+        assert_eq!(token_info.firmwareVersion.minor, 4);
+    }
+}
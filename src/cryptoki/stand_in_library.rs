@@ -0,0 +1,156 @@
+//! Drives the library against a stand-in MeeSign server in tests. The endpoint
+//! and the data directory are passed as library parameters, so that the tests
+//! don't touch the environment of the test process. The library state is global,
+//! the tests using it are therefore serialized.
+
+use std::{
+    ffi::CString,
+    fs,
+    sync::{Mutex, MutexGuard, PoisonError},
+    thread,
+    time::Duration,
+};
+
+use crate::communicator::meesign::stand_in::{MeesignStandIn, RunningStandIn};
+
+use super::{
+    bindings::{
+        CKF_SERIAL_SESSION, CKM_ECDSA_KEY_PAIR_GEN, CKR_OK, CK_C_INITIALIZE_ARGS, CK_FLAGS,
+        CK_MECHANISM, CK_MECHANISM_TYPE, CK_OBJECT_HANDLE, CK_RV, CK_SESSION_HANDLE, CK_SLOT_ID,
+        CK_ULONG, CK_VOID_PTR, NULL_PTR,
+    },
+    general_purpose::{C_Finalize, C_Initialize},
+    key_management::C_GenerateKeyPair,
+    session_management::C_OpenSession,
+    slot_token::C_GetSlotList,
+};
+
+static LIBRARY_LOCK: Mutex<()> = Mutex::new(());
+
+/// The library configured to use a stand-in server, finalized when dropped
+pub(crate) struct StandInLibrary {
+    stand_in: RunningStandIn,
+
+    /// The library parameters passed to `C_Initialize`
+    parameters: CString,
+
+    /// Keeps other tests from using the library until this one is done
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl StandInLibrary {
+    /// Starts the stand-in server and prepares a fresh data directory for the library
+    ///
+    /// # Arguments
+    ///
+    /// * `stand_in` - the server the library connects to
+    pub(crate) fn start(stand_in: MeesignStandIn) -> Self {
+        // a failed test doesn't leave the library unusable for the others
+        let lock = LIBRARY_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let stand_in = stand_in.start_in_background();
+        let data_directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&data_directory).unwrap();
+        let certificate_path = data_directory.join("ca.pem");
+        fs::write(&certificate_path, stand_in.get_ca_certificate()).unwrap();
+        let parameters = format!(
+            "hostname={} port={} certificate_path='{}' data_directory='{}'",
+            stand_in.get_address(),
+            stand_in.get_port(),
+            certificate_path.display(),
+            data_directory.display(),
+        );
+        Self {
+            stand_in,
+            parameters: CString::new(parameters).unwrap(),
+            _lock: lock,
+        }
+    }
+
+    pub(crate) fn get_stand_in(&self) -> &RunningStandIn {
+        &self.stand_in
+    }
+
+    /// Calls `C_Initialize` with the library parameters pointing to the stand-in
+    pub(crate) fn initialize(&self) -> CK_RV {
+        let mut init_args = CK_C_INITIALIZE_ARGS {
+            CreateMutex: None,
+            DestroyMutex: None,
+            LockMutex: None,
+            UnlockMutex: None,
+            flags: 0,
+            pReserved: self.parameters.as_ptr() as CK_VOID_PTR,
+        };
+        unsafe { C_Initialize(&mut init_args as *mut _ as CK_VOID_PTR) }
+    }
+
+    /// Waits until the stand-in receives a task, e.g., from a request sent
+    /// on another thread
+    pub(crate) fn wait_for_task(&self) {
+        let mut attempts = 0;
+        while self.stand_in.get_task_count() == 0 {
+            assert!(attempts < 100, "the request didn't reach the server");
+            attempts += 1;
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    /// Returns the slot of the only group of the stand-in
+    pub(crate) fn get_slot(&self) -> CK_SLOT_ID {
+        let mut slot_count: CK_ULONG = 1;
+        let mut slot_id: CK_SLOT_ID = 0;
+        assert_eq!(
+            unsafe { C_GetSlotList(0, &mut slot_id, &mut slot_count) },
+            CKR_OK as CK_RV
+        );
+        assert_eq!(slot_count, 1);
+        slot_id
+    }
+
+    /// Opens a session in the slot of the only group and returns it
+    /// together with the handle of the group's private key
+    pub(crate) fn open_session_with_group_key(&self) -> (CK_SESSION_HANDLE, CK_OBJECT_HANDLE) {
+        let mut session_handle = 0;
+        assert_eq!(
+            unsafe {
+                C_OpenSession(
+                    self.get_slot(),
+                    CKF_SERIAL_SESSION as CK_FLAGS,
+                    NULL_PTR as CK_VOID_PTR,
+                    None,
+                    &mut session_handle,
+                )
+            },
+            CKR_OK as CK_RV
+        );
+
+        let mut mechanism = CK_MECHANISM {
+            mechanism: CKM_ECDSA_KEY_PAIR_GEN as CK_MECHANISM_TYPE,
+            pParameter: NULL_PTR as CK_VOID_PTR,
+            ulParameterLen: 0,
+        };
+        let mut public_key: CK_OBJECT_HANDLE = 0;
+        let mut private_key: CK_OBJECT_HANDLE = 0;
+        assert_eq!(
+            unsafe {
+                C_GenerateKeyPair(
+                    session_handle,
+                    &mut mechanism,
+                    std::ptr::null_mut(),
+                    0,
+                    std::ptr::null_mut(),
+                    0,
+                    &mut public_key,
+                    &mut private_key,
+                )
+            },
+            CKR_OK as CK_RV
+        );
+        (session_handle, private_key)
+    }
+}
+
+impl Drop for StandInLibrary {
+    fn drop(&mut self) {
+        let _ = C_Finalize(NULL_PTR as CK_VOID_PTR);
+    }
+}
@@ -327,17 +327,6 @@ unsupported!(
     )
 );
 
-unsupported!(
-    C_DeriveKey(
-        hSession: CK_SESSION_HANDLE,
-        pMechanism: CK_MECHANISM_PTR,
-        hBaseKey: CK_OBJECT_HANDLE,
-        pTemplate: CK_ATTRIBUTE_PTR,
-        ulAttributeCount: CK_ULONG,
-        phKey: CK_OBJECT_HANDLE_PTR,
-    )
-);
-
 unsupported!(
     C_SeedRandom(
         hSession: CK_SESSION_HANDLE,
@@ -358,10 +347,6 @@ unsupported!(
     C_GetFunctionStatus(hSession: CK_SESSION_HANDLE)
 );
 
-unsupported!(
-    C_CancelFunction(hSession: CK_SESSION_HANDLE)
-);
-
 unsupported!(
     C_WaitForSlotEvent(
         flags: CK_FLAGS,
@@ -12,6 +12,10 @@ pub unsafe trait FromPointer<T> {
 
 unsafe impl<T> FromPointer<T> for Vec<T> {
     unsafe fn from_pointer(pointer: *mut T, count: usize) -> Self {
+        // callers pass a null pointer together with a zero count for empty arrays
+        if count == 0 {
+            return Vec::new();
+        }
         let mut vector = Vec::with_capacity(count);
         unsafe {
             ptr::copy(pointer, vector.as_mut_ptr(), count);
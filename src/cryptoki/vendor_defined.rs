@@ -0,0 +1,49 @@
+use super::bindings::{
+    CKA_VENDOR_DEFINED, CKM_VENDOR_DEFINED, CK_ATTRIBUTE_TYPE, CK_MECHANISM_TYPE,
+};
+
+/// Originator of a signing request, e.g., the website domain name,
+/// passed as a mechanism parameter of `C_SignInit`
+pub(crate) const CKA_REQUEST_ORIGINATOR: CK_ATTRIBUTE_TYPE =
+    (CKA_VENDOR_DEFINED as CK_ATTRIBUTE_TYPE) | 0x000000000000abcd;
+
+/// Key type of the MeeSign group a key object belongs to
+pub(crate) const CKA_MEESIGN_KEY_TYPE: CK_ATTRIBUTE_TYPE =
+    (CKA_VENDOR_DEFINED as CK_ATTRIBUTE_TYPE) | 0x000000000000abce;
+
+/// Devices of the MeeSign group a key object belongs to, as a comma-separated
+/// list of hex-encoded device IDs. Passed in the `C_GenerateKeyPair` templates,
+/// it makes the call create a new group.
+pub(crate) const CKA_MEESIGN_DEVICE_IDS: CK_ATTRIBUTE_TYPE =
+    (CKA_VENDOR_DEFINED as CK_ATTRIBUTE_TYPE) | 0x000000000000abcf;
+
+/// Number of devices of the MeeSign group required to use the key, a `CK_ULONG`
+pub(crate) const CKA_MEESIGN_THRESHOLD: CK_ATTRIBUTE_TYPE =
+    (CKA_VENDOR_DEFINED as CK_ATTRIBUTE_TYPE) | 0x000000000000abd0;
+
+/// Number of devices of the MeeSign group holding a share of the key, a `CK_ULONG`
+pub(crate) const CKA_MEESIGN_PARTICIPANT_COUNT: CK_ATTRIBUTE_TYPE =
+    (CKA_VENDOR_DEFINED as CK_ATTRIBUTE_TYPE) | 0x000000000000abd1;
+
+/// Names of the devices of the MeeSign group in the order of `CKA_MEESIGN_DEVICE_IDS`,
+/// as UTF-8 strings, unknown names are left empty. Each name is prefixed
+/// by its length in bytes as a 4-byte big-endian integer, as the names
+/// may contain any characters.
+pub(crate) const CKA_MEESIGN_DEVICE_NAMES: CK_ATTRIBUTE_TYPE =
+    (CKA_VENDOR_DEFINED as CK_ATTRIBUTE_TYPE) | 0x000000000000abd2;
+
+/// DER-encoded certificates of the devices of the MeeSign group in the order
+/// of `CKA_MEESIGN_DEVICE_IDS`, unknown certificates are left empty. Each certificate
+/// is prefixed by its length in bytes as a 4-byte big-endian integer,
+/// just like the names in `CKA_MEESIGN_DEVICE_NAMES`.
+pub(crate) const CKA_MEESIGN_DEVICE_CERTIFICATES: CK_ATTRIBUTE_TYPE =
+    (CKA_VENDOR_DEFINED as CK_ATTRIBUTE_TYPE) | 0x000000000000abd3;
+
+/// Signs a PDF document using a MeeSign SignPDF group
+pub(crate) const CKM_MEESIGN_SIGN_PDF: CK_MECHANISM_TYPE =
+    (CKM_VENDOR_DEFINED as CK_MECHANISM_TYPE) | 0x000000000000abcd;
+
+/// Decrypts data using a MeeSign decryption group, the data are sent
+/// to the group as they are and the plaintext is returned once the quorum approves
+pub(crate) const CKM_MEESIGN_DECRYPT: CK_MECHANISM_TYPE =
+    (CKM_VENDOR_DEFINED as CK_MECHANISM_TYPE) | 0x000000000000abce;
@@ -6,10 +6,15 @@ use crate::{
     communicator::communicator_error::CommunicatorError,
     configuration::ConfigurationProviderError,
     cryptoki::bindings::{
-        CKR_CRYPTOKI_NOT_INITIALIZED, CKR_DEVICE_ERROR, CKR_FUNCTION_FAILED,
-        CKR_FUNCTION_NOT_SUPPORTED, CKR_GENERAL_ERROR, CKR_OBJECT_HANDLE_INVALID,
-        CKR_OPERATION_NOT_INITIALIZED, CKR_SESSION_HANDLE_INVALID, CKR_SLOT_ID_INVALID, CK_RV,
+        CKR_ARGUMENTS_BAD, CKR_ATTRIBUTE_VALUE_INVALID, CKR_CRYPTOKI_ALREADY_INITIALIZED,
+        CKR_CRYPTOKI_NOT_INITIALIZED, CKR_DATA_INVALID, CKR_DEVICE_ERROR,
+        CKR_DOMAIN_PARAMS_INVALID, CKR_FUNCTION_CANCELED, CKR_FUNCTION_FAILED,
+        CKR_FUNCTION_NOT_SUPPORTED, CKR_GENERAL_ERROR, CKR_KEY_FUNCTION_NOT_PERMITTED,
+        CKR_KEY_TYPE_INCONSISTENT, CKR_MECHANISM_INVALID, CKR_OBJECT_HANDLE_INVALID,
+        CKR_OPERATION_NOT_INITIALIZED, CKR_SESSION_HANDLE_INVALID, CKR_SLOT_ID_INVALID,
+        CKR_TEMPLATE_INCOMPLETE, CK_RV,
     },
+    diagnostics,
     persistence::persistence_error::PersistenceError,
 };
 
@@ -19,6 +24,8 @@ pub(crate) enum CryptokiError {
     SynchronizationElementPoisoned,
     #[error("Cryptoki not initialized")]
     CryptokiNotInitialized,
+    #[error("Cryptoki already initialized")]
+    CryptokiAlreadyInitialized,
     #[error("Session handle is invalid")]
     SessionHandleInvalid,
     #[error("Function is not supported")]
@@ -35,13 +42,37 @@ pub(crate) enum CryptokiError {
     SlotIdInvalid,
     #[error("General device error")]
     DeviceError,
+    #[error("Data are not valid for the operation")]
+    DataInvalid,
+    #[error("Key can't be used with the mechanism")]
+    KeyTypeInconsistent,
+    #[error("Function was canceled")]
+    FunctionCanceled,
+    #[error("Communicator is not supported: {0}")]
+    UnsupportedCommunicator(String),
+    #[error("Mechanism is not supported for the operation")]
+    MechanismInvalid,
+    #[error("Template lacks an attribute required by the operation")]
+    TemplateIncomplete,
+    #[error("Template contains an invalid attribute value")]
+    AttributeValueInvalid,
+    #[error("Curve is not supported")]
+    DomainParamsInvalid,
+    #[error("No group is routed for the request originator")]
+    OriginatorNotRouted,
+    #[error("Arguments are invalid")]
+    ArgumentsBad,
 }
 
 impl CryptokiError {
+    /// Returns the return value of the error. The errors returned to the application
+    /// are reported here, the conversions from the errors of the components don't report them.
     pub(crate) fn into_ck_rv(self) -> CK_RV {
+        diagnostics::report(&self);
         match self {
             Self::SynchronizationElementPoisoned => CKR_GENERAL_ERROR as CK_RV,
             Self::CryptokiNotInitialized => CKR_CRYPTOKI_NOT_INITIALIZED as CK_RV,
+            Self::CryptokiAlreadyInitialized => CKR_CRYPTOKI_ALREADY_INITIALIZED as CK_RV,
             Self::SessionHandleInvalid => CKR_SESSION_HANDLE_INVALID as CK_RV,
             Self::FunctionNotSupported => CKR_FUNCTION_NOT_SUPPORTED as CK_RV,
             Self::OperationNotInitialized => CKR_OPERATION_NOT_INITIALIZED as CK_RV,
@@ -50,6 +81,16 @@ impl CryptokiError {
             Self::TransportError => CKR_GENERAL_ERROR as CK_RV,
             Self::SlotIdInvalid => CKR_SLOT_ID_INVALID as CK_RV,
             Self::DeviceError => CKR_DEVICE_ERROR as CK_RV,
+            Self::DataInvalid => CKR_DATA_INVALID as CK_RV,
+            Self::KeyTypeInconsistent => CKR_KEY_TYPE_INCONSISTENT as CK_RV,
+            Self::FunctionCanceled => CKR_FUNCTION_CANCELED as CK_RV,
+            Self::UnsupportedCommunicator(_) => CKR_DEVICE_ERROR as CK_RV,
+            Self::MechanismInvalid => CKR_MECHANISM_INVALID as CK_RV,
+            Self::TemplateIncomplete => CKR_TEMPLATE_INCOMPLETE as CK_RV,
+            Self::AttributeValueInvalid => CKR_ATTRIBUTE_VALUE_INVALID as CK_RV,
+            Self::DomainParamsInvalid => CKR_DOMAIN_PARAMS_INVALID as CK_RV,
+            Self::OriginatorNotRouted => CKR_KEY_FUNCTION_NOT_PERMITTED as CK_RV,
+            Self::ArgumentsBad => CKR_ARGUMENTS_BAD as CK_RV,
         }
     }
 }
@@ -67,10 +108,13 @@ impl From<CommunicatorError> for CryptokiError {
             CommunicatorError::CryptographicError(_) => Self::FunctionFailed,
             CommunicatorError::Transport(_) => Self::TransportError,
             CommunicatorError::InvalidConfiguration(_) => Self::FunctionFailed,
+            CommunicatorError::InvalidRequestData => Self::DataInvalid,
             CommunicatorError::TaskFailed => Self::FunctionFailed,
             CommunicatorError::TaskTimedOut(_) => Self::FunctionFailed,
             CommunicatorError::InvalidStatus(_) => Self::TransportError,
             CommunicatorError::Io(_) => Self::DeviceError,
+            CommunicatorError::UnsupportedServer(reason) => Self::UnsupportedCommunicator(reason),
+            CommunicatorError::DeviceIdentity(_) => Self::FunctionFailed,
         }
     }
 }
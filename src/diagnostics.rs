@@ -0,0 +1,129 @@
+mod error_diagnostics;
+pub(crate) mod forwarder;
+mod scrubber;
+
+use std::{fmt, time::Duration};
+
+use tokio::{
+    runtime::Runtime,
+    sync::mpsc::{self, UnboundedSender},
+    task::JoinHandle,
+    time,
+};
+
+use crate::DIAGNOSTICS;
+
+use self::{forwarder::DiagnosticsSink, scrubber::scrub};
+
+/// Upper bound of the time spent delivering the remaining events when shutting down
+static SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// An error or another event worth reporting to the server administrators
+pub(crate) trait Diagnostic {
+    /// Returns the severity of the event, `None` if the event is not worth reporting,
+    /// e.g., when it is caused by the calling application misusing the API
+    fn get_severity(&self) -> Option<Severity>;
+
+    /// Returns the name of the component the event comes from
+    fn get_source(&self) -> &'static str;
+
+    /// Returns a description of the event, it must not contain the data to be signed
+    /// nor key material. The description is scrubbed once more before it is sent.
+    fn get_description(&self) -> String;
+}
+
+/// A scrubbed event waiting to be forwarded
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct DiagnosticEvent {
+    severity: Severity,
+    source: &'static str,
+    description: String,
+}
+
+impl DiagnosticEvent {
+    pub(crate) fn new(severity: Severity, source: &'static str, description: &str) -> Self {
+        Self {
+            severity,
+            source,
+            description: scrub(description),
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}: {}", self.severity, self.source, self.description)
+    }
+}
+
+/// Collects diagnostic events and forwards them in batches, in the background
+pub(crate) struct Diagnostics {
+    sender: UnboundedSender<DiagnosticEvent>,
+    forwarder: JoinHandle<()>,
+}
+
+impl Diagnostics {
+    /// Starts forwarding events to the sink
+    ///
+    /// # Arguments
+    ///
+    /// * `sink` - receives the batches of events
+    /// * `runtime` - the runtime the forwarding runs on
+    pub(crate) fn start(sink: Box<dyn DiagnosticsSink>, runtime: &Runtime) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let forwarder = runtime.spawn(forwarder::forward_diagnostics(receiver, sink));
+        Self { sender, forwarder }
+    }
+
+    fn send(&self, event: DiagnosticEvent) {
+        // the forwarder stops only after the sender is dropped
+        let _ = self.sender.send(event);
+    }
+
+    /// Forwards the events collected so far and stops the forwarding
+    pub(crate) async fn shutdown(self) {
+        drop(self.sender);
+        if time::timeout(SHUTDOWN_TIMEOUT, self.forwarder)
+            .await
+            .is_err()
+        {
+            eprintln!("Couldn't forward the remaining diagnostics in time");
+        }
+    }
+}
+
+/// Queues the event for forwarding, if diagnostics forwarding is enabled
+///
+/// # Arguments
+///
+/// * `diagnostic` - the event to be reported
+pub(crate) fn report(diagnostic: &dyn Diagnostic) {
+    let Some(severity) = diagnostic.get_severity() else {
+        return;
+    };
+    let Ok(diagnostics) = DIAGNOSTICS.read() else {
+        return;
+    };
+    if let Some(diagnostics) = diagnostics.as_ref() {
+        diagnostics.send(DiagnosticEvent::new(
+            severity,
+            diagnostic.get_source(),
+            &diagnostic.get_description(),
+        ));
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{
+    communicator::communicator_error::CommunicatorError, configuration::ConfigurationProviderError,
+    cryptoki_error::CryptokiError,
+};
+
+use super::{Diagnostic, Severity};
+
+impl Diagnostic for CryptokiError {
+    fn get_severity(&self) -> Option<Severity> {
+        match self {
+            Self::SynchronizationElementPoisoned
+            | Self::TransportError
+            | Self::DeviceError
+            | Self::UnsupportedCommunicator(_) => Some(Severity::Error),
+            Self::FunctionFailed
+            | Self::DataInvalid
+            | Self::KeyTypeInconsistent
+            | Self::OriginatorNotRouted => Some(Severity::Warning),
+            // caused by the calling application or the user
+            Self::CryptokiNotInitialized
+            | Self::CryptokiAlreadyInitialized
+            | Self::SessionHandleInvalid
+            | Self::FunctionNotSupported
+            | Self::OperationNotInitialized
+            | Self::ObjectHandleInvalid
+            | Self::SlotIdInvalid
+            | Self::FunctionCanceled
+            | Self::MechanismInvalid
+            | Self::TemplateIncomplete
+            | Self::AttributeValueInvalid
+            | Self::DomainParamsInvalid
+            | Self::ArgumentsBad => None,
+        }
+    }
+
+    fn get_source(&self) -> &'static str {
+        "cryptoki"
+    }
+
+    fn get_description(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Diagnostic for CommunicatorError {
+    fn get_severity(&self) -> Option<Severity> {
+        match self {
+            Self::InvalidRequestData | Self::TaskFailed | Self::TaskTimedOut(_) => {
+                Some(Severity::Warning)
+            }
+            _ => Some(Severity::Error),
+        }
+    }
+
+    fn get_source(&self) -> &'static str {
+        "communicator"
+    }
+
+    fn get_description(&self) -> String {
+        match self {
+            // status details and metadata may carry request data
+            Self::InvalidStatus(status) => format!(
+                "Communicator responded with an invalid status: {:?}: {}",
+                status.code(),
+                status.message()
+            ),
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl Diagnostic for ConfigurationProviderError {
+    fn get_severity(&self) -> Option<Severity> {
+        Some(Severity::Error)
+    }
+
+    fn get_source(&self) -> &'static str {
+        "configuration"
+    }
+
+    fn get_description(&self) -> String {
+        self.to_string()
+    }
+}
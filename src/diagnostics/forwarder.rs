@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use tokio::{sync::mpsc::UnboundedReceiver, time};
+use tonic::async_trait;
+
+use crate::communicator::communicator_error::CommunicatorError;
+
+use super::DiagnosticEvent;
+
+/// Maximum number of events sent at once
+static MAX_BATCH_SIZE: usize = 20;
+/// Maximum time an event waits for other events to be batched with
+static FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Receives batches of diagnostic events, e.g., the server's log endpoint
+#[async_trait]
+pub(crate) trait DiagnosticsSink: Send {
+    async fn send_diagnostics(&mut self, message: String) -> Result<(), CommunicatorError>;
+}
+
+/// Forwards the received events to the sink in batches, until all senders are dropped
+///
+/// # Arguments
+///
+/// * `receiver` - receives the events to be forwarded
+/// * `sink` - receives the batches
+pub(super) async fn forward_diagnostics(
+    mut receiver: UnboundedReceiver<DiagnosticEvent>,
+    mut sink: Box<dyn DiagnosticsSink>,
+) {
+    while let Some(event) = receiver.recv().await {
+        let mut batch = vec![event];
+        let flush = time::sleep(FLUSH_INTERVAL);
+        tokio::pin!(flush);
+        while batch.len() < MAX_BATCH_SIZE {
+            tokio::select! {
+                _ = &mut flush => break,
+                event = receiver.recv() => match event {
+                    Some(event) => batch.push(event),
+                    None => break,
+                },
+            }
+        }
+        if let Err(err) = sink.send_diagnostics(format_batch(&batch)).await {
+            eprintln!("Couldn't forward diagnostics: {err}");
+        }
+    }
+}
+
+/// Formats the batch as a single message, one line per event.
+/// Repeated events are merged into a single line.
+fn format_batch(batch: &[DiagnosticEvent]) -> String {
+    let mut lines: Vec<(&DiagnosticEvent, usize)> = vec![];
+    for event in batch {
+        match lines.iter_mut().find(|(line, _)| *line == event) {
+            Some((_, count)) => *count += 1,
+            None => lines.push((event, 1)),
+        }
+    }
+
+    let mut message = format!("cryptoki-bridge {}", env!("CARGO_PKG_VERSION"));
+    for (event, count) in lines {
+        message.push('\n');
+        message.push_str(&event.to_string());
+        if count > 1 {
+            message.push_str(&format!(" ({count} times)"));
+        }
+    }
+    message
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use tokio::sync::mpsc;
+
+    use crate::diagnostics::Severity;
+
+    use super::*;
+
+    struct CollectingSink {
+        messages: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl DiagnosticsSink for CollectingSink {
+        async fn send_diagnostics(&mut self, message: String) -> Result<(), CommunicatorError> {
+            self.messages.lock().unwrap().push(message);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn given_queued_events_they_are_sent_in_a_single_batch() {
+        let messages = Arc::new(Mutex::new(vec![]));
+        let sink = CollectingSink {
+            messages: messages.clone(),
+        };
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let timed_out = DiagnosticEvent::new(Severity::Warning, "communicator", "Task timed out");
+        sender.send(timed_out.clone()).unwrap();
+        sender.send(timed_out).unwrap();
+        sender
+            .send(DiagnosticEvent::new(
+                Severity::Error,
+                "configuration",
+                "Value is in incorrect format",
+            ))
+            .unwrap();
+        drop(sender);
+
+        forward_diagnostics(receiver, Box::new(sink)).await;
+
+        let messages = messages.lock().unwrap();
+        assert_eq!(messages.len(), 1);
+        let lines: Vec<&str> = messages[0].lines().skip(1).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "warning communicator: Task timed out (2 times)",
+                "error configuration: Value is in incorrect format"
+            ]
+        );
+    }
+}
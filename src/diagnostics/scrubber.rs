@@ -0,0 +1,59 @@
+/// Shortest run of hex or base64 characters considered to be binary data
+static MIN_REDACTED_LENGTH: usize = 32;
+static REDACTED: &str = "<redacted>";
+
+/// Replaces anything resembling encoded binary data, e.g., hashes, signatures, keys,
+/// or the data to be signed, so that they never leave the machine
+///
+/// # Arguments
+///
+/// * `message` - the message to be scrubbed
+pub(super) fn scrub(message: &str) -> String {
+    let mut scrubbed = String::with_capacity(message.len());
+    let mut token = String::new();
+    for character in message.chars() {
+        if is_encoding_character(character) {
+            token.push(character);
+            continue;
+        }
+        push_token(&mut scrubbed, &token);
+        token.clear();
+        scrubbed.push(character);
+    }
+    push_token(&mut scrubbed, &token);
+    scrubbed
+}
+
+fn is_encoding_character(character: char) -> bool {
+    character.is_ascii_alphanumeric() || matches!(character, '+' | '/' | '=' | '_' | '-')
+}
+
+fn push_token(scrubbed: &mut String, token: &str) {
+    let is_binary_data =
+        token.len() >= MIN_REDACTED_LENGTH && token.chars().any(|c| c.is_ascii_digit());
+    scrubbed.push_str(if is_binary_data { REDACTED } else { token });
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(
+        "signing data 3f5a0c9d1e2b4a6f8c0d1e2f3a4b5c6d7e8f9a0b1c2d3e4f failed",
+        "signing data <redacted> failed"
+    )]
+    #[case(
+        "key: MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAE8xOUetsCa8EfOlDEBAfREhJqspDo",
+        "key: <redacted>"
+    )]
+    #[case(
+        "Communicator interaction failed: transport error",
+        "Communicator interaction failed: transport error"
+    )]
+    fn given_message_encoded_data_are_redacted(#[case] message: &str, #[case] expected: &str) {
+        assert_eq!(scrub(message), expected);
+    }
+}
@@ -4,7 +4,9 @@ pub(crate) mod communicator;
 mod configuration;
 pub mod cryptoki;
 mod cryptoki_error;
+mod diagnostics;
 mod persistence;
+mod process_identity;
 pub(crate) mod state;
 pub(crate) mod utils;
 pub(crate) mod package_info {
@@ -14,19 +16,20 @@ pub(crate) mod package_info {
 }
 
 use crate::{
-    communicator::Communicator,
-    configuration::ConfigurationProvider,
-    state::{session::sessions::Sessions, slots::Slots},
+    communicator::CommunicatorStore,
+    configuration::InterfaceConfiguration,
+    diagnostics::Diagnostics,
+    state::{session::sessions::Sessions, slots::Slots, GlobalState},
 };
 use lazy_static::lazy_static;
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::Arc;
 use tokio::runtime::Runtime;
 
 lazy_static! {
-    pub(crate) static ref SLOTS: RwLock<Option<Slots>> = RwLock::new(None);
-    pub(crate) static ref CONFIGURATION: RwLock<Option<Arc<dyn ConfigurationProvider>>> =
-        RwLock::new(None);
-    pub(crate) static ref SESSIONS: RwLock<Option<Sessions>> = RwLock::new(None);
-    pub(crate) static ref RUNTIME: RwLock<Option<Runtime>> = RwLock::new(None);
-    pub(crate) static ref COMMUNICATOR: Mutex<Option<Box<dyn Communicator>>> = Mutex::new(None);
+    pub(crate) static ref SLOTS: GlobalState<Slots> = GlobalState::new();
+    pub(crate) static ref CONFIGURATION: GlobalState<InterfaceConfiguration> = GlobalState::new();
+    pub(crate) static ref SESSIONS: GlobalState<Sessions> = GlobalState::new();
+    pub(crate) static ref RUNTIME: GlobalState<Arc<Runtime>> = GlobalState::new();
+    pub(crate) static ref COMMUNICATORS: GlobalState<Vec<CommunicatorStore>> = GlobalState::new();
+    pub(crate) static ref DIAGNOSTICS: GlobalState<Diagnostics> = GlobalState::new();
 }
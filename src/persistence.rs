@@ -1,7 +1,9 @@
 mod cryptoki_repo;
 pub(crate) mod models;
+mod pending_task_repo;
 pub(crate) mod persistence_error;
 mod sqlite_cryptoki_repo;
 
 pub(crate) use cryptoki_repo::CryptokiRepo;
+pub(crate) use pending_task_repo::PendingTaskRepo;
 pub(crate) use sqlite_cryptoki_repo::SqliteCryptokiRepo;
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use rusqlite::Row;
 use uuid::Uuid;
 
+use crate::communicator::TaskId;
 use crate::cryptoki::bindings::{
     CKA_CLASS, CKA_LABEL, CKO_DATA, CKO_PRIVATE_KEY, CKO_PUBLIC_KEY, CKO_SECRET_KEY,
     CK_ATTRIBUTE_TYPE,
@@ -62,6 +63,37 @@ impl ObjectModel {
     }
 }
 
+/// Represents a signing task whose result wasn't collected yet
+pub(crate) struct PendingTaskModel {
+    /// Hash of the group and the data the task signs
+    pub request_hash: Vec<u8>,
+
+    /// The id of the task on the communicator
+    pub task_id: TaskId,
+
+    /// Unix timestamp, in seconds, of the task creation
+    pub created_at: i64,
+}
+
+impl PendingTaskModel {
+    pub(crate) fn new(request_hash: Vec<u8>, task_id: TaskId, created_at: i64) -> Self {
+        Self {
+            request_hash,
+            task_id,
+            created_at,
+        }
+    }
+
+    /// Creates an instance of PendingTaskModel from an SQLite row
+    pub(crate) fn from_row(row: &Row<'_>) -> Result<Self, rusqlite::Error> {
+        Ok(PendingTaskModel {
+            request_hash: row.get(0)?,
+            task_id: row.get(1)?,
+            created_at: row.get(2)?,
+        })
+    }
+}
+
 // While the following functions are really ugly, we can't
 // implement `From<T>` for `Option<ObjectModel>` or `Result<ObjectModel, E>`
 // because of the orphan rule.
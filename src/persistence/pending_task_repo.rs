@@ -0,0 +1,28 @@
+use super::{models::PendingTaskModel, persistence_error::PersistenceError};
+
+/// Repository for storing signing tasks created on a communicator until their result is collected,
+/// so that a repeated request can pick up the task instead of creating a new one
+pub(crate) trait PendingTaskRepo: Send + Sync {
+    fn store_pending_task(&self, pending_task: &PendingTaskModel) -> Result<(), PersistenceError>;
+
+    /// Returns the task created for the request, unless it was created before `created_since`
+    ///
+    /// # Arguments
+    ///
+    /// * `request_hash` - the hash identifying the request
+    /// * `created_since` - unix timestamp, in seconds, of the oldest task that can be returned
+    fn get_pending_task(
+        &self,
+        request_hash: &[u8],
+        created_since: i64,
+    ) -> Result<Option<PendingTaskModel>, PersistenceError>;
+
+    fn remove_pending_task(&self, request_hash: &[u8]) -> Result<(), PersistenceError>;
+
+    /// Removes tasks that can no longer be picked up
+    ///
+    /// # Arguments
+    ///
+    /// * `created_before` - unix timestamp, in seconds, tasks created before it are removed
+    fn remove_expired_pending_tasks(&self, created_before: i64) -> Result<(), PersistenceError>;
+}
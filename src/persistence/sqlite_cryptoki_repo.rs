@@ -3,7 +3,7 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use rusqlite::{named_params, Connection, OpenFlags};
+use rusqlite::{named_params, Connection, OpenFlags, OptionalExtension};
 use uuid::Uuid;
 
 use crate::{
@@ -17,7 +17,8 @@ use crate::{
 
 use super::{
     cryptoki_repo::CryptokiRepo,
-    models::{try_object_model_from_cryptoki_object, ObjectModel},
+    models::{try_object_model_from_cryptoki_object, ObjectModel, PendingTaskModel},
+    pending_task_repo::PendingTaskRepo,
     persistence_error::PersistenceError,
 };
 
@@ -50,6 +51,14 @@ impl SqliteCryptokiRepo {
             );",
             (),
         )?;
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS pending_tasks (
+                `request_hash` BLOB PRIMARY KEY,
+                `task_id` BLOB NOT NULL,
+                `created_at` INTEGER NOT NULL
+            );",
+            (),
+        )?;
         Ok(())
     }
 
@@ -143,3 +152,77 @@ impl CryptokiRepo for SqliteCryptokiRepo {
             .collect())
     }
 }
+
+impl PendingTaskRepo for SqliteCryptokiRepo {
+    fn store_pending_task(&self, pending_task: &PendingTaskModel) -> Result<(), PersistenceError> {
+        let connection = self.connection.lock()?;
+        let mut statement = connection.prepare(
+            "INSERT OR REPLACE INTO pending_tasks (request_hash, task_id, created_at) VALUES (?1, ?2, ?3)",
+        )?;
+        statement.execute((
+            &pending_task.request_hash,
+            &pending_task.task_id,
+            pending_task.created_at,
+        ))?;
+        Ok(())
+    }
+
+    fn get_pending_task(
+        &self,
+        request_hash: &[u8],
+        created_since: i64,
+    ) -> Result<Option<PendingTaskModel>, PersistenceError> {
+        let connection = self.connection.lock()?;
+        let mut statement = connection.prepare(
+            "SELECT request_hash, task_id, created_at FROM pending_tasks WHERE request_hash = ?1 AND created_at >= ?2;",
+        )?;
+        let pending_task = statement
+            .query_row((request_hash, created_since), PendingTaskModel::from_row)
+            .optional()?;
+        Ok(pending_task)
+    }
+
+    fn remove_pending_task(&self, request_hash: &[u8]) -> Result<(), PersistenceError> {
+        let connection = self.connection.lock()?;
+        connection.execute(
+            "DELETE FROM pending_tasks WHERE request_hash = ?1;",
+            (request_hash,),
+        )?;
+        Ok(())
+    }
+
+    fn remove_expired_pending_tasks(&self, created_before: i64) -> Result<(), PersistenceError> {
+        let connection = self.connection.lock()?;
+        connection.execute(
+            "DELETE FROM pending_tasks WHERE created_at < ?1;",
+            (created_before,),
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn given_task_outside_of_reuse_window_it_is_not_returned() {
+        let directory = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&directory).unwrap();
+        let repo = SqliteCryptokiRepo::new(directory).unwrap();
+        repo.create_tables().unwrap();
+        let request_hash = vec![0x01; 32];
+        repo.store_pending_task(&PendingTaskModel::new(
+            request_hash.clone(),
+            vec![0x02],
+            100,
+        ))
+        .unwrap();
+
+        assert!(repo.get_pending_task(&request_hash, 100).unwrap().is_some());
+        assert!(repo.get_pending_task(&request_hash, 101).unwrap().is_none());
+
+        repo.remove_expired_pending_tasks(101).unwrap();
+        assert!(repo.get_pending_task(&request_hash, 0).unwrap().is_none());
+    }
+}
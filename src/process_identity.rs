@@ -0,0 +1,263 @@
+#[cfg(target_os = "linux")]
+mod proc_fs;
+mod trust_policy;
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock, PoisonError, RwLock},
+};
+
+use lazy_static::lazy_static;
+
+pub(crate) use self::trust_policy::TrustPolicy;
+
+/// Maximum number of ancestors collected, e.g., `ssh-pkcs11-helper < ssh < bash < sshd`
+static MAX_ANCESTORS: usize = 8;
+/// Helpers loading the library on behalf of the tool the user actually runs,
+/// the tool is looked up among their ancestors
+static HELPER_BINARIES: [&str; 1] = ["ssh-pkcs11-helper"];
+/// Number of bytes of the executable hash shown to the approvers
+static SHORT_HASH_LENGTH: usize = 8;
+
+lazy_static! {
+    static ref CURRENT_PROCESS: RwLock<Arc<ProcessIdentity>> =
+        RwLock::new(Arc::new(ProcessIdentity::collect()));
+}
+
+/// Information about a single process
+#[derive(Debug, Default)]
+pub(crate) struct ProcessInfo {
+    pid: u32,
+
+    /// Path of the executable as resolved by the kernel, unlike `argv[0]`,
+    /// it can't be set by the process
+    executable: Option<PathBuf>,
+
+    /// Whether the executable is owned by root, writable by no one else,
+    /// and still installed at its path, i.e., the path wasn't replaced since
+    executable_protected: bool,
+
+    /// Lazily computed SHA-256 of the executable
+    executable_hash: OnceLock<Option<String>>,
+
+    /// Real UID of the process
+    uid: Option<u32>,
+
+    parent_pid: Option<u32>,
+    cmdline: Vec<String>,
+}
+
+impl ProcessInfo {
+    pub(crate) fn get_pid(&self) -> u32 {
+        self.pid
+    }
+
+    pub(crate) fn get_executable(&self) -> Option<&Path> {
+        self.executable.as_deref()
+    }
+
+    pub(crate) fn get_uid(&self) -> Option<u32> {
+        self.uid
+    }
+
+    pub(crate) fn get_cmdline(&self) -> &[String] {
+        &self.cmdline
+    }
+
+    /// Returns the file name of the executable
+    pub(crate) fn get_name(&self) -> Option<String> {
+        self.executable
+            .as_ref()?
+            .file_name()?
+            .to_str()
+            .map(String::from)
+    }
+
+    /// Returns the hex-encoded SHA-256 of the executable, computed on the first call
+    pub(crate) fn get_executable_hash(&self) -> Option<&str> {
+        self.executable_hash
+            .get_or_init(|| self.hash_executable())
+            .as_deref()
+    }
+
+    /// Returns the beginning of the executable hash, long enough to tell binaries apart
+    pub(crate) fn get_short_executable_hash(&self) -> Option<&str> {
+        self.get_executable_hash()
+            .map(|hash| &hash[..2 * SHORT_HASH_LENGTH])
+    }
+
+    #[cfg(target_os = "linux")]
+    fn hash_executable(&self) -> Option<String> {
+        proc_fs::hash_executable(self.pid)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn hash_executable(&self) -> Option<String> {
+        let executable = std::fs::read(self.executable.as_ref()?).ok()?;
+        Some(hex::encode(openssl::sha::sha256(&executable)))
+    }
+}
+
+/// Identifies the process that loaded the library, together with its ancestors,
+/// so that requests can be attributed to the tool the user runs
+#[derive(Debug, Default)]
+pub(crate) struct ProcessIdentity {
+    process: ProcessInfo,
+
+    /// Ancestors of the process, the parent first
+    ancestors: Vec<ProcessInfo>,
+}
+
+impl ProcessIdentity {
+    /// Returns the identity of the current process, collected on the first call
+    /// and again whenever it is refreshed
+    pub(crate) fn current() -> Arc<Self> {
+        CURRENT_PROCESS
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Collects the identity of the current process again. A forked child inherits
+    /// the identity of its parent, its requests would be attributed to the parent otherwise.
+    pub(crate) fn refresh() {
+        let identity = Arc::new(Self::collect());
+        // a lock held by a thread of the parent is never released in the forked child
+        match CURRENT_PROCESS.try_write() {
+            Ok(mut current) => *current = identity,
+            Err(_) => eprintln!("Couldn't refresh the identity of the forked process"),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn collect() -> Self {
+        let process = proc_fs::read_process_info(std::process::id()).unwrap_or_default();
+        let mut ancestors: Vec<ProcessInfo> = vec![];
+        let mut parent_pid = process.parent_pid;
+        while let Some(pid) = parent_pid.filter(|&pid| pid != 0) {
+            if ancestors.len() == MAX_ANCESTORS {
+                break;
+            }
+            let Some(parent) = proc_fs::read_process_info(pid) else {
+                break;
+            };
+            parent_pid = parent.parent_pid;
+            ancestors.push(parent);
+        }
+        Self { process, ancestors }
+    }
+
+    /// Outside of Linux, only the executable path is known
+    #[cfg(not(target_os = "linux"))]
+    fn collect() -> Self {
+        let process = ProcessInfo {
+            pid: std::process::id(),
+            executable: std::env::current_exe().ok(),
+            ..Default::default()
+        };
+        Self {
+            process,
+            ancestors: vec![],
+        }
+    }
+
+    pub(crate) fn get_process(&self) -> &ProcessInfo {
+        &self.process
+    }
+
+    /// Returns the name of the tool the request comes from, i.e., the executable
+    /// of the process, or of its nearest ancestor that is not a helper
+    /// loading the library on behalf of the tool. `None` if the policy
+    /// doesn't trust the executable.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - determines which executables are trusted
+    pub(crate) fn get_tool_name(&self, policy: TrustPolicy) -> Option<String> {
+        let tool = std::iter::once(&self.process)
+            .chain(&self.ancestors)
+            .find(|process| {
+                process
+                    .get_name()
+                    .is_none_or(|name| !HELPER_BINARIES.contains(&name.as_str()))
+            })?;
+        policy.trusts(tool).then(|| tool.get_name()).flatten()
+    }
+
+    /// Returns the names of the ancestors, the parent first
+    pub(crate) fn get_ancestor_names(&self) -> Vec<String> {
+        self.ancestors
+            .iter()
+            .map(|ancestor| ancestor.get_name().unwrap_or_else(|| "?".into()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn get_process(executable: &str, protected: bool) -> ProcessInfo {
+        ProcessInfo {
+            executable: Some(executable.into()),
+            executable_protected: protected,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn given_helper_process_tool_is_taken_from_its_parent() {
+        let identity = ProcessIdentity {
+            process: get_process("/usr/lib/openssh/ssh-pkcs11-helper", true),
+            ancestors: vec![
+                get_process("/usr/bin/ssh", true),
+                get_process("/usr/bin/bash", true),
+            ],
+        };
+        assert_eq!(
+            identity.get_tool_name(TrustPolicy::TrustedPath),
+            Some("ssh".into())
+        );
+        assert_eq!(identity.get_ancestor_names(), vec!["ssh", "bash"]);
+    }
+
+    #[test]
+    fn given_untrusted_executable_tool_name_depends_on_the_policy() {
+        let identity = ProcessIdentity {
+            process: get_process("/home/mallory/bin/ssh", false),
+            ancestors: vec![],
+        };
+        assert_eq!(
+            identity.get_tool_name(TrustPolicy::ExecutableName),
+            Some("ssh".into())
+        );
+        assert_eq!(identity.get_tool_name(TrustPolicy::TrustedPath), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn given_current_process_its_identity_is_collected() {
+        let identity = ProcessIdentity::current();
+        let process = identity.get_process();
+        assert_eq!(process.get_pid(), std::process::id());
+        assert_eq!(
+            process.get_executable(),
+            std::env::current_exe().ok().as_deref()
+        );
+        assert!(process.get_uid().is_some());
+        assert!(!process.get_cmdline().is_empty());
+        assert!(!identity.get_ancestor_names().is_empty());
+        assert_eq!(process.get_executable_hash().map(str::len), Some(64));
+    }
+
+    #[test]
+    fn given_refresh_the_identity_is_collected_again() {
+        let inherited = ProcessIdentity::current();
+
+        ProcessIdentity::refresh();
+
+        let refreshed = ProcessIdentity::current();
+        assert!(!Arc::ptr_eq(&inherited, &refreshed));
+        assert_eq!(refreshed.get_process().get_pid(), std::process::id());
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    fs::{self, File, Metadata},
+    io,
+    os::unix::fs::MetadataExt,
+    path::PathBuf,
+};
+
+use openssl::sha::Sha256;
+
+use super::ProcessInfo;
+
+/// Bits allowing the group and others to write
+static GROUP_OTHERS_WRITE_MODE: u32 = 0o022;
+
+/// Reads the information about the process from `/proc`,
+/// `None` if the process doesn't exist or isn't accessible
+///
+/// # Arguments
+///
+/// * `pid` - the id of the process
+pub(super) fn read_process_info(pid: u32) -> Option<ProcessInfo> {
+    let process_directory = get_process_directory(pid);
+    let status = fs::read_to_string(process_directory.join("status")).ok()?;
+    let (uid, parent_pid) = parse_status(&status);
+    let executable = fs::read_link(process_directory.join("exe")).ok();
+    // the link resolves to the running executable even if its path was replaced since
+    let metadata = fs::metadata(process_directory.join("exe")).ok();
+    let installed_metadata = executable
+        .as_ref()
+        .and_then(|executable| fs::metadata(executable).ok());
+    let cmdline = fs::read(process_directory.join("cmdline"))
+        .map(|cmdline| parse_cmdline(&cmdline))
+        .unwrap_or_default();
+
+    Some(ProcessInfo {
+        pid,
+        executable,
+        executable_protected: is_protected(metadata, installed_metadata),
+        executable_hash: Default::default(),
+        uid,
+        parent_pid,
+        cmdline,
+    })
+}
+
+/// Returns whether the running executable is owned by root, writable by no one else,
+/// and is the same file as the one installed at its path
+fn is_protected(metadata: Option<Metadata>, installed_metadata: Option<Metadata>) -> bool {
+    let (Some(metadata), Some(installed_metadata)) = (metadata, installed_metadata) else {
+        return false;
+    };
+    let is_installed =
+        (metadata.dev(), metadata.ino()) == (installed_metadata.dev(), installed_metadata.ino());
+    is_installed && metadata.uid() == 0 && metadata.mode() & GROUP_OTHERS_WRITE_MODE == 0
+}
+
+/// Hashes the executable the process is running, read through `/proc`,
+/// so that it is the running binary, even if the path was replaced since
+pub(super) fn hash_executable(pid: u32) -> Option<String> {
+    let mut executable = File::open(get_process_directory(pid).join("exe")).ok()?;
+    let mut hasher = HashWriter(Sha256::new());
+    io::copy(&mut executable, &mut hasher).ok()?;
+    Some(hex::encode(hasher.0.finish()))
+}
+
+fn get_process_directory(pid: u32) -> PathBuf {
+    PathBuf::from("/proc").join(pid.to_string())
+}
+
+/// Returns the real UID and the parent PID from the contents of `/proc/<pid>/status`
+fn parse_status(status: &str) -> (Option<u32>, Option<u32>) {
+    let get_first_value = |key: &str| {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(key))
+            .and_then(|values| values.split_whitespace().next())
+            .and_then(|value| value.parse().ok())
+    };
+    (get_first_value("Uid:"), get_first_value("PPid:"))
+}
+
+/// Splits the NUL-separated arguments of `/proc/<pid>/cmdline`
+fn parse_cmdline(cmdline: &[u8]) -> Vec<String> {
+    cmdline
+        .split(|&byte| byte == 0)
+        .filter(|argument| !argument.is_empty())
+        .map(|argument| String::from_utf8_lossy(argument).into_owned())
+        .collect()
+}
+
+struct HashWriter(Sha256);
+
+impl io::Write for HashWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn given_status_uid_and_parent_pid_are_parsed() {
+        let status = "Name:\tssh\nUmask:\t0022\nState:\tS (sleeping)\nPid:\t4242\nPPid:\t4200\nUid:\t1000\t1000\t1000\t1000\n";
+        assert_eq!(parse_status(status), (Some(1000), Some(4200)));
+    }
+
+    #[test]
+    fn given_cmdline_arguments_are_split() {
+        assert_eq!(
+            parse_cmdline(b"ssh\0-i\0key\0git@example.com\0"),
+            vec!["ssh", "-i", "key", "git@example.com"]
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use std::{fs, path::Path};
+
+use toml::{value::Table, Value};
+
+use crate::configuration::SYSTEM_CONFIGURATION_PATH;
+
+use super::ProcessInfo;
+
+static TRUST_POLICY_KEY: &str = "process_trust_policy";
+/// Directories of system-wide binaries, writable only by root
+static TRUSTED_DIRECTORIES: [&str; 7] = [
+    "/bin",
+    "/sbin",
+    "/usr/bin",
+    "/usr/sbin",
+    "/usr/local/bin",
+    "/usr/libexec",
+    "/usr/lib/openssh",
+];
+
+/// Determines which executables are trusted to be the tools they claim to be,
+/// used when selecting the tool configuration and when naming tasks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum TrustPolicy {
+    /// Any executable is trusted, its file name can be spoofed
+    /// by copying or linking the binary
+    ExecutableName,
+
+    /// Only executables installed in a system directory, owned by root
+    /// and writable by no one else, are trusted
+    #[default]
+    TrustedPath,
+}
+
+impl TrustPolicy {
+    /// Reads the policy from the top-level `process_trust_policy` key of the system-wide
+    /// configuration file, either `name` or `path`. Only root can change the file,
+    /// so the process being judged can't weaken its own policy. It has to be known
+    /// before the configuration is fetched, as the configuration depends on the tool.
+    pub(crate) fn from_system_configuration() -> Self {
+        fs::read_to_string(SYSTEM_CONFIGURATION_PATH)
+            .map(|content| Self::from_configuration(&content))
+            .unwrap_or_default()
+    }
+
+    /// Parses the policy from the configuration file, failing closed
+    /// to [`TrustPolicy::TrustedPath`] if the key is missing or unknown
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - the content of the configuration file
+    fn from_configuration(content: &str) -> Self {
+        let Ok(file) = toml::from_str::<Table>(content) else {
+            return Self::default();
+        };
+        match file.get(TRUST_POLICY_KEY).and_then(Value::as_str) {
+            Some("name") => Self::ExecutableName,
+            _ => Self::TrustedPath,
+        }
+    }
+
+    pub(crate) fn trusts(&self, process: &ProcessInfo) -> bool {
+        match self {
+            Self::ExecutableName => true,
+            Self::TrustedPath => {
+                let is_in_trusted_directory = process
+                    .get_executable()
+                    .and_then(Path::parent)
+                    .is_some_and(|directory| {
+                        TRUSTED_DIRECTORIES
+                            .iter()
+                            .any(|trusted| directory == Path::new(trusted))
+                    });
+                is_in_trusted_directory && process.executable_protected
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn given_no_policy_key_trusted_path_is_required() {
+        let policy = TrustPolicy::from_configuration("[cryptoki]\ngroup_id = \"abcd\"");
+
+        assert_eq!(policy, TrustPolicy::TrustedPath);
+    }
+
+    #[test]
+    fn given_name_policy_executable_names_are_trusted() {
+        let policy = TrustPolicy::from_configuration("process_trust_policy = \"name\"");
+
+        assert_eq!(policy, TrustPolicy::ExecutableName);
+    }
+
+    #[test]
+    fn given_unknown_policy_trusted_path_is_required() {
+        let policy = TrustPolicy::from_configuration("process_trust_policy = \"anything\"");
+
+        assert_eq!(policy, TrustPolicy::TrustedPath);
+    }
+
+    #[test]
+    fn given_invalid_file_trusted_path_is_required() {
+        let policy = TrustPolicy::from_configuration("process_trust_policy = name");
+
+        assert_eq!(policy, TrustPolicy::TrustedPath);
+    }
+}
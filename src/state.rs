@@ -1,7 +1,13 @@
+mod lifecycle;
 pub(crate) mod object;
 pub(crate) mod session;
 pub(crate) mod slots;
 mod state_accessor;
+mod threading_model;
 pub(crate) mod token;
 
+pub(crate) use lifecycle::GlobalState;
 pub(crate) use state_accessor::StateAccessor;
+pub(crate) use threading_model::{
+    ensure_outside_of_runtime, resolve_on_calling_thread, ThreadingModel,
+};
@@ -0,0 +1,176 @@
+use std::{
+    mem, process,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    },
+};
+
+use crate::cryptoki_error::CryptokiError;
+
+/// Process ID marking that no process initialized the library
+const NO_PROCESS: u32 = 0;
+
+pub(crate) static LIFECYCLE: Lifecycle = Lifecycle::new();
+
+/// State of the library in the calling process
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum LifecycleState {
+    Uninitialized,
+    Initialized,
+
+    /// The library was initialized by the parent of the forked process.
+    /// The inherited state relies on threads that don't exist in this process,
+    /// so the library has to be initialized again.
+    InheritedFromParent,
+}
+
+/// Tracks which process initialized the library, so that a forked child
+/// is recognized by its process ID
+pub(crate) struct Lifecycle {
+    /// ID of the process that initialized the library
+    initialized_in: AtomicU32,
+
+    /// Serializes the initialization and the finalization
+    transition: Mutex<()>,
+}
+
+impl Lifecycle {
+    const fn new() -> Self {
+        Self {
+            initialized_in: AtomicU32::new(NO_PROCESS),
+            transition: Mutex::new(()),
+        }
+    }
+
+    pub(crate) fn get_state(&self) -> LifecycleState {
+        self.get_state_in(process::id())
+    }
+
+    /// Returns the state of the library as seen by the process
+    ///
+    /// # Arguments
+    ///
+    /// * `process_id` - the ID of the process accessing the library
+    fn get_state_in(&self, process_id: u32) -> LifecycleState {
+        match self.initialized_in.load(Ordering::Acquire) {
+            NO_PROCESS => LifecycleState::Uninitialized,
+            initialized_in if initialized_in == process_id => LifecycleState::Initialized,
+            _ => LifecycleState::InheritedFromParent,
+        }
+    }
+
+    /// Locks the lifecycle, so that only one thread initializes or finalizes the library
+    pub(crate) fn begin_transition(&self) -> Result<MutexGuard<'_, ()>, CryptokiError> {
+        Ok(self.transition.lock()?)
+    }
+
+    pub(crate) fn mark_initialized(&self) {
+        self.mark_initialized_in(process::id());
+    }
+
+    fn mark_initialized_in(&self, process_id: u32) {
+        self.initialized_in.store(process_id, Ordering::Release);
+    }
+
+    pub(crate) fn mark_uninitialized(&self) {
+        self.initialized_in.store(NO_PROCESS, Ordering::Release);
+    }
+}
+
+/// Global state of the library, accessible only while the library is initialized
+/// by the calling process
+pub(crate) struct GlobalState<T> {
+    state: RwLock<Option<T>>,
+}
+
+impl<T> GlobalState<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: RwLock::new(None),
+        }
+    }
+
+    pub(crate) fn read(&self) -> Result<RwLockReadGuard<'_, Option<T>>, CryptokiError> {
+        ensure_initialized()?;
+        Ok(self.state.read()?)
+    }
+
+    pub(crate) fn write(&self) -> Result<RwLockWriteGuard<'_, Option<T>>, CryptokiError> {
+        ensure_initialized()?;
+        Ok(self.state.write()?)
+    }
+
+    /// Sets the state while the library is being initialized
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - the new state
+    pub(super) fn set(&self, value: T) -> Result<(), CryptokiError> {
+        let _ = self.state.write()?.insert(value);
+        Ok(())
+    }
+
+    /// Removes the state while the library is being finalized
+    pub(super) fn take(&self) -> Result<Option<T>, CryptokiError> {
+        Ok(self.state.write()?.take())
+    }
+
+    /// Removes the state inherited from the parent process without dropping it.
+    /// Dropping would wait for threads that don't exist in this process, or close
+    /// resources, e.g., database connections, the parent still uses.
+    pub(super) fn abandon(&self) {
+        // a lock held by a thread of the parent is never released in this process
+        let mut state = match self.state.try_write() {
+            Ok(state) => state,
+            Err(_) => {
+                eprintln!("Couldn't abandon the state inherited from the parent process");
+                return;
+            }
+        };
+        mem::forget(state.take());
+    }
+}
+
+fn ensure_initialized() -> Result<(), CryptokiError> {
+    match LIFECYCLE.get_state() {
+        LifecycleState::Initialized => Ok(()),
+        LifecycleState::Uninitialized | LifecycleState::InheritedFromParent => {
+            Err(CryptokiError::CryptokiNotInitialized)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn given_no_initialization_the_library_is_uninitialized() {
+        let lifecycle = Lifecycle::new();
+
+        assert_eq!(lifecycle.get_state_in(42), LifecycleState::Uninitialized);
+    }
+
+    #[test]
+    fn given_initialization_in_the_process_the_library_is_initialized() {
+        let lifecycle = Lifecycle::new();
+        lifecycle.mark_initialized_in(42);
+
+        assert_eq!(lifecycle.get_state_in(42), LifecycleState::Initialized);
+
+        lifecycle.mark_uninitialized();
+        assert_eq!(lifecycle.get_state_in(42), LifecycleState::Uninitialized);
+    }
+
+    #[test]
+    fn given_initialization_in_the_parent_the_state_is_inherited() {
+        let lifecycle = Lifecycle::new();
+        lifecycle.mark_initialized_in(42);
+
+        assert_eq!(
+            lifecycle.get_state_in(43),
+            LifecycleState::InheritedFromParent
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use crate::cryptoki::{
-    bindings::{CK_ATTRIBUTE, CK_ATTRIBUTE_TYPE},
+    bindings::{CK_ATTRIBUTE, CK_ATTRIBUTE_TYPE, CK_BBOOL, CK_FALSE, CK_TRUE},
     utils::FromPointer,
 };
 use crate::state::object::cryptoki_object::AttributeValue;
@@ -66,6 +66,17 @@ impl ToAttributeValue for u32 {
     }
 }
 
+impl ToAttributeValue for bool {
+    fn to_attribute_value(self) -> AttributeValue {
+        let value: CK_BBOOL = if self {
+            CK_TRUE as CK_BBOOL
+        } else {
+            CK_FALSE as CK_BBOOL
+        };
+        vec![value]
+    }
+}
+
 impl ToAttributeValue for &str {
     fn to_attribute_value(self) -> AttributeValue {
         self.as_bytes().to_vec()
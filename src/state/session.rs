@@ -1,3 +1,5 @@
+pub(crate) mod group_task;
 mod handle_resolver;
 pub(crate) mod sessions;
+pub(crate) mod signing_task;
 pub(crate) mod single_session;
@@ -0,0 +1,129 @@
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    communicator::{
+        group::{Group, GroupParameters},
+        Communicator,
+    },
+    cryptoki_error::CryptokiError,
+};
+
+/// Asks the communicator to create the group and waits until its members
+/// finish the distributed key generation. Once canceled, the wait is interrupted
+/// and the communicator is told about it.
+///
+/// # Arguments
+///
+/// * `communicator` - the communicator that will create the group
+/// * `parameters` - describes the group to be created
+/// * `cancellation_token` - cancels the key generation
+pub(crate) async fn create_group(
+    communicator: &mut dyn Communicator,
+    parameters: GroupParameters,
+    cancellation_token: &CancellationToken,
+) -> Result<Group, CryptokiError> {
+    let task_id = tokio::select! {
+        biased;
+        _ = cancellation_token.cancelled() => return Err(CryptokiError::FunctionCanceled),
+        task_id = communicator.send_group_request(parameters.clone()) => task_id?,
+    };
+
+    let response = tokio::select! {
+        biased;
+        _ = cancellation_token.cancelled() => None,
+        response = communicator.get_auth_response(task_id.clone()) => Some(response),
+    };
+    let Some(response) = response else {
+        if let Err(err) = communicator.cancel_task(task_id).await {
+            eprintln!("Couldn't cancel the task: {err}");
+        }
+        return Err(CryptokiError::FunctionCanceled);
+    };
+    let group_id = response?.ok_or(CryptokiError::FunctionFailed)?;
+    Ok(parameters.into_group(group_id))
+}
+
+#[cfg(all(test, not(feature = "mocked_communicator")))]
+mod test {
+    use tonic::transport::Certificate;
+
+    use crate::communicator::{
+        group::{GroupCurve, GroupKeyType},
+        meesign::{
+            stand_in::{verify_signature_on_curve, MeesignStandIn},
+            Meesign,
+        },
+        request_context::RequestContext,
+    };
+
+    use super::*;
+
+    static CHALLENGE: [u8; 32] = [0xab; 32];
+
+    #[tokio::test]
+    async fn given_group_parameters_created_group_is_listed_and_signs() {
+        let stand_in = MeesignStandIn::new().start().await;
+        let directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let endpoint = stand_in.get_endpoint("unused".into());
+        let certificate = Certificate::from_pem(stand_in.get_ca_certificate());
+        let mut meesign = Meesign::new(&endpoint, certificate, &directory)
+            .await
+            .unwrap();
+        let parameters = GroupParameters::new(
+            "ssh".into(),
+            vec![vec![1; 16], vec![2; 16], vec![3; 16]],
+            2,
+            GroupKeyType::SignChallenge,
+            GroupCurve::Ed25519,
+        );
+
+        let group = create_group(&mut meesign, parameters, &CancellationToken::new())
+            .await
+            .unwrap();
+
+        assert_eq!(group.get_name(), "ssh");
+        assert_eq!(group.get_curve(), GroupCurve::Ed25519);
+        let groups = meesign.get_groups().await.unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].get_group_id(), group.get_group_id());
+        let task_id = meesign
+            .send_auth_request(
+                group.get_group_id().clone(),
+                GroupKeyType::SignChallenge,
+                CHALLENGE.to_vec(),
+                RequestContext::default(),
+            )
+            .await
+            .unwrap();
+        let signature = meesign.get_auth_response(task_id).await.unwrap().unwrap();
+        assert!(verify_signature_on_curve(
+            GroupCurve::Ed25519,
+            group.get_group_id(),
+            &CHALLENGE.to_vec(),
+            &signature
+        ));
+    }
+
+    #[tokio::test]
+    async fn given_threshold_above_device_count_group_is_not_created() {
+        let stand_in = MeesignStandIn::new().start().await;
+        let directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let endpoint = stand_in.get_endpoint("unused".into());
+        let certificate = Certificate::from_pem(stand_in.get_ca_certificate());
+        let mut meesign = Meesign::new(&endpoint, certificate, &directory)
+            .await
+            .unwrap();
+        let parameters = GroupParameters::new(
+            "wallet".into(),
+            vec![vec![1; 16]],
+            2,
+            GroupKeyType::SignChallenge,
+            GroupCurve::Secp256k1,
+        );
+
+        let result = create_group(&mut meesign, parameters, &CancellationToken::new()).await;
+
+        assert!(result.is_err());
+        assert_eq!(stand_in.get_task_count(), 0);
+    }
+}
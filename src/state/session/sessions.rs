@@ -3,7 +3,9 @@ use std::{collections::HashMap, sync::Arc};
 use rand::{rngs::OsRng, Rng};
 
 use crate::{
-    cryptoki::bindings::CK_SESSION_HANDLE, persistence::CryptokiRepo, state::slots::TokenStore,
+    cryptoki::bindings::CK_SESSION_HANDLE,
+    persistence::{CryptokiRepo, PendingTaskRepo},
+    state::slots::TokenStore,
 };
 
 use super::single_session::Session;
@@ -15,16 +17,27 @@ pub(crate) struct Sessions {
 
     /// A repository for accessing the database
     cryptoki_repo: Arc<dyn CryptokiRepo>,
+
+    /// A repository remembering signing tasks until their result is collected
+    pending_task_repo: Arc<dyn PendingTaskRepo>,
 }
 
 impl Sessions {
-    pub(crate) fn new(cryptoki_repo: Arc<dyn CryptokiRepo>) -> Self {
+    pub(crate) fn new(
+        cryptoki_repo: Arc<dyn CryptokiRepo>,
+        pending_task_repo: Arc<dyn PendingTaskRepo>,
+    ) -> Self {
         Self {
             sessions: HashMap::new(),
             cryptoki_repo,
+            pending_task_repo,
         }
     }
 
+    pub(crate) fn get_pending_task_repo(&self) -> Arc<dyn PendingTaskRepo> {
+        self.pending_task_repo.clone()
+    }
+
     fn generate_session_handle(&self) -> CK_SESSION_HANDLE {
         OsRng.gen_range(0..CK_SESSION_HANDLE::MAX)
     }
@@ -41,7 +54,9 @@ impl Sessions {
     }
 
     pub(crate) fn close_session(&mut self, session_handle: &CK_SESSION_HANDLE) {
-        self.sessions.remove(session_handle);
+        if let Some(session) = self.sessions.remove(session_handle) {
+            session.cancel_operation();
+        }
         self.sessions.shrink_to_fit();
     }
 
@@ -57,6 +72,9 @@ impl Sessions {
     }
 
     pub(crate) fn close_sessions(&mut self) {
+        for session in self.sessions.values() {
+            session.cancel_operation();
+        }
         self.sessions.clear();
         self.sessions.shrink_to_fit();
     }
@@ -0,0 +1,370 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use openssl::sha::Sha256;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    communicator::{
+        communicator_error::CommunicatorError, group::GroupKeyType,
+        request_context::RequestContext, request_kind::RequestKind, AuthResponse, Communicator,
+        GroupId, RequestData, TaskId,
+    },
+    cryptoki_error::CryptokiError,
+    persistence::{models::PendingTaskModel, PendingTaskRepo},
+    process_identity::ProcessIdentity,
+};
+
+/// Sends the request of the given kind and waits for the response. If a task for the same
+/// request was created within the reuse window, e.g., by a process that crashed
+/// before collecting the result, the task is picked up instead.
+/// The task is forgotten once it is resolved, canceled, or fails for a reason
+/// other than a network failure or a timeout.
+///
+/// Tasks are remembered in the database of the user by the hash of the server,
+/// the requester, the group, the kind and the data of the request. The requester
+/// limits who gets the result the approvers authorised for the task, see [`get_requester`].
+///
+/// # Arguments
+///
+/// * `communicator` - the communicator owning the group
+/// * `pending_task_repo` - the repository remembering the tasks
+/// * `reuse_window` - how long a created task can be picked up
+/// * `group_id` - the id of the group that will handle the request
+/// * `request_kind` - the kind of the task requested from the group
+/// * `data` - the data of the request, e.g., the data to be signed
+/// * `request_context` - describes the request to the approvers
+/// * `cancellation_token` - cancels the request
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn send_or_resume_request(
+    communicator: &mut dyn Communicator,
+    pending_task_repo: &dyn PendingTaskRepo,
+    reuse_window: Duration,
+    group_id: GroupId,
+    request_kind: RequestKind,
+    data: RequestData,
+    request_context: RequestContext,
+    cancellation_token: &CancellationToken,
+) -> Result<Option<AuthResponse>, CryptokiError> {
+    let request_hash = get_request_hash(
+        &communicator.get_server_address(),
+        &get_requester(request_kind),
+        &group_id,
+        request_kind,
+        &data,
+    );
+    let now = get_unix_timestamp();
+    let created_since = now.saturating_sub(reuse_window.as_secs() as i64);
+    // the tasks are remembered on a best-effort basis, the request doesn't depend on them
+    if let Err(err) = pending_task_repo.remove_expired_pending_tasks(created_since) {
+        eprintln!("Couldn't forget the expired tasks: {err}");
+    }
+    let pending_task = pending_task_repo
+        .get_pending_task(&request_hash, created_since)
+        .unwrap_or_else(|err| {
+            eprintln!("Couldn't look up the task created for the request: {err}");
+            None
+        });
+    let task_id = match pending_task {
+        Some(pending_task) => pending_task.task_id,
+        None => {
+            let task_id = tokio::select! {
+                biased;
+                _ = cancellation_token.cancelled() => return Err(CryptokiError::FunctionCanceled),
+                task_id = send_request(communicator, group_id, request_kind, data, request_context) => task_id?,
+            };
+            let pending_task = PendingTaskModel::new(request_hash.clone(), task_id.clone(), now);
+            if let Err(err) = pending_task_repo.store_pending_task(&pending_task) {
+                eprintln!(
+                    "Couldn't remember the task, it won't be picked up when interrupted: {err}"
+                );
+            }
+            task_id
+        }
+    };
+
+    let response = tokio::select! {
+        biased;
+        _ = cancellation_token.cancelled() => None,
+        response = communicator.get_auth_response(task_id.clone()) => Some(response),
+    };
+    match response {
+        None => {
+            forget_pending_task(pending_task_repo, &request_hash);
+            if let Err(err) = communicator.cancel_task(task_id).await {
+                eprintln!("Couldn't cancel the task: {err}");
+            }
+            Err(CryptokiError::FunctionCanceled)
+        }
+        Some(Err(err)) if is_resumable(&err) => Err(err.into()),
+        Some(response) => {
+            forget_pending_task(pending_task_repo, &request_hash);
+            Ok(response?)
+        }
+    }
+}
+
+/// Forgets the task, a task that can't be forgotten expires after the reuse window
+fn forget_pending_task(pending_task_repo: &dyn PendingTaskRepo, request_hash: &[u8]) {
+    if let Err(err) = pending_task_repo.remove_pending_task(request_hash) {
+        eprintln!("Couldn't forget the task: {err}");
+    }
+}
+
+async fn send_request(
+    communicator: &mut dyn Communicator,
+    group_id: GroupId,
+    request_kind: RequestKind,
+    data: RequestData,
+    request_context: RequestContext,
+) -> Result<TaskId, CommunicatorError> {
+    match request_kind {
+        RequestKind::Authentication => {
+            communicator
+                .send_auth_request(group_id, GroupKeyType::SignChallenge, data, request_context)
+                .await
+        }
+        RequestKind::DocumentSigning => {
+            communicator
+                .send_auth_request(group_id, GroupKeyType::SignPdf, data, request_context)
+                .await
+        }
+        RequestKind::Decryption => {
+            communicator
+                .send_decryption_request(group_id, data, request_context)
+                .await
+        }
+        RequestKind::KeyAgreement => {
+            communicator
+                .send_key_agreement_request(group_id, data, request_context)
+                .await
+        }
+    }
+}
+
+/// Returns whether the task can still be resolved after the error
+fn is_resumable(err: &CommunicatorError) -> bool {
+    err.is_network_failure() || matches!(err, CommunicatorError::TaskTimedOut(_))
+}
+
+/// Identifies who may pick up a remembered task. A signature can be collected
+/// by a restarted instance of the same tool, i.e., of the same executable.
+/// Decrypted data and agreed secrets are handed only to the process that requested them.
+///
+/// # Arguments
+///
+/// * `request_kind` - the kind of the requested task
+fn get_requester(request_kind: RequestKind) -> Vec<u8> {
+    let process_identity = ProcessIdentity::current();
+    let process = process_identity.get_process();
+    let mut requester = process
+        .get_executable()
+        .map(|executable| executable.to_string_lossy().as_bytes().to_vec())
+        .unwrap_or_default();
+    if matches!(
+        request_kind,
+        RequestKind::Decryption | RequestKind::KeyAgreement
+    ) {
+        requester.extend(process.get_pid().to_be_bytes());
+    }
+    requester
+}
+
+fn get_request_hash(
+    server_address: &str,
+    requester: &[u8],
+    group_id: &GroupId,
+    request_kind: RequestKind,
+    data: &RequestData,
+) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    // the variable-length fields are prefixed with their length, so they can't run into each other
+    for field in [server_address.as_bytes(), requester, group_id] {
+        hasher.update(&(field.len() as u64).to_be_bytes());
+        hasher.update(field);
+    }
+    hasher.update(&[request_kind.to_byte()]);
+    hasher.update(data);
+    hasher.finish().to_vec()
+}
+
+fn get_unix_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(all(test, not(feature = "mocked_communicator")))]
+mod test {
+    use std::time::Duration;
+
+    use tonic::transport::Certificate;
+
+    use crate::communicator::{
+        meesign::{
+            stand_in::{verify_signature, MeesignStandIn},
+            Meesign,
+        },
+        Communicator,
+    };
+
+    use crate::persistence::{persistence_error::PersistenceError, SqliteCryptokiRepo};
+
+    use super::*;
+
+    static CHALLENGE: [u8; 32] = [0xab; 32];
+
+    #[test]
+    fn given_secret_request_only_the_requesting_process_picks_it_up() {
+        let tool = get_requester(RequestKind::Authentication);
+        let process = get_requester(RequestKind::Decryption);
+
+        assert!(process.starts_with(&tool));
+        assert_eq!(&process[tool.len()..], std::process::id().to_be_bytes());
+        assert_eq!(get_requester(RequestKind::KeyAgreement), process);
+    }
+
+    #[test]
+    fn given_requests_to_different_servers_their_hashes_differ() {
+        let group_id = vec![1, 2, 3];
+        let hash_on = |server_address| {
+            get_request_hash(
+                server_address,
+                b"tool",
+                &group_id,
+                RequestKind::Authentication,
+                &CHALLENGE.to_vec(),
+            )
+        };
+
+        assert_ne!(hash_on("first:1337"), hash_on("second:1337"));
+    }
+
+    #[tokio::test]
+    async fn given_task_created_before_restart_repeated_request_picks_it_up() {
+        let stand_in = MeesignStandIn::new()
+            .with_group("challenge", GroupKeyType::SignChallenge)
+            .with_held_tasks()
+            .start()
+            .await;
+        let directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&directory).unwrap();
+        let endpoint =
+            stand_in
+                .get_endpoint("unused".into())
+                .with_timeouts(Some(5), Some(5), Some(1));
+        let certificate = Certificate::from_pem(stand_in.get_ca_certificate());
+        let reuse_window = Duration::from_secs(60);
+        let cancellation_token = CancellationToken::new();
+
+        let mut meesign = Meesign::new(&endpoint, certificate.clone(), &directory)
+            .await
+            .unwrap();
+        let group_id = meesign.get_groups().await.unwrap()[0]
+            .get_group_id()
+            .clone();
+        let repo = SqliteCryptokiRepo::new(directory.clone()).unwrap();
+        repo.create_tables().unwrap();
+        let timed_out = send_or_resume_request(
+            &mut meesign,
+            &repo,
+            reuse_window,
+            group_id.clone(),
+            RequestKind::Authentication,
+            CHALLENGE.to_vec(),
+            RequestContext::default(),
+            &cancellation_token,
+        )
+        .await;
+        assert!(matches!(timed_out, Err(CryptokiError::FunctionFailed)));
+        drop((meesign, repo));
+        stand_in.release_tasks();
+
+        let mut meesign = Meesign::new(&endpoint, certificate, &directory)
+            .await
+            .unwrap();
+        let repo = SqliteCryptokiRepo::new(directory.clone()).unwrap();
+        let response = send_or_resume_request(
+            &mut meesign,
+            &repo,
+            reuse_window,
+            group_id.clone(),
+            RequestKind::Authentication,
+            CHALLENGE.to_vec(),
+            RequestContext::default(),
+            &cancellation_token,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(stand_in.get_task_count(), 1);
+        assert!(verify_signature(&group_id, &CHALLENGE.to_vec(), &response));
+        let request_hash = get_request_hash(
+            &meesign.get_server_address(),
+            &get_requester(RequestKind::Authentication),
+            &group_id,
+            RequestKind::Authentication,
+            &CHALLENGE.to_vec(),
+        );
+        assert!(repo.get_pending_task(&request_hash, 0).unwrap().is_none());
+    }
+
+    /// Fails like a locked database
+    struct UnavailablePendingTaskRepo;
+
+    impl PendingTaskRepo for UnavailablePendingTaskRepo {
+        fn store_pending_task(&self, _: &PendingTaskModel) -> Result<(), PersistenceError> {
+            Err(PersistenceError::SynchronizationElementPoisoned)
+        }
+
+        fn get_pending_task(
+            &self,
+            _: &[u8],
+            _: i64,
+        ) -> Result<Option<PendingTaskModel>, PersistenceError> {
+            Err(PersistenceError::SynchronizationElementPoisoned)
+        }
+
+        fn remove_pending_task(&self, _: &[u8]) -> Result<(), PersistenceError> {
+            Err(PersistenceError::SynchronizationElementPoisoned)
+        }
+
+        fn remove_expired_pending_tasks(&self, _: i64) -> Result<(), PersistenceError> {
+            Err(PersistenceError::SynchronizationElementPoisoned)
+        }
+    }
+
+    #[tokio::test]
+    async fn given_unavailable_pending_task_repo_request_is_still_resolved() {
+        let stand_in = MeesignStandIn::new()
+            .with_group("challenge", GroupKeyType::SignChallenge)
+            .start()
+            .await;
+        let directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let endpoint = stand_in.get_endpoint("unused".into());
+        let certificate = Certificate::from_pem(stand_in.get_ca_certificate());
+        let mut meesign = Meesign::new(&endpoint, certificate, &directory)
+            .await
+            .unwrap();
+        let group_id = meesign.get_groups().await.unwrap()[0]
+            .get_group_id()
+            .clone();
+
+        let response = send_or_resume_request(
+            &mut meesign,
+            &UnavailablePendingTaskRepo,
+            Duration::from_secs(60),
+            group_id.clone(),
+            RequestKind::Authentication,
+            CHALLENGE.to_vec(),
+            RequestContext::default(),
+            &CancellationToken::new(),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert!(verify_signature(&group_id, &CHALLENGE.to_vec(), &response));
+    }
+}
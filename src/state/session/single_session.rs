@@ -2,34 +2,41 @@ use std::{collections::HashMap, iter::Chain, sync::Arc, vec::IntoIter};
 
 use aes::Aes128;
 use openssl::hash::Hasher;
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 use crate::{
-    communicator::{AuthResponse, GroupId},
-    cryptoki::bindings::{
-        CKA_ALWAYS_AUTHENTICATE, CKA_CLASS, CKA_EC_PARAMS, CKA_EC_POINT, CKA_ID, CKA_KEY_TYPE,
-        CKA_LABEL, CKA_VALUE, CKK_ECDSA, CKO_PRIVATE_KEY, CKO_PUBLIC_KEY, CK_FALSE,
-        CK_OBJECT_HANDLE,
+    communicator::{
+        group::{Group, GroupKeyType},
+        AuthResponse, CommunicatorId,
+    },
+    cryptoki::{
+        bindings::{
+            CKA_ALWAYS_AUTHENTICATE, CKA_CLASS, CKA_DECRYPT, CKA_DERIVE, CKA_EC_PARAMS,
+            CKA_EC_POINT, CKA_ENCRYPT, CKA_ID, CKA_KEY_TYPE, CKA_LABEL, CKA_SIGN, CKA_VALUE,
+            CKA_VERIFY, CKO_PRIVATE_KEY, CKO_PUBLIC_KEY, CK_FALSE, CK_MECHANISM_TYPE,
+            CK_OBJECT_HANDLE, CK_ULONG,
+        },
+        vendor_defined::{
+            CKA_MEESIGN_DEVICE_CERTIFICATES, CKA_MEESIGN_DEVICE_IDS, CKA_MEESIGN_DEVICE_NAMES,
+            CKA_MEESIGN_KEY_TYPE, CKA_MEESIGN_PARTICIPANT_COUNT, CKA_MEESIGN_THRESHOLD,
+        },
     },
     cryptoki_error::CryptokiError,
     persistence::{persistence_error::PersistenceError, CryptokiRepo},
     state::{
         object::{
-            attribute::Attribute,
-            cryptoki_object::{AttributeValue, CryptokiObject},
-            object_search::ObjectSearch,
-            private_key_object::PrivateKeyObject,
-            public_key_object::PublicKeyObject,
+            attribute::Attribute, cryptoki_object::CryptokiObject, object_search::ObjectSearch,
+            private_key_object::PrivateKeyObject, public_key_object::PublicKeyObject,
             template::Template,
         },
         slots::TokenStore,
     },
-    utils::as_der_octet_string,
+    utils::{as_der_octet_string, encode_length_prefixed},
 };
 
 use super::handle_resolver::HandleResolver;
 
-const NIST_P256_EC_PARAMS_DER_HEX: &str = "06082a8648ce3d030107";
 static KEYPAIR_IDENTIFIER_FROM_PUBLIC_PREFIX_LENGTH: usize = 8;
 
 type ObjectSearchIterator = Chain<IntoIter<CK_OBJECT_HANDLE>, IntoIter<CK_OBJECT_HANDLE>>;
@@ -48,59 +55,109 @@ pub(crate) struct Session {
     // TODO: RwLock
     handle_resolver: HandleResolver,
 
-    // TODO: utilize token attribute
-    #[allow(dead_code)]
     token: TokenStore,
 
     encryptor: Option<Aes128>,
 
     signer: Option<Signer>,
 
+    /// Decrypts using a MeeSign decryption group, `None` for local decryption
+    decryptor: Option<Decryptor>,
+
     key_pair: Option<(CK_OBJECT_HANDLE, CK_OBJECT_HANDLE)>,
 
     cryptoki_repo: Arc<dyn CryptokiRepo>,
     ephemeral_objects: HashMap<Uuid, Arc<dyn CryptokiObject>>,
+
+    /// Cancels the operation currently waiting for the communicator, if there is any
+    cancellation_token: CancellationToken,
 }
 
 #[derive(Clone)]
 pub(crate) struct Signer {
     pub key: Arc<dyn CryptokiObject>,
+    pub key_type: GroupKeyType,
+    pub mechanism: CK_MECHANISM_TYPE,
     pub response: Option<AuthResponse>,
     pub auth_request_originator: Option<String>,
+    /// The communicator serving the key's group, which differs from the session's one
+    /// if the request is routed to a group of another communicator
+    pub communicator_id: CommunicatorId,
 }
 impl Signer {
     pub(crate) fn new(
         key: Arc<dyn CryptokiObject>,
+        key_type: GroupKeyType,
+        mechanism: CK_MECHANISM_TYPE,
         auth_request_originator: Option<String>,
+        communicator_id: CommunicatorId,
     ) -> Self {
         Self {
             key,
+            key_type,
+            mechanism,
             response: None,
             auth_request_originator,
+            communicator_id,
+        }
+    }
+}
+
+/// Decrypts data using a MeeSign decryption group
+#[derive(Clone)]
+pub(crate) struct Decryptor {
+    pub key: Arc<dyn CryptokiObject>,
+    /// The plaintext, kept until the caller provides a large enough buffer
+    pub response: Option<AuthResponse>,
+    pub request_originator: Option<String>,
+}
+impl Decryptor {
+    pub(crate) fn new(key: Arc<dyn CryptokiObject>, request_originator: Option<String>) -> Self {
+        Self {
+            key,
+            response: None,
+            request_originator,
         }
     }
 }
 impl Session {
     pub(crate) fn new(token: TokenStore, cryptoki_repo: Arc<dyn CryptokiRepo>) -> Self {
-        // TODO: refactor
-        let pubkey: GroupId = token.read().unwrap().get_public_key().into();
-        let token_label: String = token.read().unwrap().get_label().into();
+        let group = token.read().unwrap().get_group().clone();
         let mut session = Self {
             hasher: None,
             object_search: None,
             token,
             encryptor: None,
             signer: None,
+            decryptor: None,
             object_search_iterator: None,
             key_pair: None,
             cryptoki_repo,
             handle_resolver: HandleResolver::new(),
             ephemeral_objects: HashMap::new(),
+            cancellation_token: CancellationToken::new(),
         };
 
-        session.key_pair = Some(session.create_communicator_keypair(pubkey, token_label));
+        session.key_pair = Some(session.create_communicator_keypair(&group));
         session
     }
+    /// Returns the communicator that owns the session token's group
+    pub fn get_communicator_id(&self) -> CommunicatorId {
+        self.token.read().unwrap().get_communicator_id()
+    }
+
+    /// Returns a fresh token for a new cancellable operation,
+    /// so that a cancellation of a previous operation doesn't affect it
+    pub fn start_cancellable_operation(&mut self) -> CancellationToken {
+        self.cancellation_token = CancellationToken::new();
+        self.cancellation_token.clone()
+    }
+
+    /// Cancels the pending operation, a no-op if there is none
+    pub fn cancel_operation(&self) {
+        self.cancellation_token.cancel();
+    }
+
     pub fn get_keypair(&self) -> (CK_OBJECT_HANDLE, CK_OBJECT_HANDLE) {
         self.key_pair.unwrap()
     }
@@ -226,16 +283,33 @@ impl Session {
         signer.response = Some(response);
     }
 
+    pub fn set_decryptor(&mut self, decryptor: Option<Decryptor>) {
+        self.decryptor = decryptor
+    }
+
+    pub fn get_decryptor(&self) -> Option<Decryptor> {
+        self.decryptor.clone()
+    }
+
+    pub fn store_decryption_response(&mut self, response: AuthResponse) {
+        let Some(ref mut decryptor) = self.decryptor else {
+            return;
+        };
+
+        decryptor.response = Some(response);
+    }
+
+    /// Creates the key pair objects of the group, returns the handles
+    /// of the private and the public key
     pub fn create_communicator_keypair(
         &mut self,
-        pubkey: GroupId,
-        token_label: String,
+        group: &Group,
     ) -> (CK_OBJECT_HANDLE, CK_OBJECT_HANDLE) {
-        let pubkey_template = get_communicator_public_key_template(&token_label, pubkey.clone());
+        let pubkey_template = get_communicator_public_key_template(group);
         let pubkey_object = PublicKeyObject::from_template(pubkey_template);
         let pubkey_handle = self.create_ephemeral_object(Arc::new(pubkey_object));
 
-        let private_key_template = get_communicator_private_key_template(&token_label, pubkey);
+        let private_key_template = get_communicator_private_key_template(group);
         let private_key = PrivateKeyObject::from_template(private_key_template);
         let private_key_handle = self.create_ephemeral_object(Arc::new(private_key));
 
@@ -243,47 +317,147 @@ impl Session {
     }
 }
 
-fn get_communicator_common_key_attributes(
-    token_label: &str,
-    public_key: Vec<u8>,
-) -> Vec<Attribute> {
+/// Creates the private key object of the group without storing it in a session
+pub(crate) fn create_group_private_key(group: &Group) -> Arc<dyn CryptokiObject> {
+    let private_key_template = get_communicator_private_key_template(group);
+    Arc::new(PrivateKeyObject::from_template(private_key_template))
+}
+
+fn get_communicator_common_key_attributes(group: &Group) -> Vec<Attribute> {
+    let public_key = group.get_group_id().clone();
     let key_identifier: Vec<u8> = public_key
         .iter()
         .cloned()
         .take(KEYPAIR_IDENTIFIER_FROM_PUBLIC_PREFIX_LENGTH)
         .collect();
-    vec![
-        Attribute::from_parts(CKA_LABEL, token_label),
+    let curve = group.get_curve();
+    let mut attributes = vec![
+        Attribute::from_parts(CKA_LABEL, group.get_name()),
         Attribute::from_parts(CKA_VALUE, public_key),
         Attribute::from_parts(CKA_ID, key_identifier),
+        Attribute::from_parts(CKA_KEY_TYPE, curve.get_key_type().to_le_bytes().to_vec()),
+        Attribute::from_parts(CKA_EC_PARAMS, curve.get_ec_params()),
+        Attribute::new(
+            CKA_MEESIGN_KEY_TYPE,
+            Some(group.get_key_type().to_attribute_value()),
+        ),
+    ];
+    attributes.append(&mut get_group_metadata_attributes(group));
+    attributes
+}
+
+/// Describes the quorum protecting the group's key, so that it can be audited
+/// without the MeeSign app
+fn get_group_metadata_attributes(group: &Group) -> Vec<Attribute> {
+    let devices = group.get_devices();
+    let device_ids = devices
+        .iter()
+        .map(|device| hex::encode(device.get_device_id()))
+        .collect::<Vec<_>>()
+        .join(",");
+    let device_names = encode_length_prefixed(
+        devices
+            .iter()
+            .map(|device| device.get_name().unwrap_or_default().as_bytes()),
+    );
+    let device_certificates = encode_length_prefixed(
+        devices
+            .iter()
+            .map(|device| device.get_certificate().unwrap_or_default()),
+    );
+    vec![
+        Attribute::new(
+            CKA_MEESIGN_THRESHOLD,
+            Some((group.get_threshold() as CK_ULONG).to_le_bytes().to_vec()),
+        ),
+        Attribute::new(
+            CKA_MEESIGN_PARTICIPANT_COUNT,
+            Some((devices.len() as CK_ULONG).to_le_bytes().to_vec()),
+        ),
+        Attribute::new(CKA_MEESIGN_DEVICE_IDS, Some(device_ids.into_bytes())),
+        Attribute::new(CKA_MEESIGN_DEVICE_NAMES, Some(device_names)),
+        Attribute::new(CKA_MEESIGN_DEVICE_CERTIFICATES, Some(device_certificates)),
     ]
 }
 
-fn get_communicator_public_key_template(token_label: &str, public_key: AttributeValue) -> Template {
-    let mut common_attributes =
-        get_communicator_common_key_attributes(token_label, public_key.clone());
-    let ec_params = hex::decode(NIST_P256_EC_PARAMS_DER_HEX).unwrap();
+fn get_communicator_public_key_template(group: &Group) -> Template {
+    let mut common_attributes = get_communicator_common_key_attributes(group);
+    let is_decryption_key = group.get_key_type() == GroupKeyType::Decrypt;
     let mut attributes = vec![
-        Attribute::from_parts(CKA_KEY_TYPE, CKK_ECDSA),
-        Attribute::from_parts(CKA_EC_PARAMS, ec_params),
-        Attribute::from_parts(CKA_EC_POINT, as_der_octet_string(&public_key)),
+        Attribute::from_parts(CKA_EC_POINT, as_der_octet_string(group.get_group_id())),
         Attribute::from_parts(CKA_CLASS, CKO_PUBLIC_KEY),
+        Attribute::from_parts(CKA_VERIFY, !is_decryption_key),
+        Attribute::from_parts(CKA_ENCRYPT, is_decryption_key),
     ];
     attributes.append(&mut common_attributes);
 
     Template::from_vec(attributes)
 }
 
-fn get_communicator_private_key_template(
-    token_label: &str,
-    public_key: AttributeValue,
-) -> Template {
-    let mut common_attributes = get_communicator_common_key_attributes(token_label, public_key);
+fn get_communicator_private_key_template(group: &Group) -> Template {
+    let mut common_attributes = get_communicator_common_key_attributes(group);
+    let is_decryption_key = group.get_key_type() == GroupKeyType::Decrypt;
     let mut attributes = vec![
         Attribute::from_parts(CKA_ALWAYS_AUTHENTICATE, CK_FALSE),
         Attribute::from_parts(CKA_CLASS, CKO_PRIVATE_KEY),
+        Attribute::from_parts(CKA_SIGN, !is_decryption_key),
+        Attribute::from_parts(CKA_DECRYPT, is_decryption_key),
+        Attribute::from_parts(CKA_DERIVE, is_decryption_key),
     ];
     attributes.append(&mut common_attributes);
 
     Template::from_vec(attributes)
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        communicator::group::{GroupCurve, GroupDevice},
+        utils::decode_length_prefixed,
+    };
+
+    use super::*;
+
+    #[test]
+    fn given_group_devices_metadata_attributes_keep_names_and_certificates_apart() {
+        let devices = vec![
+            GroupDevice::new(
+                vec![0x0a],
+                Some("Alice's\nphone".into()),
+                Some(vec![0x30, 0x00]),
+            ),
+            GroupDevice::new(vec![0x0b], None, None),
+            GroupDevice::new(
+                vec![0x0c],
+                Some("laptop".into()),
+                Some(vec![0x30, 0x01, 0x0a]),
+            ),
+        ];
+        let group = Group::new(
+            vec![0xab; 32],
+            "ssh".into(),
+            GroupKeyType::SignChallenge,
+            GroupCurve::P256,
+        )
+        .with_members(2, devices);
+
+        let attributes = get_group_metadata_attributes(&group);
+        let get_value = |attribute_type| {
+            attributes
+                .iter()
+                .find(|attribute| attribute.get_attribute_type() == attribute_type)
+                .and_then(|attribute| attribute.get_attribute_value())
+                .unwrap()
+        };
+
+        assert_eq!(get_value(CKA_MEESIGN_DEVICE_IDS), b"0a,0b,0c");
+        assert_eq!(
+            decode_length_prefixed(get_value(CKA_MEESIGN_DEVICE_NAMES)).unwrap(),
+            vec![b"Alice's\nphone".to_vec(), vec![], b"laptop".to_vec()]
+        );
+        assert_eq!(
+            decode_length_prefixed(get_value(CKA_MEESIGN_DEVICE_CERTIFICATES)).unwrap(),
+            vec![vec![0x30, 0x00], vec![], vec![0x30, 0x01, 0x0a]]
+        );
+    }
+}
@@ -3,7 +3,10 @@ use std::{
     sync::{Arc, RwLock},
 };
 
-use crate::cryptoki::bindings::{CK_SLOT_ID, CK_SLOT_INFO, CK_TOKEN_INFO};
+use crate::{
+    communicator::{CommunicatorId, GroupId},
+    cryptoki::bindings::{CK_SLOT_ID, CK_SLOT_INFO, CK_TOKEN_INFO},
+};
 
 use super::token::Token;
 
@@ -45,4 +48,65 @@ impl Slots {
     pub(crate) fn get_token(&self, slot_id: &CK_SLOT_ID) -> Option<TokenStore> {
         self.tokens.get(slot_id).cloned()
     }
+
+    /// Returns a token of the group, preferring the one owned by the communicator,
+    /// as the routed group may be served by another communicator than the session's
+    ///
+    /// # Arguments
+    ///
+    /// * `communicator_id` - the preferred communicator
+    /// * `group_id` - the ID of the group
+    pub(crate) fn find_group_token(
+        &self,
+        communicator_id: CommunicatorId,
+        group_id: &GroupId,
+    ) -> Option<TokenStore> {
+        let is_group_token =
+            |token: &&TokenStore| token.read().unwrap().get_group().get_group_id() == group_id;
+        let mut group_tokens = self.tokens.values().filter(is_group_token);
+        let preferred_token = group_tokens
+            .clone()
+            .find(|token| token.read().unwrap().get_communicator_id() == communicator_id);
+        preferred_token.or_else(|| group_tokens.next()).cloned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        communicator::{
+            group::{Group, GroupCurve, GroupKeyType},
+            server_info::ServerVersion,
+        },
+        state::token::MeesignToken,
+    };
+
+    use super::*;
+
+    fn insert_group_token(slots: &mut Slots, group_id: GroupId, communicator_id: CommunicatorId) {
+        let group = Group::new(
+            group_id,
+            "group".into(),
+            GroupKeyType::SignChallenge,
+            GroupCurve::P256,
+        );
+        let token = MeesignToken::new(group, communicator_id, ServerVersion::new(0, 5, 0));
+        slots.insert_token(Arc::new(RwLock::new(token)));
+    }
+
+    #[test]
+    fn given_group_of_another_communicator_its_token_is_found() {
+        let mut slots = Slots::new();
+        insert_group_token(&mut slots, vec![1; 33], 0);
+        insert_group_token(&mut slots, vec![2; 33], 1);
+        insert_group_token(&mut slots, vec![2; 33], 2);
+
+        let token = slots.find_group_token(0, &vec![2; 33]).unwrap();
+        assert_ne!(token.read().unwrap().get_communicator_id(), 0);
+
+        let token = slots.find_group_token(2, &vec![2; 33]).unwrap();
+        assert_eq!(token.read().unwrap().get_communicator_id(), 2);
+
+        assert!(slots.find_group_token(0, &vec![3; 33]).is_none());
+    }
 }
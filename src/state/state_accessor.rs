@@ -1,33 +1,61 @@
 use crate::{
     communicator::{
-        group::Group, meesign::Meesign, AuthResponse, Communicator, GroupId, RequestData, TaskId,
+        group::{Group, GroupParameters},
+        meesign::Meesign,
+        request_context::RequestContext,
+        request_kind::RequestKind,
+        server_info::ServerVersion,
+        task_name_provider::TaskNameProvider,
+        task_name_template::TaskNameTemplate,
+        AuthResponse, Communicator, CommunicatorId, CommunicatorStore, GroupId, RequestData,
+        TaskId,
     },
     configuration::{
-        ConfigurationProvider, ConfigurationProviderError, ControllerConfiguration,
-        EnvConfiguration,
+        announce_configuration_source, route_originator, select_configuration_provider,
+        CommunicatorEndpoint, ConfigurationProviderError, GroupRoute, GroupRouting,
+        InterfaceConfiguration, LibraryParameters,
     },
     cryptoki::bindings::{
         CK_OBJECT_HANDLE, CK_SESSION_HANDLE, CK_SLOT_ID, CK_SLOT_INFO, CK_TOKEN_INFO,
     },
     cryptoki_error::CryptokiError,
-    persistence::SqliteCryptokiRepo,
-    COMMUNICATOR, CONFIGURATION, RUNTIME, SESSIONS, SLOTS,
+    diagnostics::{self, Diagnostics},
+    persistence::{PendingTaskRepo, SqliteCryptokiRepo},
+    process_identity::ProcessIdentity,
+    COMMUNICATORS, CONFIGURATION, DIAGNOSTICS, RUNTIME, SESSIONS, SLOTS,
 };
 use aes::Aes128;
 use home::home_dir;
 use openssl::hash::Hasher;
-use std::{fs, path::PathBuf, sync::Arc};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
+};
 use tokio::runtime::Runtime;
+use tokio_util::sync::CancellationToken;
 use tonic::transport::Certificate;
 
-use super::session::sessions::Sessions;
+use super::lifecycle::{LifecycleState, LIFECYCLE};
+use super::session::{
+    group_task::create_group, sessions::Sessions, signing_task::send_or_resume_request,
+};
 use super::slots::{Slots, TokenStore};
+use super::threading_model::{
+    ensure_outside_of_runtime, resolve_on_calling_thread, ThreadingModel,
+};
+use super::token::MeesignToken;
 
 use super::{
     object::{cryptoki_object::CryptokiObject, object_search::ObjectSearch},
-    session::single_session::Signer,
+    session::single_session::{create_group_private_key, Decryptor, Signer},
 };
 
+/// How long the finalization waits for the tasks still running on the runtime
+static RUNTIME_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
 pub(crate) struct StateAccessor {}
 
 impl StateAccessor {
@@ -84,14 +112,17 @@ impl StateAccessor {
             .ok_or(CryptokiError::ObjectHandleInvalid)
     }
 
+    /// Tears down the state of the library, so that it can be initialized again
     pub(crate) fn finalize(&self) -> Result<(), CryptokiError> {
-        let mut sessions = SESSIONS.write()?;
-        sessions
-            .as_mut()
-            .ok_or(CryptokiError::CryptokiNotInitialized)?
-            .close_sessions();
-        Ok(())
+        let _transition = LIFECYCLE.begin_transition()?;
+        if LIFECYCLE.get_state() != LifecycleState::Initialized {
+            return Err(CryptokiError::CryptokiNotInitialized);
+        }
+        // calls racing with the finalization fail instead of using the state being torn down
+        LIFECYCLE.mark_uninitialized();
+        tear_down_state()
     }
+
     pub(crate) fn get_token_info(
         &self,
         slot_id: &CK_SLOT_ID,
@@ -118,31 +149,78 @@ impl StateAccessor {
         Ok(slot_info)
     }
 
-    pub(crate) fn initialize_state(&self) -> Result<(), CryptokiError> {
-        ensure_file_structure()?;
+    /// Sets up the state of the library. A second initialization is refused
+    /// as such before the library parameters are looked at.
+    ///
+    /// # Arguments
+    ///
+    /// * `parameters` - the library parameters passed by the application, if any,
+    ///   or the reason they couldn't be parsed
+    /// * `threading_model` - how the library may use threads
+    pub(crate) fn initialize_state(
+        &self,
+        parameters: Result<Option<LibraryParameters>, ConfigurationProviderError>,
+        threading_model: ThreadingModel,
+    ) -> Result<(), CryptokiError> {
+        let _transition = LIFECYCLE.begin_transition()?;
+        match LIFECYCLE.get_state() {
+            LifecycleState::Initialized => return Err(CryptokiError::CryptokiAlreadyInitialized),
+            LifecycleState::InheritedFromParent => abandon_inherited_state(),
+            LifecycleState::Uninitialized => {}
+        }
+        let parameters = parameters.map_err(|err| {
+            eprintln!("{err}");
+            CryptokiError::ArgumentsBad
+        })?;
+        if let Err(err) = self.set_up_state(parameters, threading_model) {
+            tear_down_state()?;
+            return Err(err);
+        }
+        LIFECYCLE.mark_initialized();
+        Ok(())
+    }
 
-        let env_configuration = EnvConfiguration::new().map_err(|err| {
-            eprintln!("Env configuration is not done properly. Please, consult the project documentation.");
+    fn set_up_state(
+        &self,
+        parameters: Option<LibraryParameters>,
+        threading_model: ThreadingModel,
+    ) -> Result<(), CryptokiError> {
+        ensure_outside_of_runtime()?;
+        let cryptoki_directory = parameters
+            .as_ref()
+            .and_then(LibraryParameters::get_data_directory)
+            .map_or_else(get_cryptoki_path, |data_directory| {
+                Ok(data_directory.clone())
+            })?;
+        ensure_file_structure(&cryptoki_directory)?;
+
+        let configuration_provider =
+            select_configuration_provider(&cryptoki_directory, parameters, threading_model)?;
+        announce_configuration_source(&configuration_provider.get_source());
+        let configuration = configuration_provider.get_interface_configuration().map_err(|err|{
+            eprintln!("Couldn't get interface configuration. Either launch bridge controller, or provide appropriate ENV varriables.");
+            eprintln!("In case bridge controller is running, make sure the interface is configured.");
             err
         })?;
 
-        let configuration: Arc<dyn ConfigurationProvider> = match env_configuration {
-            Some(env_configuration) => Arc::new(env_configuration),
-            None => Arc::new(ControllerConfiguration::new()),
-        };
-
-        let runtime = Runtime::new().unwrap();
+        let runtime = threading_model.build_runtime()?;
 
-        let cryptoki_repo = Arc::new(SqliteCryptokiRepo::new(get_cryptoki_path())?);
-        cryptoki_repo
-            .create_tables()
-            .expect("Couldn't crate tables");
-        let communicator = self.get_communicator(&configuration, &runtime)?;
-        let _ = SESSIONS.write()?.insert(Sessions::new(cryptoki_repo));
-        let _ = SLOTS.write()?.insert(Slots::new());
-        let _ = CONFIGURATION.write()?.insert(configuration);
-        let _ = RUNTIME.write()?.insert(runtime);
-        let _ = COMMUNICATOR.lock()?.insert(communicator);
+        let cryptoki_repo = Arc::new(SqliteCryptokiRepo::new(cryptoki_directory.clone())?);
+        cryptoki_repo.create_tables().map_err(|err| {
+            eprintln!("Couldn't create tables: {err}");
+            err
+        })?;
+        let communicators = self.get_communicators(
+            &configuration,
+            &runtime,
+            &cryptoki_directory,
+            threading_model,
+        )?;
+        SESSIONS.set(Sessions::new(cryptoki_repo.clone(), cryptoki_repo))?;
+        SLOTS.set(Slots::new())?;
+        CONFIGURATION.set(configuration)?;
+        RUNTIME.set(Arc::new(runtime))?;
+        COMMUNICATORS.set(communicators)?;
 
         Ok(())
     }
@@ -175,78 +253,343 @@ impl StateAccessor {
         session.get_hasher().ok_or(CryptokiError::FunctionFailed)
     }
 
-    pub(crate) fn get_groups_blocking(&self) -> Result<Vec<Group>, CryptokiError> {
+    fn get_communicator_store(
+        &self,
+        communicator_id: CommunicatorId,
+    ) -> Result<CommunicatorStore, CryptokiError> {
+        let communicators = COMMUNICATORS.read()?;
+        let communicator = communicators
+            .as_ref()
+            .ok_or(CryptokiError::CryptokiNotInitialized)?
+            .get(communicator_id)
+            .ok_or(CryptokiError::FunctionFailed)?;
+        Ok(communicator.clone())
+    }
+
+    /// Returns a communicator sharing the connection of the stored one.
+    /// The store is locked only while cloning, so that a call waiting for a task
+    /// doesn't block the other calls using the communicator.
+    ///
+    /// # Arguments
+    ///
+    /// * `communicator_id` - the communicator to be returned
+    fn get_communicator(
+        &self,
+        communicator_id: CommunicatorId,
+    ) -> Result<Box<dyn Communicator>, CryptokiError> {
+        let communicator = self.get_communicator_store(communicator_id)?;
+        let communicator = communicator.lock()?;
+        Ok(communicator.clone_box())
+    }
+
+    /// Returns the runtime without holding the lock of the global state,
+    /// so that the library can be finalized while a call waits on the runtime
+    fn get_runtime(&self) -> Result<Arc<Runtime>, CryptokiError> {
         let runtime = RUNTIME.read()?;
         let runtime = runtime
             .as_ref()
             .ok_or(CryptokiError::CryptokiNotInitialized)?;
-        let mut communicator = COMMUNICATOR.lock()?;
-        let communicator = communicator
-            .as_mut()
-            .ok_or(CryptokiError::CryptokiNotInitialized)?;
-        let groups = runtime.block_on(async move { communicator.get_groups().await })?;
+        Ok(runtime.clone())
+    }
+
+    /// Returns groups of all communicators, together with the communicator owning each group.
+    /// An unreachable communicator is skipped, unless all of them are unreachable.
+    pub(crate) fn get_groups_blocking(
+        &self,
+    ) -> Result<Vec<(CommunicatorId, Group)>, CryptokiError> {
+        let runtime = self.get_runtime()?;
+        let communicator_count = COMMUNICATORS
+            .read()?
+            .as_ref()
+            .ok_or(CryptokiError::CryptokiNotInitialized)?
+            .len();
+
+        let mut groups = vec![];
+        let mut errors = vec![];
+        for communicator_id in 0..communicator_count {
+            let mut communicator = self.get_communicator(communicator_id)?;
+            match runtime.block_on(communicator.get_groups()) {
+                Ok(communicator_groups) => groups.extend(
+                    communicator_groups
+                        .into_iter()
+                        .map(|group| (communicator_id, group)),
+                ),
+                Err(err) => {
+                    eprintln!("Couldn't get groups from communicator {communicator_id}: {err}");
+                    errors.push(err);
+                }
+            }
+        }
+        // the returned error is reported once it is returned to the application,
+        // the errors of the skipped communicators are reported here
+        let returned_error = groups.is_empty().then(|| errors.pop()).flatten();
+        for err in &errors {
+            diagnostics::report(err);
+        }
+        if let Some(err) = returned_error {
+            return Err(err.into());
+        }
         self.filter_groups_based_on_configuration(groups)
     }
 
     fn filter_groups_based_on_configuration(
         &self,
-        groups: Vec<Group>,
-    ) -> Result<Vec<Group>, CryptokiError> {
+        groups: Vec<(CommunicatorId, Group)>,
+    ) -> Result<Vec<(CommunicatorId, Group)>, CryptokiError> {
         let configuration = CONFIGURATION.read()?;
-        let configuration = match configuration
+        let configuration = configuration
             .as_ref()
-            .ok_or(CryptokiError::CryptokiNotInitialized)?
-            .get_interface_configuration()
-        {
-            Ok(conf) => conf,
-            Err(ConfigurationProviderError::ReqwestError(_)) => {
-                // TODO:
-                // a temporary hot fix before we properly refactor the solution
-                // and allow for mocked communicator without the need to mock the configuration
-                return Ok(groups);
-            }
-            Err(err) => return Err(err.into()),
-        };
+            .ok_or(CryptokiError::CryptokiNotInitialized)?;
 
         if let Some(configured_group_id) = configuration.get_group_id() {
-            let selected_group = groups
+            if !groups
+                .iter()
+                .any(|(_, group)| group.get_group_id() == configured_group_id)
+            {
+                eprintln!("The specified group is not present!");
+                return Err(CryptokiError::FunctionFailed);
+            }
+            // routed groups stay available, so that requests can be routed to them
+            let routed_group_ids: Vec<&GroupId> = configuration
+                .get_group_routes()
                 .iter()
-                .find(|group| group.get_group_id() == configured_group_id)
-                .ok_or_else(|| {
-                    eprintln!("The specified group is not present!");
-                    CryptokiError::FunctionFailed
-                })?;
-            return Ok(vec![selected_group.clone()]);
+                .map(GroupRoute::get_group_id)
+                .collect();
+            return Ok(groups
+                .into_iter()
+                .filter(|(_, group)| {
+                    group.get_group_id() == configured_group_id
+                        || routed_group_ids.contains(&group.get_group_id())
+                })
+                .collect());
         }
 
         Ok(groups)
     }
 
-    pub(crate) fn send_signing_request_wait_for_response(
+    /// Returns the key signing requests of the originator, as decided by the configured
+    /// group routes, together with the communicator the requests are sent to.
+    /// Without routes, the key chosen by the caller is returned.
+    /// The key of a routed group is looked up among the tokens of all communicators,
+    /// the session's communicator is preferred if it serves the group too.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_handle` - the session the signature is made in
+    /// * `signing_key` - the key chosen by the caller
+    /// * `originator` - the originator of the request, if known
+    pub(crate) fn route_signing_key(
         &self,
-        group_id: GroupId,
-        data: RequestData,
-        request_originator: Option<String>,
-    ) -> Result<TaskId, CryptokiError> {
-        let runtime = RUNTIME.read()?;
-        let runtime = runtime
+        session_handle: &CK_SESSION_HANDLE,
+        signing_key: Arc<dyn CryptokiObject>,
+        originator: Option<&str>,
+    ) -> Result<(Arc<dyn CryptokiObject>, CommunicatorId), CryptokiError> {
+        let communicator_id = self.get_communicator_id(session_handle)?;
+        let configuration = CONFIGURATION.read()?;
+        let configuration = configuration
             .as_ref()
             .ok_or(CryptokiError::CryptokiNotInitialized)?;
-        let mut communicator = COMMUNICATOR.lock()?;
-        let communicator = communicator
+
+        let group_id = match route_originator(configuration.get_group_routes(), originator) {
+            GroupRouting::Unrouted => return Ok((signing_key, communicator_id)),
+            GroupRouting::Refused => {
+                eprintln!(
+                    "No group is routed for the request originator {}",
+                    originator.unwrap_or("(unknown)")
+                );
+                return Err(CryptokiError::OriginatorNotRouted);
+            }
+            GroupRouting::Routed(group_id) => group_id,
+        };
+        if signing_key.get_value().as_ref() == Some(group_id) {
+            return Ok((signing_key, communicator_id));
+        }
+
+        let token = SLOTS
+            .read()?
+            .as_ref()
+            .ok_or(CryptokiError::CryptokiNotInitialized)?
+            .find_group_token(communicator_id, group_id)
+            .ok_or_else(|| {
+                eprintln!("The routed group is not present!");
+                CryptokiError::FunctionFailed
+            })?;
+        let token = token.read()?;
+        Ok((
+            create_group_private_key(token.get_group()),
+            token.get_communicator_id(),
+        ))
+    }
+
+    /// Returns the version the communicator reported on connect
+    ///
+    /// # Arguments
+    ///
+    /// * `communicator_id` - the communicator whose version is returned
+    pub(crate) fn get_server_version(
+        &self,
+        communicator_id: CommunicatorId,
+    ) -> Result<ServerVersion, CryptokiError> {
+        let communicator = self.get_communicator_store(communicator_id)?;
+        let communicator = communicator.lock()?;
+        Ok(communicator.get_server_info().get_version())
+    }
+
+    pub(crate) fn get_communicator_id(
+        &self,
+        session_handle: &CK_SESSION_HANDLE,
+    ) -> Result<CommunicatorId, CryptokiError> {
+        let sessions = SESSIONS.read()?;
+        let session = sessions
+            .as_ref()
+            .ok_or(CryptokiError::CryptokiNotInitialized)?
+            .get_session(session_handle)
+            .ok_or(CryptokiError::SessionHandleInvalid)?;
+        Ok(session.get_communicator_id())
+    }
+
+    /// Starts a cancellable operation in the session
+    ///
+    /// # Arguments
+    ///
+    /// * `session_handle` - the session the operation runs in
+    pub(crate) fn start_cancellable_operation(
+        &self,
+        session_handle: &CK_SESSION_HANDLE,
+    ) -> Result<CancellationToken, CryptokiError> {
+        let mut sessions = SESSIONS.write()?;
+        let session = sessions
             .as_mut()
-            .ok_or(CryptokiError::CryptokiNotInitialized)?;
-        let response = runtime.block_on(async move {
-            println!("Waiting for authentication response...");
-            let task_id = communicator
-                .send_auth_request(group_id, data, request_originator)
-                .await?;
-            communicator.get_auth_response(task_id).await
-        })?;
+            .ok_or(CryptokiError::CryptokiNotInitialized)?
+            .get_session_mut(session_handle)
+            .ok_or(CryptokiError::SessionHandleInvalid)?;
+        Ok(session.start_cancellable_operation())
+    }
+
+    /// Cancels the operation pending in the session
+    ///
+    /// # Arguments
+    ///
+    /// * `session_handle` - the session whose operation is canceled
+    pub(crate) fn cancel_operation(
+        &self,
+        session_handle: &CK_SESSION_HANDLE,
+    ) -> Result<(), CryptokiError> {
+        let sessions = SESSIONS.read()?;
+        let session = sessions
+            .as_ref()
+            .ok_or(CryptokiError::CryptokiNotInitialized)?
+            .get_session(session_handle)
+            .ok_or(CryptokiError::SessionHandleInvalid)?;
+        session.cancel_operation();
+        Ok(())
+    }
+
+    /// Sends the request to the communicator and waits for the response.
+    /// A task created for the same request within the reuse window is picked up instead.
+    /// Once canceled, the wait is interrupted and the communicator is told about it.
+    ///
+    /// # Arguments
+    ///
+    /// * `communicator_id` - the communicator serving the group, usually the session's one
+    /// * `group_id` - the id of the group that will handle the request
+    /// * `request_kind` - the kind of the task requested from the group
+    /// * `data` - the data of the request, e.g., the data to be signed
+    /// * `request_context` - describes the request to the approvers
+    /// * `cancellation_token` - cancels the request
+    pub(crate) fn send_request_wait_for_response(
+        &self,
+        communicator_id: CommunicatorId,
+        group_id: GroupId,
+        request_kind: RequestKind,
+        data: RequestData,
+        request_context: RequestContext,
+        cancellation_token: CancellationToken,
+    ) -> Result<TaskId, CryptokiError> {
+        let pending_task_repo = self.get_pending_task_repo()?;
+        let reuse_window = self.get_pending_task_reuse_window()?;
+        let runtime = self.get_runtime()?;
+        let mut communicator = self.get_communicator(communicator_id)?;
+        match request_kind {
+            RequestKind::Authentication | RequestKind::DocumentSigning => {
+                println!("Waiting for authentication response...")
+            }
+            _ => println!("Waiting for the approval of the {request_kind}..."),
+        }
+        let response = runtime.block_on(send_or_resume_request(
+            communicator.as_mut(),
+            pending_task_repo.as_ref(),
+            reuse_window,
+            group_id,
+            request_kind,
+            data,
+            request_context,
+            &cancellation_token,
+        ))?;
 
         response.ok_or(CryptokiError::FunctionFailed)
     }
 
+    /// Asks the communicator owning the session's group to create a group
+    /// using distributed key generation and waits until the key is generated.
+    /// The new group gets a slot of its own and its key pair objects are created
+    /// in the session, the handles of the private and the public key are returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_handle` - the session the key pair is generated in
+    /// * `parameters` - describes the group to be created
+    /// * `cancellation_token` - cancels the key generation
+    pub(crate) fn create_group_wait_for_key_pair(
+        &self,
+        session_handle: &CK_SESSION_HANDLE,
+        parameters: GroupParameters,
+        cancellation_token: CancellationToken,
+    ) -> Result<(CK_OBJECT_HANDLE, CK_OBJECT_HANDLE), CryptokiError> {
+        let communicator_id = self.get_communicator_id(session_handle)?;
+        let group = {
+            let runtime = self.get_runtime()?;
+            let mut communicator = self.get_communicator(communicator_id)?;
+            println!("Waiting for the group members to generate the key...");
+            runtime.block_on(create_group(
+                communicator.as_mut(),
+                parameters,
+                &cancellation_token,
+            ))?
+        };
+
+        let server_version = self.get_server_version(communicator_id)?;
+        let token = MeesignToken::new(group.clone(), communicator_id, server_version);
+        self.insert_token(Arc::new(RwLock::new(token)))?;
+
+        let mut sessions = SESSIONS.write()?;
+        let session = sessions
+            .as_mut()
+            .ok_or(CryptokiError::CryptokiNotInitialized)?
+            .get_session_mut(session_handle)
+            .ok_or(CryptokiError::SessionHandleInvalid)?;
+        Ok(session.create_communicator_keypair(&group))
+    }
+
+    fn get_pending_task_repo(&self) -> Result<Arc<dyn PendingTaskRepo>, CryptokiError> {
+        let sessions = SESSIONS.read()?;
+        let pending_task_repo = sessions
+            .as_ref()
+            .ok_or(CryptokiError::CryptokiNotInitialized)?
+            .get_pending_task_repo();
+        Ok(pending_task_repo)
+    }
+
+    /// Returns the reuse window of the configuration loaded when the library was initialized
+    fn get_pending_task_reuse_window(&self) -> Result<Duration, CryptokiError> {
+        let configuration = CONFIGURATION.read()?;
+        let reuse_window = configuration
+            .as_ref()
+            .ok_or(CryptokiError::CryptokiNotInitialized)?
+            .get_pending_task_reuse_window();
+        Ok(reuse_window)
+    }
+
     pub(crate) fn store_signing_response(
         &self,
         session_handle: &CK_SESSION_HANDLE,
@@ -429,50 +772,204 @@ impl StateAccessor {
         Ok(signer)
     }
 
+    /// Sets the decryptor of the session, `None` for local decryption
+    ///
+    /// # Arguments
+    ///
+    /// * `session_handle` - the session the decryption runs in
+    /// * `decryptor` - the decryptor using a MeeSign decryption group
+    pub(crate) fn set_decryptor(
+        &self,
+        session_handle: &CK_SESSION_HANDLE,
+        decryptor: Option<Decryptor>,
+    ) -> Result<(), CryptokiError> {
+        let mut sessions = SESSIONS.write()?;
+        let session = sessions
+            .as_mut()
+            .ok_or(CryptokiError::CryptokiNotInitialized)?
+            .get_session_mut(session_handle)
+            .ok_or(CryptokiError::SessionHandleInvalid)?;
+        session.set_decryptor(decryptor);
+        Ok(())
+    }
+
+    /// Returns the decryptor of the session, `None` if the decryption is local
+    pub(crate) fn get_decryptor(
+        &self,
+        session_handle: &CK_SESSION_HANDLE,
+    ) -> Result<Option<Decryptor>, CryptokiError> {
+        let sessions = SESSIONS.read()?;
+        let session = sessions
+            .as_ref()
+            .ok_or(CryptokiError::CryptokiNotInitialized)?
+            .get_session(session_handle)
+            .ok_or(CryptokiError::SessionHandleInvalid)?;
+        Ok(session.get_decryptor())
+    }
+
+    pub(crate) fn store_decryption_response(
+        &self,
+        session_handle: &CK_SESSION_HANDLE,
+        response: AuthResponse,
+    ) -> Result<(), CryptokiError> {
+        let mut sessions = SESSIONS.write()?;
+        let session = sessions
+            .as_mut()
+            .ok_or(CryptokiError::CryptokiNotInitialized)?
+            .get_session_mut(session_handle)
+            .ok_or(CryptokiError::SessionHandleInvalid)?;
+
+        session.store_decryption_response(response);
+        Ok(())
+    }
+
+    /// Connects to all configured communicators. An unreachable communicator is skipped,
+    /// unless all of them are unreachable. If enabled, diagnostics are forwarded
+    /// to the first reachable one.
     #[cfg(not(feature = "mocked_communicator"))]
-    fn get_communicator(
+    fn get_communicators(
         &self,
-        configuration: &Arc<dyn ConfigurationProvider>,
+        configuration: &InterfaceConfiguration,
         runtime: &Runtime,
-    ) -> Result<Box<dyn Communicator>, CryptokiError> {
-        let configuration = configuration.get_interface_configuration().map_err(|err|{
-            eprintln!("Couldn't get interface configuration. Either launch bridge controller, or provide appropriate ENV varriables.");
-            eprintln!("In case bridge controller is running, make sure the interface is configured.");
-            err
-        })?;
-        let hostname = configuration.get_communicator_hostname().into();
-        let certificate_path = configuration.get_communicator_certificate_path();
-        let certificate = std::fs::read(certificate_path)?;
-        let cert = Certificate::from_pem(certificate);
-
-        let meesign = runtime.block_on(async move { Meesign::new(hostname, 1337, cert).await })?;
-        Ok(Box::new(meesign))
+        cryptoki_directory: &Path,
+        threading_model: ThreadingModel,
+    ) -> Result<Vec<CommunicatorStore>, CryptokiError> {
+        let task_name_template = configuration
+            .get_task_name_template()
+            .map(TaskNameTemplate::from_str)
+            .transpose()?;
+        let task_name_provider = TaskNameProvider::new()
+            .with_template(task_name_template)
+            .with_format(configuration.get_task_name_format());
+        let mut communicators = vec![];
+        let mut log_sink = None;
+        let mut last_error = None;
+        for endpoint in configuration.get_communicator_endpoints() {
+            let meesign = get_connectable_endpoint(endpoint, threading_model).and_then(
+                |connectable_endpoint| {
+                    let certificate = std::fs::read(endpoint.get_certificate_path())?;
+                    let cert = Certificate::from_pem(certificate);
+                    let meesign = runtime.block_on(Meesign::new(
+                        &connectable_endpoint,
+                        cert,
+                        cryptoki_directory,
+                    ))?;
+                    Ok(meesign.with_task_name_provider(task_name_provider.clone()))
+                },
+            );
+            match meesign {
+                Ok(meesign) => {
+                    if log_sink.is_none() {
+                        log_sink = Some(meesign.get_log_sink());
+                    }
+                    let communicator: Box<dyn Communicator> = Box::new(meesign);
+                    communicators.push(Arc::new(Mutex::new(communicator)));
+                }
+                Err(err) => {
+                    eprintln!(
+                        "Couldn't connect to communicator {}: {err}",
+                        endpoint.get_hostname()
+                    );
+                    last_error = Some(err);
+                }
+            }
+        }
+        if let (true, Some(log_sink)) = (configuration.forwards_diagnostics(), log_sink) {
+            let diagnostics = Diagnostics::start(Box::new(log_sink), runtime);
+            DIAGNOSTICS.set(diagnostics)?;
+        }
+        match (communicators.is_empty(), last_error) {
+            (true, Some(err)) => Err(err),
+            _ => Ok(communicators),
+        }
     }
 
     #[cfg(feature = "mocked_communicator")]
-    fn get_communicator(
+    fn get_communicators(
         &self,
-        _configuration: &Arc<dyn ConfigurationProvider>,
+        _configuration: &InterfaceConfiguration,
         _runtime: &Runtime,
-    ) -> Result<Box<dyn Communicator>, CryptokiError> {
+        _cryptoki_directory: &Path,
+        _threading_model: ThreadingModel,
+    ) -> Result<Vec<CommunicatorStore>, CryptokiError> {
         use crate::communicator::mocked_communicator::MockedMeesign;
-        let meesign = MockedMeesign::new("testgrp".into());
-        Ok(Box::new(meesign))
+        let meesign: Box<dyn Communicator> = Box::new(MockedMeesign::new("testgrp".into()));
+        Ok(vec![Arc::new(Mutex::new(meesign))])
     }
 }
 
-fn ensure_file_structure() -> Result<(), CryptokiError> {
-    let cryptoki_directory_path = get_cryptoki_path();
-    fs::create_dir_all(cryptoki_directory_path).unwrap();
-
+/// Closes the sessions, forwards the remaining diagnostics and drops the global state.
+/// The runtime is shut down last, as the other parts of the state may use it.
+/// A call still waiting on the runtime keeps it alive, the runtime is then dropped
+/// once the call returns.
+fn tear_down_state() -> Result<(), CryptokiError> {
+    if let Some(mut sessions) = SESSIONS.take()? {
+        sessions.close_sessions();
+    }
+    let diagnostics = DIAGNOSTICS.take()?;
+    let communicators = COMMUNICATORS.take()?;
+    let runtime = RUNTIME.take()?;
+    if let (Some(diagnostics), Some(runtime)) = (diagnostics, runtime.as_ref()) {
+        runtime.block_on(diagnostics.shutdown());
+    }
+    drop(communicators);
+    SLOTS.take()?;
+    CONFIGURATION.take()?;
+    if let Some(Ok(runtime)) = runtime.map(Arc::try_unwrap) {
+        runtime.shutdown_timeout(RUNTIME_SHUTDOWN_TIMEOUT);
+    }
     Ok(())
 }
 
-fn get_cryptoki_path() -> PathBuf {
+/// Forgets the state the forked process inherited from its parent
+fn abandon_inherited_state() {
+    SESSIONS.abandon();
+    DIAGNOSTICS.abandon();
+    COMMUNICATORS.abandon();
+    SLOTS.abandon();
+    CONFIGURATION.abandon();
+    RUNTIME.abandon();
+    ProcessIdentity::refresh();
+    LIFECYCLE.mark_uninitialized();
+}
+
+/// Returns the endpoint to connect to. If the runtime can't resolve hostnames
+/// without spawning threads, the hostname is resolved on the calling thread.
+///
+/// # Arguments
+///
+/// * `endpoint` - the configured endpoint
+/// * `threading_model` - how the library may use threads
+#[cfg(not(feature = "mocked_communicator"))]
+fn get_connectable_endpoint(
+    endpoint: &CommunicatorEndpoint,
+    threading_model: ThreadingModel,
+) -> Result<CommunicatorEndpoint, CryptokiError> {
+    if !threading_model.requires_own_resolution() {
+        return Ok(endpoint.clone());
+    }
+    let address = resolve_on_calling_thread(endpoint.get_hostname(), endpoint.get_port())?;
+    Ok(endpoint.clone().with_resolved_address(Some(address)))
+}
+
+fn ensure_file_structure(cryptoki_directory: &Path) -> Result<(), CryptokiError> {
+    fs::create_dir_all(cryptoki_directory).map_err(|err| {
+        eprintln!(
+            "Couldn't create the directory {}: {err}",
+            cryptoki_directory.display()
+        );
+        CryptokiError::FunctionFailed
+    })
+}
+
+fn get_cryptoki_path() -> Result<PathBuf, CryptokiError> {
     let Some(home_directory) = home_dir() else {
-        todo!();
+        eprintln!(
+            "Couldn't find the home directory, pass data_directory in the library parameters"
+        );
+        return Err(CryptokiError::FunctionFailed);
     };
 
     static CRYPTOKI_DIRECTORY_NAME: &str = ".cryptoki-bridge";
-    home_directory.join(CRYPTOKI_DIRECTORY_NAME)
+    Ok(home_directory.join(CRYPTOKI_DIRECTORY_NAME))
 }
@@ -0,0 +1,98 @@
+use std::{
+    io,
+    net::{SocketAddr, ToSocketAddrs},
+};
+
+use tokio::runtime::{Builder, Handle, Runtime};
+
+/// How the library may use OS threads, as allowed by the application in `C_Initialize`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum ThreadingModel {
+    /// The library may create threads of its own
+    #[default]
+    MultiThreaded,
+
+    /// The application set `CKF_LIBRARY_CANT_CREATE_OS_THREADS`, all the work is done
+    /// on the threads calling the library. Background tasks, e.g., diagnostics forwarding,
+    /// only progress while a call waits for the communicator.
+    CallingThreadOnly,
+}
+
+impl ThreadingModel {
+    /// Creates the runtime the communicators run on
+    pub(crate) fn build_runtime(&self) -> io::Result<Runtime> {
+        match self {
+            Self::MultiThreaded => Runtime::new(),
+            Self::CallingThreadOnly => Builder::new_current_thread().enable_all().build(),
+        }
+    }
+
+    /// Returns whether hostnames have to be resolved by the library before connecting.
+    /// The resolver of the runtime runs on a thread pool of its own.
+    pub(crate) fn requires_own_resolution(&self) -> bool {
+        *self == Self::CallingThreadOnly
+    }
+}
+
+/// Fails if the calling thread already runs an asynchronous runtime, e.g., the application
+/// calls the library from a tokio task. Blocking on a runtime of the library would panic there.
+pub(crate) fn ensure_outside_of_runtime() -> io::Result<()> {
+    if Handle::try_current().is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the library can't block within an asynchronous runtime",
+        ));
+    }
+    Ok(())
+}
+
+/// Resolves the hostname on the calling thread, returns the first address found
+///
+/// # Arguments
+///
+/// * `hostname` - the hostname or an IP address
+/// * `port` - the port of the resolved address
+pub(crate) fn resolve_on_calling_thread(hostname: &str, port: u16) -> io::Result<SocketAddr> {
+    (hostname, port).to_socket_addrs()?.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{hostname} has no address"),
+        )
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn given_calling_thread_only_model_the_runtime_spawns_no_workers() {
+        let runtime = ThreadingModel::CallingThreadOnly.build_runtime().unwrap();
+
+        let calling_thread = std::thread::current().id();
+        let task_thread = runtime.block_on(async {
+            tokio::spawn(async { std::thread::current().id() })
+                .await
+                .unwrap()
+        });
+
+        assert_eq!(task_thread, calling_thread);
+    }
+
+    #[test]
+    fn given_asynchronous_context_blocking_is_refused() {
+        assert!(ensure_outside_of_runtime().is_ok());
+
+        let runtime = ThreadingModel::CallingThreadOnly.build_runtime().unwrap();
+        let result = runtime.block_on(async { ensure_outside_of_runtime() });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn given_ip_address_it_is_resolved_to_itself() {
+        let address = resolve_on_calling_thread("127.0.0.1", 1337).unwrap();
+
+        assert_eq!(address, SocketAddr::from(([127, 0, 0, 1], 1337)));
+    }
+}
@@ -1,7 +1,11 @@
 use std::iter::repeat;
 
 use crate::{
-    communicator::{group::Group, GroupId},
+    communicator::{
+        group::{Group, GroupKeyType},
+        server_info::ServerVersion,
+        CommunicatorId,
+    },
     cryptoki::bindings::{
         CKF_TOKEN_INITIALIZED, CKF_TOKEN_PRESENT, CK_CHAR, CK_FLAGS, CK_SLOT_INFO, CK_TOKEN_INFO,
         CK_VERSION,
@@ -9,24 +13,31 @@ use crate::{
 };
 
 static LABEL_PREFIX: &str = "Meesign: ";
+static DOCUMENT_SIGNING_LABEL_PREFIX: &str = "Meesign PDF: ";
+static DECRYPTION_LABEL_PREFIX: &str = "Meesign decrypt: ";
 const LABEL_BUFFER_LENGTH: usize = 32;
 const DESCRIPTION_BUFFER_LENGTH: usize = 64;
+const MODEL_BUFFER_LENGTH: usize = 16;
+const SERIAL_NUMBER_BUFFER_LENGTH: usize = 16;
 
 pub(crate) trait Token: Sync + Send {
     fn get_token_info(&self) -> CK_TOKEN_INFO;
 
-    fn get_public_key(&self) -> &[u8];
+    /// Returns the group whose key the token holds
+    fn get_group(&self) -> &Group;
 
-    fn get_label(&self) -> &str;
+    /// Returns the communicator that owns the token's group
+    fn get_communicator_id(&self) -> CommunicatorId;
 
     fn get_slot_info(&self) -> CK_SLOT_INFO;
 }
 
-// TODO: store other info, like group name?
-#[derive(Default)]
 pub(crate) struct MeesignToken {
-    group_id: GroupId,
-    name: String,
+    group: Group,
+    communicator_id: CommunicatorId,
+
+    /// Version of the communicator, reported as the token's firmware version
+    server_version: ServerVersion,
 }
 
 impl Token for MeesignToken {
@@ -35,8 +46,8 @@ impl Token for MeesignToken {
         CK_TOKEN_INFO {
             label: self.create_token_label(),
             manufacturerID: Default::default(),
-            model: Default::default(),
-            serialNumber: Default::default(),
+            model: self.create_model(),
+            serialNumber: self.create_serial_number(),
             flags: self.get_flags(),
             ulMaxSessionCount: 2,
             ulSessionCount: 1, // TODO
@@ -49,7 +60,11 @@ impl Token for MeesignToken {
             ulTotalPrivateMemory: 1 << 20,
             ulFreePrivateMemory: 1 << 20,
             hardwareVersion: CK_VERSION { major: 0, minor: 0 },
-            firmwareVersion: CK_VERSION { major: 0, minor: 0 },
+            // the components of larger versions don't fit, they are saturated
+            firmwareVersion: CK_VERSION {
+                major: u8::try_from(self.server_version.get_major()).unwrap_or(u8::MAX),
+                minor: u8::try_from(self.server_version.get_minor()).unwrap_or(u8::MAX),
+            },
             utcTime: Self::get_utc_time(),
         }
     }
@@ -65,16 +80,28 @@ impl Token for MeesignToken {
         }
     }
 
-    fn get_public_key(&self) -> &[u8] {
-        &self.group_id
+    fn get_group(&self) -> &Group {
+        &self.group
     }
 
-    fn get_label(&self) -> &str {
-        &self.name
+    fn get_communicator_id(&self) -> CommunicatorId {
+        self.communicator_id
     }
 }
 
 impl MeesignToken {
+    pub(crate) fn new(
+        group: Group,
+        communicator_id: CommunicatorId,
+        server_version: ServerVersion,
+    ) -> Self {
+        Self {
+            group,
+            communicator_id,
+            server_version,
+        }
+    }
+
     fn create_token_label(&self) -> [u8; LABEL_BUFFER_LENGTH] {
         let token_label = self.create_token_name(LABEL_BUFFER_LENGTH);
         match token_label.try_into() {
@@ -92,14 +119,42 @@ impl MeesignToken {
     }
 
     fn create_token_name(&self, length: usize) -> Vec<u8> {
-        let label: Vec<u8> = (String::from(LABEL_PREFIX) + &self.name)
-            .chars()
-            .map(|character: char| character as u8)
-            .chain(repeat(b' '))
-            .take(length)
-            .collect();
+        let label_prefix = match self.group.get_key_type() {
+            GroupKeyType::SignChallenge => LABEL_PREFIX,
+            GroupKeyType::SignPdf => DOCUMENT_SIGNING_LABEL_PREFIX,
+            GroupKeyType::Decrypt => DECRYPTION_LABEL_PREFIX,
+        };
+        pad_field(
+            &(String::from(label_prefix) + self.group.get_name()),
+            length,
+        )
+    }
 
-        label
+    /// Describes the quorum protecting the key, e.g., `FROST 2-of-3`
+    fn create_model(&self) -> [u8; MODEL_BUFFER_LENGTH] {
+        let protocol = self.group.get_protocol().get_name();
+        let model = match self.group.get_devices().len() {
+            0 => protocol.to_string(),
+            participants => format!(
+                "{protocol} {}-of-{participants}",
+                self.group.get_threshold()
+            ),
+        };
+        match pad_field(&model, MODEL_BUFFER_LENGTH).try_into() {
+            Ok(val) => val,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Identifies the group by the same public key prefix as the key objects' `CKA_ID`
+    fn create_serial_number(&self) -> [u8; SERIAL_NUMBER_BUFFER_LENGTH] {
+        let group_id = self.group.get_group_id();
+        let serial_number =
+            hex::encode(&group_id[..group_id.len().min(SERIAL_NUMBER_BUFFER_LENGTH / 2)]);
+        match pad_field(&serial_number, SERIAL_NUMBER_BUFFER_LENGTH).try_into() {
+            Ok(val) => val,
+            Err(_) => unreachable!(),
+        }
     }
 
     // TODO
@@ -112,11 +167,43 @@ impl MeesignToken {
     }
 }
 
-impl From<Group> for MeesignToken {
-    fn from(value: Group) -> Self {
-        Self {
-            name: value.get_name().into(),
-            group_id: value.get_group_id().to_owned(),
-        }
+/// Pads the text with spaces to the length of a token info field, truncating longer texts
+///
+/// # Arguments
+///
+/// * `text` - the text of the field
+/// * `length` - the length of the field
+fn pad_field(text: &str, length: usize) -> Vec<u8> {
+    text.chars()
+        .map(|character: char| character as u8)
+        .chain(repeat(b' '))
+        .take(length)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::communicator::group::{GroupCurve, GroupDevice};
+
+    use super::*;
+
+    #[test]
+    fn given_group_members_token_info_describes_the_quorum() {
+        let devices = (1..=3)
+            .map(|device_id| GroupDevice::new(vec![device_id], None, None))
+            .collect();
+        let group = Group::new(
+            vec![0xab; 32],
+            "ssh".into(),
+            GroupKeyType::SignChallenge,
+            GroupCurve::Ed25519,
+        )
+        .with_members(2, devices);
+        let token = MeesignToken::new(group, 0, ServerVersion::new(0, 5, 0));
+
+        let token_info = token.get_token_info();
+
+        assert_eq!(&token_info.model, b"FROST 2-of-3    ");
+        assert_eq!(&token_info.serialNumber, b"abababababababab");
     }
 }
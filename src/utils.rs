@@ -1,3 +1,9 @@
+use openssl::{
+    bn::BigNumContext,
+    ec::{EcGroup, EcPoint, PointConversionForm},
+    nid::Nid,
+};
+
 const DER_OCTET_STRING_TYPE: u8 = 0x04;
 
 pub(crate) fn as_der_octet_string(public_key: &[u8]) -> Vec<u8> {
@@ -10,6 +16,70 @@ pub(crate) fn as_der_octet_string(public_key: &[u8]) -> Vec<u8> {
     octet_string
 }
 
+/// Parses a P-256 public key passed as a SEC1 point, either raw or wrapped
+/// in a DER octet string. Returns the uncompressed point, or `None`
+/// if the data don't encode a point on the curve.
+///
+/// # Arguments
+///
+/// * `public_key` - the encoded public key
+pub(crate) fn parse_p256_point(public_key: &[u8]) -> Option<Vec<u8>> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).ok()?;
+    let mut context = BigNumContext::new().ok()?;
+    let point = EcPoint::from_bytes(&group, public_key, &mut context)
+        .ok()
+        .or_else(|| {
+            if public_key.len() < 2 {
+                return None;
+            }
+            let (header, point) = public_key.split_at(2);
+            if header != [DER_OCTET_STRING_TYPE, point.len() as u8] {
+                return None;
+            }
+            EcPoint::from_bytes(&group, point, &mut context).ok()
+        })?;
+    point
+        .to_bytes(&group, PointConversionForm::UNCOMPRESSED, &mut context)
+        .ok()
+}
+
+/// Encodes a list of byte strings, each prefixed by its length
+/// as a 4-byte big-endian integer, so that the values may hold any bytes
+///
+/// # Arguments
+///
+/// * `values` - the values to be encoded, in order
+pub(crate) fn encode_length_prefixed<'a>(values: impl IntoIterator<Item = &'a [u8]>) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    for value in values {
+        encoded.extend((value.len() as u32).to_be_bytes());
+        encoded.extend(value);
+    }
+    encoded
+}
+
+/// Decodes a list encoded by [`encode_length_prefixed`], returns `None`
+/// if the data are truncated
+///
+/// # Arguments
+///
+/// * `encoded` - the encoded list
+#[cfg(test)]
+pub(crate) fn decode_length_prefixed(mut encoded: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut values = Vec::new();
+    while !encoded.is_empty() {
+        let (length, rest) = encoded.split_first_chunk::<4>()?;
+        let length = u32::from_be_bytes(*length) as usize;
+        if rest.len() < length {
+            return None;
+        }
+        let (value, rest) = rest.split_at(length);
+        values.push(value.to_vec());
+        encoded = rest;
+    }
+    Some(values)
+}
+
 // TODO: don't panic
 pub(crate) fn to_fixed_size_array<T, const N: usize>(v: Vec<T>) -> [T; N] {
     v.try_into().unwrap_or_else(|vector: Vec<T>| {
@@ -34,4 +104,40 @@ mod test {
         assert_eq!(octet_string[0], 0x04);
         assert_eq!(octet_string[1], PUBKEY_LENGTH as u8);
     }
+
+    #[test]
+    fn given_p256_point_in_any_encoding_it_is_parsed_as_uncompressed() {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = openssl::ec::EcKey::generate(&group).unwrap();
+        let mut context = BigNumContext::new().unwrap();
+        let mut encode = |form| {
+            key.public_key()
+                .to_bytes(&group, form, &mut context)
+                .unwrap()
+        };
+        let uncompressed = encode(PointConversionForm::UNCOMPRESSED);
+        let compressed = encode(PointConversionForm::COMPRESSED);
+
+        assert_eq!(parse_p256_point(&uncompressed), Some(uncompressed.clone()));
+        assert_eq!(parse_p256_point(&compressed), Some(uncompressed.clone()));
+        assert_eq!(
+            parse_p256_point(&as_der_octet_string(&uncompressed)),
+            Some(uncompressed.clone())
+        );
+
+        let mut off_curve = uncompressed;
+        off_curve[64] ^= 1;
+        assert_eq!(parse_p256_point(&off_curve), None);
+    }
+
+    #[test]
+    fn given_length_prefixed_values_they_are_decoded_unchanged() {
+        let values: Vec<&[u8]> = vec![b"first\nline", b"", b"\x00,\xff"];
+
+        let encoded = encode_length_prefixed(values.clone());
+
+        assert_eq!(&encoded[..4], &[0, 0, 0, 10]);
+        assert_eq!(decode_length_prefixed(&encoded).unwrap(), values);
+        assert!(decode_length_prefixed(&encoded[..encoded.len() - 1]).is_none());
+    }
 }